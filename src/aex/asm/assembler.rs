@@ -0,0 +1,1110 @@
+// ColdFire Assembler
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+use num::ToPrimitive;
+
+use aex::ast::Node;
+use aex::message::Messages;
+use aex::source::Source;
+use aex::target::cf::{branch_len, Encoder, Flags, Mnemonic, Relaxer, Size, OPCODES};
+use aex::util::{ByteOrder, BE};
+
+use super::{branch_disp, encode_insn, encode_insn_fields, eval, is_branch, note_sites};
+use super::{parse_expansion, resolve_directive, resolve_name, resolve_size};
+use super::{Arg, DirectiveOp, EvalError, Expansion, Field, Insn, Line, Location, LowerError};
+use super::{is_local_label, LocalLabels, NameCond, Operand, SrcExpr, SrcId, Stmt, SymbolKind, SymbolTable, Value};
+
+/// Maximum number of layout passes before the assembler gives up.
+pub const MAX_PASSES: usize = 32;
+
+/// ColdFire assembler.
+///
+/// Assembles source in multiple passes.  The first pass defines symbols and
+/// assigns each line to a section.  Layout passes then assign addresses,
+/// growing branches as needed, until every symbol value and line length is
+/// stable.  The final pass generates the contents of each section, now that
+/// every symbol has its final value.
+///
+/// A symbol declared `.global` but not defined is external.  Each reference
+/// to an external symbol, or to a section from outside it, yields a
+/// relocation, for a linker to resolve.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Assembler {
+    /// ISA revisions and extensions to accept.
+    pub isa: Flags,
+
+    /// Address at which each section begins.
+    pub origin: u64,
+
+    /// Byte order of values generated by data directives.  Instructions
+    /// are always big-endian.
+    pub order: ByteOrder,
+}
+
+/// The result of assembly.
+#[derive(Clone, Debug)]
+pub struct Assembly<'a> {
+    /// Output sections.  The first is always `.text`.
+    pub sections: Vec<Section<'a>>,
+
+    /// Symbols defined by the source.
+    pub symbols: SymbolTable<'a>,
+
+    /// Placement of each line of the source, in the order of the lines of
+    /// its expansion.
+    pub lines: Vec<Placement>,
+
+    /// Diagnostic messages.
+    pub messages: Messages<'a>,
+}
+
+/// The placement of a line of source in the output.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Placement {
+    /// Number of the section containing the line.
+    pub section: usize,
+
+    /// Address of the line.
+    pub addr: u64,
+
+    /// Number of bytes generated by the line.
+    pub len: u64,
+
+    /// For a branch without a size suffix, the size chosen by relaxation.
+    pub branch: Option<Size>,
+}
+
+/// An output section.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Section<'a> {
+    /// Name of the section.
+    pub name: &'a str,
+
+    /// Kind of contents.
+    pub kind: SectionKind,
+
+    /// Address at which the section begins.
+    pub addr: u64,
+
+    /// Largest alignment requested within the section, in bytes.  At least
+    /// 2, the alignment of instructions.
+    pub align: u64,
+
+    /// Contents of the section.
+    pub data: Vec<u8>,
+
+    /// Relocations of the contents, in order of offset.
+    pub relocs: Vec<Reloc<'a>>,
+}
+
+/// A relocation: a field of section contents whose value depends on where a
+/// linker places a section or symbol.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Reloc<'a> {
+    /// Offset of the field from the start of the section.
+    pub offset: u64,
+
+    /// Size of the field in bytes: 1, 2, or 4.
+    pub size: u8,
+
+    /// Whether the field is a displacement from its own address.
+    pub pc: bool,
+
+    /// Section or symbol whose address the field holds.
+    pub target: RelocTarget<'a>,
+
+    /// Value added to the address of the target.
+    pub addend: i64,
+}
+
+/// The target of a relocation.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RelocTarget<'a> {
+    /// Start of an output section, by number
+    Section(usize),
+
+    /// External symbol, by name
+    Symbol(&'a str),
+}
+
+/// Kinds of output section contents.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SectionKind {
+    /// Executable code
+    Text,
+
+    /// Writable data
+    Data,
+
+    /// Read-only data
+    ReadOnly,
+
+    /// Writable data, initially zero, that occupies no space in the output
+    Bss,
+}
+
+impl SectionKind {
+    /// Returns the kind of a section with the given name and flags.  Without
+    /// flags, the kind follows from the name, like `.text` or `.rodata.x`.
+    /// Otherwise, `x` marks code and `w` marks writable data.
+    pub fn new(name: &str, flags: Option<&[u8]>) -> Self {
+        let is = |prefix: &str| name == prefix || name.starts_with(&format!("{}.", prefix));
+
+        match flags {
+            Some(f) if f.contains(&b'x') => SectionKind::Text,
+            _ if is(".bss")              => SectionKind::Bss,
+            Some(f) if f.contains(&b'w') => SectionKind::Data,
+            Some(_)                      => SectionKind::ReadOnly,
+            None if is(".text")          => SectionKind::Text,
+            None if is(".rodata")        => SectionKind::ReadOnly,
+            None                         => SectionKind::Data,
+        }
+    }
+}
+
+// A line of source, as laid out.
+struct Item<'l, 'a: 'l> {
+    line:    &'l Line<'a>,
+    index:   usize,
+    labels:  Vec<&'a str>,
+    locals:  Vec<(&'a str, &'a str)>,  // local labels, each with its scope
+    scope:   &'a str,
+    action:  Option<Action<'l, 'a>>,
+    defined: bool,                     // whether an equate defined its symbol
+    section: usize,
+    addr:    u64,
+    len:     u64,
+    branch:  Size,
+}
+
+// What a line of source does.
+enum Action<'l, 'a: 'l> {
+    Insn(Op<'l, 'a>),
+    Directive(DirectiveOp<'l, 'a>),
+}
+
+// An instruction with resolved name and size.
+struct Op<'l, 'a: 'l> {
+    insn: &'l Insn<'a>,
+    name: Mnemonic,
+    cond: Option<NameCond>,
+    size: Option<Size>,
+}
+
+/// Number of the `.text` section, into which source is assembled by default.
+const TEXT: usize = 0;
+
+impl Assembler {
+    /// Creates an assembler for the given ISA profile, with output beginning
+    /// at address 0 and big-endian data.
+    pub fn new(isa: Flags) -> Self {
+        Assembler { isa, origin: 0, order: BE }
+    }
+
+    /// Assembles the given source, after macro expansion.  Symbols defined
+    /// by the expander are absolute equates.
+    pub fn assemble<'a>(&self, source: &'a Expansion<'a>) -> Assembly<'a> {
+        let mut messages = source.messages.clone();
+        let mut symbols  = SymbolTable::new();
+        let mut locals   = LocalLabels::new();
+        let mut sections = vec![self.section(".text", None)];
+
+        // Symbols defined before the source are absolute equates
+        for (name, value) in &source.defines {
+            if symbols.define(name, Source::BuiltIn, SymbolKind::Equate).is_ok() {
+                symbols.set_value(name, Value::abs(value.clone()));
+            }
+        }
+
+        let lines     = parse_expansion(source, &mut messages);
+        let mut items = self.define(&lines, &mut sections, &mut symbols, &mut locals, &mut messages);
+
+        // Each external symbol is addressed as if it began a section of its
+        // own, numbered after the output sections, until assembly ends
+        let base    = sections.len();
+        let externs = symbols.iter()
+            .filter(|s| s.kind == SymbolKind::Undefined && s.global)
+            .map(|s| s.name)
+            .collect::<Vec<_>>();
+
+        for (i, name) in externs.iter().enumerate() {
+            symbols.set_value(name, Value::rel(0, base + i));
+        }
+
+        self.layout(&mut items, sections.len(), &mut symbols, &mut locals, &mut messages);
+        self.emit(&items, &mut sections, &mut symbols, &locals, &mut messages);
+
+        for r in sections.iter_mut().flat_map(|s| s.relocs.iter_mut()) {
+            match r.target {
+                RelocTarget::Section(n) if n >= base => r.target = RelocTarget::Symbol(externs[n - base]),
+                _                                    => (),
+            }
+        }
+
+        let relative = symbols.iter()
+            .filter(|s| s.value.as_ref().and_then(|v| v.section).is_some_and(|n| n >= base))
+            .map(|s| s.name)
+            .collect::<Vec<_>>();
+
+        for name in relative {
+            symbols.forget_value(name);
+        }
+
+        let lines = items.iter()
+            .map(|i| Placement {
+                section: i.section,
+                addr:    i.addr,
+                len:     i.len,
+                branch:  match i.action {
+                    Some(Action::Insn(ref op)) if op.size.is_none() && is_branch(op.name) => Some(i.branch),
+                    _                                                                      => None,
+                },
+            })
+            .collect();
+
+        Assembly { sections, symbols, lines, messages }
+    }
+
+    fn section<'a>(&self, name: &'a str, flags: Option<&[u8]>) -> Section<'a> {
+        Section {
+            name, kind: SectionKind::new(name, flags), addr: self.origin, align: 2,
+            data: vec![], relocs: vec![]
+        }
+    }
+
+    // Defines symbols and local labels, resolves instructions and
+    // directives, and assigns each line to a section.  Each non-local label
+    // begins a new scope for scoped local labels.
+    fn define<'l, 'a>(&self,
+                      lines:    &'l [Line<'a>],
+                      sections: &mut Vec<Section<'a>>,
+                      symbols:  &mut SymbolTable<'a>,
+                      locals:   &mut LocalLabels<'a>,
+                      messages: &mut Messages<'a>)
+                     -> Vec<Item<'l, 'a>> {
+        let mut section = TEXT;
+        let mut scope   = "";
+
+        lines.iter().enumerate().map(|(index, line)| {
+            let mut labels     = vec![];
+            let mut local_defs = vec![];
+            let errs           = messages.as_slice().len();
+
+            for label in &line.labels {
+                if is_local_label(label.name) {
+                    if locals.define(label.name, index, scope) {
+                        local_defs.push((label.name, scope));
+                    } else {
+                        messages.err_sym_redefined(label.ann, label.name);
+                    }
+                    continue;
+                }
+
+                scope = label.name;
+
+                match symbols.define(label.name, label.ann, SymbolKind::Label) {
+                    Ok(())   => labels.push(label.name),
+                    Err(sym) => messages.err_sym_redefined(label.ann, sym.name),
+                }
+            }
+
+            let mut defined = true;
+
+            let action = match line.stmt {
+                Some(Stmt::Insn(ref insn)) => {
+                    resolve(insn, messages).map(Action::Insn)
+                },
+                Some(Stmt::Directive(ref d)) => {
+                    resolve_directive(d, messages).map(Action::Directive)
+                },
+                None => None,
+            };
+
+            match action {
+                Some(Action::Directive(DirectiveOp::Section(name, flags))) => {
+                    section = match sections.iter().position(|s| s.name == name) {
+                        Some(n) => n,
+                        None    => { sections.push(self.section(name, flags)); sections.len() - 1 },
+                    };
+                },
+                Some(Action::Directive(DirectiveOp::Equate(id, _, kind))) => {
+                    if let Err(sym) = symbols.define(id.name, id.ann, kind) {
+                        messages.err_sym_redefined(id.ann, sym.name);
+                        defined = false;
+                    }
+                },
+                Some(Action::Directive(DirectiveOp::Global(ref ids, global))) => {
+                    for id in ids { symbols.set_global(id.name, id.ann, global); }
+                },
+                _ => (),
+            }
+
+            note_sites(messages, errs, line.sites);
+
+            // An unsized branch starts with its shortest form
+            let branch = match action {
+                Some(Action::Insn(Op { name: Mnemonic::Fbcc, .. })) => Size::Word,
+                _                                                   => Size::Byte,
+            };
+
+            Item {
+                line, index, labels, locals: local_defs, scope, action, defined, section,
+                addr: self.origin, len: 0, branch
+            }
+        }).collect()
+    }
+
+    // Assigns addresses until every label address and length is stable.
+    fn layout<'l, 'a>(&self,
+                      items:    &mut [Item<'l, 'a>],
+                      sections: usize,
+                      symbols:  &mut SymbolTable<'a>,
+                      locals:   &mut LocalLabels<'a>,
+                      messages: &mut Messages<'a>) {
+        let encoder = Encoder::new(OPCODES, self.isa);
+        let relaxer = Relaxer::new(self.isa);
+        let mut src = Source::BuiltIn;
+
+        for pass in 0..MAX_PASSES {
+            let mut addrs   = vec![self.origin; sections];
+            let mut changed = None;
+
+            for item in items.iter_mut() {
+                let addr    = addrs[item.section];
+                let section = item.section;
+                let index   = item.index;
+                let scope   = item.scope;
+                item.addr   = addr;
+
+                for &label in &item.labels {
+                    if symbols.set_value(label, Value::rel(addr, section)) {
+                        changed = changed.or(Some(item.line.src));
+                    }
+                }
+
+                for &(label, label_scope) in &item.locals {
+                    match locals.set_value(label, index, label_scope, Value::rel(addr, section)) {
+                        Some(true)        => changed = changed.or(Some(item.line.src)),
+                        Some(false)       => (),
+                        None if pass == 0 => messages.err_local_undefined(
+                            item.line.src, label, "on this line"
+                        ),
+                        None              => (),
+                    }
+                }
+
+                // Forward references yield the current address until defined
+                let mut lookup = |id: &SrcId<'a>| Some(
+                    value_of(symbols, locals, id.name, index, scope)
+                        .unwrap_or_else(|| Value::rel(addr, section))
+                );
+
+                let len = match item.action {
+                    Some(Action::Insn(ref op)) if is_branch(op.name) => {
+                        if op.size.is_none() {
+                            match branch_size(op, addr, section, &relaxer, &mut lookup) {
+                                Some(need) if need > item.branch => item.branch = need,
+                                _                                => (),
+                            }
+                        }
+                        branch_len(op.size.unwrap_or(item.branch))
+                    },
+                    Some(Action::Insn(ref op)) => {
+                        let at = Location { addr, section };
+                        encode_insn(&encoder, op.insn, op.name, op.cond, op.size, at, &mut lookup)
+                            .map(|bytes| bytes.len() as u64)
+                            .unwrap_or(item.len)
+                    },
+                    Some(Action::Directive(DirectiveOp::Equate(..))) if !item.defined => 0,
+                    Some(Action::Directive(DirectiveOp::Equate(id, e, kind))) => {
+                        // A .set symbol may change value within a pass
+                        if let Ok(v) = eval(e, &mut lookup) {
+                            if symbols.set_value(id.name, v) && kind == SymbolKind::Equate {
+                                changed = changed.or(Some(item.line.src));
+                            }
+                        }
+                        0
+                    },
+                    Some(Action::Directive(ref d)) => {
+                        d.len(addr, section, self.order, &mut lookup)
+                            .unwrap_or(item.len)
+                    },
+                    None => 0,
+                };
+
+                if len != item.len {
+                    item.len = len;
+                    changed  = changed.or(Some(item.line.src));
+                }
+
+                addrs[section] = addr.wrapping_add(len);
+            }
+
+            match changed {
+                Some(s) => src = s,
+                None    => return,
+            }
+        }
+
+        messages.err_no_convergence(src, MAX_PASSES);
+    }
+
+    // Generates the contents of each section.
+    fn emit<'l, 'a>(&self,
+                    items:    &[Item<'l, 'a>],
+                    sections: &mut [Section<'a>],
+                    symbols:  &mut SymbolTable<'a>,
+                    locals:   &LocalLabels<'a>,
+                    messages: &mut Messages<'a>) {
+        let encoder = Encoder::new(OPCODES, self.isa);
+
+        for item in items {
+            let errs = messages.as_slice().len();
+            self.emit_item(&encoder, item, sections, symbols, locals, messages);
+            note_sites(messages, errs, item.line.sites);
+        }
+    }
+
+    // Generates the contents of one line.
+    fn emit_item<'l, 'a>(&self,
+                         encoder:  &Encoder,
+                         item:     &Item<'l, 'a>,
+                         sections: &mut [Section<'a>],
+                         symbols:  &mut SymbolTable<'a>,
+                         locals:   &LocalLabels<'a>,
+                         messages: &mut Messages<'a>) {
+        let mut lookup = |id: &SrcId<'a>| {
+            value_of(symbols, locals, id.name, item.index, item.scope)
+        };
+
+        // Relocatable values, with the fields of the output that hold them
+        let mut values = vec![];
+        let mut align  = 0;
+
+        let bytes = match item.action {
+            Some(Action::Insn(ref op)) => {
+                // An unsized branch has the size chosen by layout
+                let size = match op.size {
+                    None if is_branch(op.name) => Some(item.branch),
+                    size                       => size,
+                };
+
+                let at = Location { addr: item.addr, section: item.section };
+                encode_insn_fields(encoder, op.insn, op.name, op.cond, size, at, &mut lookup)
+                    .and_then(|(bytes, fields)| {
+                        values = insn_values(&op.insn.operands, &fields, &mut lookup)?;
+                        Ok(bytes)
+                    })
+                    .map_err(|e| e.report(item.line.src, messages))
+            },
+            Some(Action::Directive(DirectiveOp::Equate(..))) if !item.defined => {
+                return;
+            },
+            Some(Action::Directive(DirectiveOp::Equate(id, e, _))) => {
+                match eval(e, &mut lookup) {
+                    Ok(v)  => { symbols.set_value(id.name, v); },
+                    Err(e) => e.report(messages),
+                }
+                return;
+            },
+            Some(Action::Directive(ref d)) => {
+                let bytes = d.data(item.addr, item.section, self.order, &mut lookup)
+                    .map_err(|e| e.report(messages));
+                match (&bytes, d) {
+                    (Ok(_), &DirectiveOp::Int(size, args)) => {
+                        values = data_values(size, args, &mut lookup);
+                    },
+                    (Ok(_), &DirectiveOp::Align(e, ..)) => {
+                        align = eval(e, &mut lookup).ok().and_then(|v| v.int.to_u64()).unwrap_or(0);
+                    },
+                    _ => (),
+                }
+                bytes
+            },
+            None => return,
+        };
+
+        let section = &mut sections[item.section];
+        section.align = section.align.max(align);
+
+        // Erroneous lines are filled with zeros to keep addresses
+        let bytes = match bytes {
+            Ok(ref b) if section.kind == SectionKind::Bss && b.iter().any(|&x| x != 0) => {
+                messages.err_data_in_bss(item.line.src, section.name);
+                values.clear();
+                vec![0; item.len as usize]
+            },
+            Ok(b)   => b,
+            Err(()) => vec![0; item.len as usize],
+        };
+
+        section.data.extend(bytes);
+
+        for (field, value) in values {
+            let addr = item.addr.wrapping_add(field.offset);
+            let pc   = field.pc.map(|pc| item.addr.wrapping_add(pc));
+            relocate(sections, item.section, addr, field.size, pc, &value);
+        }
+    }
+}
+
+// Returns the value of the given symbol or local label, as referenced from
+// the line with the given index and scope.
+fn value_of<'a>(symbols: &SymbolTable<'a>,
+                locals:  &LocalLabels<'a>,
+                name:    &'a str,
+                line:    usize,
+                scope:   &'a str)
+               -> Option<Value> {
+    locals.get(name, line, scope)
+        .or_else(|| symbols.get(name).and_then(|s| s.value.clone()))
+}
+
+// Returns the relocatable values of the given instruction operands, each
+// with the field of the encoding that holds it.  A relocatable value with
+// no field is an error.
+fn insn_values<'a, F>(operands: &[Operand<'a>], fields: &[Field], lookup: &mut F)
+                     -> Result<Vec<(Field, Value)>, LowerError<'a>>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    let mut values = vec![];
+
+    for (i, op) in operands.iter().enumerate() {
+        let e = match operand_expr(op) {
+            Some(e) => e,
+            None    => continue,
+        };
+        let v = match eval(e, lookup) {
+            Ok(v) if !v.is_abs() => v,
+            _                    => continue,
+        };
+        match fields.iter().find(|f| f.operand == i) {
+            Some(&f) => values.push((f, v)),
+            None     => return Err(EvalError::NotAbsolute(*e.ann()).into()),
+        }
+    }
+
+    Ok(values)
+}
+
+// Returns the expression of the given source operand, if any.
+fn operand_expr<'l, 'a>(op: &'l Operand<'a>) -> Option<&'l SrcExpr<'a>> {
+    match *op {
+        Operand::Expr(ref e)             |
+        Operand::Imm(ref e)              |
+        Operand::Abs(ref e, _)           |
+        Operand::Disp(_, ref e)          |
+        Operand::DispIdx(_, ref e, _, _) => Some(e),
+        Operand::Masked(ref op)          => operand_expr(op),
+        _                                => None,
+    }
+}
+
+// Returns the relocatable values of the given arguments of a data directive
+// of the given size, each with the field that holds it.
+fn data_values<'a, F>(size: u8, args: &[Arg<'a>], lookup: &mut F) -> Vec<(Field, Value)>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    args.iter().enumerate()
+        .filter_map(|(i, a)| match *a {
+            Arg::Expr(ref e) => eval(e, lookup).ok().map(|v| (i, v)),
+            _                => None,
+        })
+        .filter(|(_, v)| !v.is_abs())
+        .map(|(i, v)| {
+            let offset = i as u64 * size as u64;
+            (Field { operand: i, offset, size, pc: None }, v)
+        })
+        .collect()
+}
+
+// Records a relocation of the field at the given address in the given
+// section, which holds the given value.  A PC-relative field, given the
+// address to which it is relative, needs no relocation for a value in its
+// own section.
+fn relocate(sections: &mut [Section],
+            section:  usize,
+            addr:     u64,
+            size:     u8,
+            pc:       Option<u64>,
+            value:    &Value) {
+    let target = match value.section {
+        Some(n) if pc.is_none() || n != section => n,
+        _                                       => return,
+    };
+
+    let base   = sections.get(target).map_or(0, |s| s.addr);
+    let addend = value.int.to_i64().unwrap_or(0).wrapping_sub(base as i64)
+               + pc.map_or(0, |pc| addr.wrapping_sub(pc) as i64);
+
+    let section = &mut sections[section];
+    section.relocs.push(Reloc {
+        offset: addr.wrapping_sub(section.addr),
+        size,
+        pc:     pc.is_some(),
+        target: RelocTarget::Section(target),
+        addend,
+    });
+}
+
+// Resolves the name and size of an instruction, reporting errors.
+fn resolve<'l, 'a>(insn: &'l Insn<'a>, messages: &mut Messages<'a>) -> Option<Op<'l, 'a>> {
+    let (name, cond) = match resolve_name(insn.name.name) {
+        Some(x) => x,
+        None    => {
+            messages.err_unknown_op(insn.name.ann, insn.name.name);
+            return None;
+        },
+    };
+
+    let size = match insn.size {
+        Some(s) => match resolve_size(name, s) {
+            Some(size) => Some(size),
+            None       => {
+                messages.err_unknown_op(insn.name.ann, &format!("{}.{}", insn.name.name, s));
+                return None;
+            },
+        },
+        None => None,
+    };
+
+    Some(Op { insn, name, cond, size })
+}
+
+// Returns the smallest size that can reach the target of the given branch
+// at the given address in the given section, or `None` if the target is
+// not an expression.
+fn branch_size<'a, F>(op: &Op<'_, 'a>, addr: u64, section: usize, relaxer: &Relaxer, lookup: &mut F)
+                     -> Option<Size>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    let target = match op.insn.operands.last() {
+        Some(Operand::Expr(e)) => eval(e, lookup).ok()?,
+        _                      => return None,
+    };
+
+    // A target in another section is placed by the linker, and the
+    // displacement is left to a relocation
+    if target.section.is_some_and(|s| s != section) {
+        return Some(Size::Word);
+    }
+
+    let disp = branch_disp(&target.int, addr).to_i64()?;
+
+    // An unreachable target gets the largest size; encoding reports the error
+    let size = relaxer.size_for(disp).unwrap_or(Size::Long);
+
+    match (op.name, size) {
+        (Mnemonic::Fbcc, Size::Byte) => Some(Size::Word),
+        (_,              size)       => Some(size),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aex::asm::{Expander, Syntax};
+    use aex::fmt::{AttStyle, MitStyle, MotorolaStyle, Style, ToStyled};
+    use aex::source::File;
+    use aex::target::cf::{round_trip, CF_A, CF_B, CF_C, CF_EMAC, CF_FPU, EXT_PATTERNS};
+    use aex::util::LE;
+
+    fn assemble(isa: Flags, origin: u64, text: &str) -> (Vec<u8>, String, String) {
+        let file = File::new("f", text);
+        let src  = Expander::new().expand(&file);
+        let asm  = Assembler { isa, origin, order: BE }.assemble(&src);
+        let data = asm.sections[0].data.clone();
+        (data, asm.symbols.to_string(), asm.messages.to_string())
+    }
+
+    #[test]
+    fn assemble_forward_refs() {
+        let (data, syms, msgs) = assemble(CF_A, 0x1000, "\
+start:  moveq   #(done-start)/2,%d0
+loop:   subq.l  #1,%d0
+        bne     loop
+        lea     table(%pc),%a0
+        bra     done
+table:  move.l  table,%d1
+done:   rts
+        | end
+");
+        assert_eq!(msgs, "");
+        assert_eq!(data, vec![
+            0x70, 0x09,                         // moveq   #9,%d0
+            0x53, 0x80,                         // subq.l  #1,%d0
+            0x66, 0xFC,                         // bne.s   loop
+            0x41, 0xFA, 0x00, 0x04,             // lea     table(%pc),%a0
+            0x60, 0x06,                         // bra.s   done
+            0x22, 0x39, 0, 0, 0x10, 0x0C,       // move.l  table,%d1
+            0x4E, 0x75,                         // rts
+        ]);
+        assert!(syms.contains("0000100c 0    l table\n"));
+        assert!(syms.contains("00001012 0    l done\n"));
+
+        // Debug register names are ordinary symbols
+        let (data, _, msgs) = assemble(CF_A, 0x1000, "csr:\tmove.l\tcsr,%d1\n");
+        assert_eq!(msgs, "");
+        assert_eq!(data, vec![0x22, 0x39, 0, 0, 0x10, 0x00]);
+    }
+
+    #[test]
+    fn assemble_local_labels() {
+        let (data, syms, msgs) = assemble(CF_A, 0, "\
+start:  moveq   #3,%d0
+1:      subq.l  #1,%d0
+        bne     1b
+        bra     1f
+        nop
+1:      bra     .Lout
+        nop
+.Lout:  rts
+next:   bra     .Lout
+        nop
+.Lout:  rts
+");
+        assert_eq!(msgs, "");
+        assert_eq!(data, vec![
+            0x70, 0x03,                         // moveq   #3,%d0
+            0x53, 0x80,                         // subq.l  #1,%d0
+            0x66, 0xFC,                         // bne.s   1b
+            0x60, 0x02,                         // bra.s   1f
+            0x4E, 0x71,                         // nop
+            0x60, 0x02,                         // bra.s   .Lout
+            0x4E, 0x71,                         // nop
+            0x4E, 0x75,                         // rts
+            0x60, 0x02,                         // bra.s   .Lout
+            0x4E, 0x71,                         // nop
+            0x4E, 0x75,                         // rts
+        ]);
+        // Local labels are not symbols
+        assert_eq!(syms, "00000010 0    l next\n00000000 0    l start\n");
+    }
+
+    #[test]
+    fn assemble_local_label_errors() {
+        let (_, _, msgs) = assemble(CF_A, 0, "\
+        bra     1b
+        bra     2f
+1:      nop
+f:      bra     .Lx
+        bra     1f
+g:
+.Lx:    nop
+.Lx:    nop
+.Ly: h: bra     .Ly
+        bra     .Ly
+");
+        assert_eq!(msgs, "\
+f:8:1: E002: Symbol already defined: .Lx
+f:1:9: E023: Local label not defined before this line: 1b
+f:2:17: E023: Local label not defined after this line: 2f
+f:4:17: E023: Local label not defined in this scope: .Lx
+f:5:17: E023: Local label not defined after this line: 1f
+f:9:17: E023: Local label not defined in this scope: .Ly
+f:10:17: E023: Local label not defined in this scope: .Ly
+");
+    }
+
+    #[test]
+    fn assemble_relocs() {
+        let file = File::new("f", "\
+        .global ext
+start:  move.l  #table,%d0
+        lea     table(%pc),%a1
+        bsr     ext
+        bra     start
+        .data
+table:  .long   start, ext+4
+        .word   table
+");
+        let src = Expander::new().expand(&file);
+        let asm = Assembler::new(CF_A).assemble(&src);
+        assert_eq!(asm.messages.to_string(), "");
+
+        let reloc = |offset, size, pc, target, addend| Reloc { offset, size, pc, target, addend };
+        assert_eq!(asm.sections[0].data, vec![
+            0x20, 0x3C, 0, 0, 0, 0,             // move.l  #table,%d0
+            0x43, 0xFA, 0x00, 0x00,             // lea     table(%pc),%a1
+            0x61, 0x00, 0x00, 0x00,             // bsr.w   ext
+            0x60, 0xF0,                         // bra.s   start
+        ]);
+        assert_eq!(asm.sections[0].relocs, vec![
+            reloc( 2, 4, false, RelocTarget::Section(1),    0),
+            reloc( 8, 2, true,  RelocTarget::Section(1),    0),
+            reloc(12, 2, true,  RelocTarget::Symbol("ext"), 0),
+        ]);
+        assert_eq!(asm.sections[1].relocs, vec![
+            reloc( 0, 4, false, RelocTarget::Section(0),    0),
+            reloc( 4, 4, false, RelocTarget::Symbol("ext"), 4),
+            reloc( 8, 2, false, RelocTarget::Section(1),    0),
+        ]);
+        assert_eq!(asm.symbols.get("ext").unwrap().value, None);
+
+        let (_, _, msgs) = assemble(CF_A, 0, "a:\tmoveq\t#a,%d0\n");
+        assert_eq!(msgs, "f:1:11: E004: Operation requires absolute (non-relocatable) operands.\n");
+    }
+
+    #[test]
+    fn assemble_relocs_far() {
+        // Displacements to other sections are left to the relocations
+        let file = File::new("f", "\
+        .global ext
+        .space  0x9000
+        bsr     ext
+        bsr.s   ext
+        bsr     d
+        lea     ext(%pc),%a0
+        .data
+        .long   0
+d:      .word   0
+");
+        let src = Expander::new().expand(&file);
+        let asm = Assembler::new(CF_A).assemble(&src);
+        assert_eq!(asm.messages.to_string(), "");
+
+        let reloc = |offset, size, pc, target, addend| Reloc { offset, size, pc, target, addend };
+        assert_eq!(asm.sections[0].data[0x9000..].to_vec(), vec![
+            0x61, 0x00, 0x00, 0x00,             // bsr.w   ext
+            0x61, 0x00,                         // bsr.s   ext
+            0x61, 0x00, 0x00, 0x00,             // bsr.w   d
+            0x41, 0xFA, 0x00, 0x00,             // lea     ext(%pc),%a0
+        ]);
+        assert_eq!(asm.sections[0].relocs, vec![
+            reloc(0x9002, 2, true, RelocTarget::Symbol("ext"),  0),
+            reloc(0x9005, 1, true, RelocTarget::Symbol("ext"), -1),
+            reloc(0x9008, 2, true, RelocTarget::Section(1),     4),
+            reloc(0x900C, 2, true, RelocTarget::Symbol("ext"),  0),
+        ]);
+    }
+
+    #[test]
+    fn assemble_relaxation() {
+        // The forward branch must grow once the span is known
+        let mut text = String::from("\tbeq\tfar\n");
+        for _ in 0..64 { text.push_str("\tmove.l\t#0,%d0\n"); }
+        text.push_str("far:\tbeq\tfar\n\tbra.w\tfar\n");
+
+        let (data, _, msgs) = assemble(CF_A, 0, &text);
+        assert_eq!(msgs, "");
+        assert_eq!(&data[..4], &[0x67, 0x00, 0x01, 0x82]);
+        assert_eq!(&data[data.len() - 6..], &[0x67, 0xFE, 0x60, 0x00, 0xFF, 0xFC]);
+    }
+
+    #[test]
+    fn assemble_long_branch() {
+        let (data, _, msgs) = assemble(CF_B, 0, "\tbra\t0x20000\n");
+        assert_eq!(msgs, "");
+        assert_eq!(data, vec![0x60, 0xFF, 0x00, 0x01, 0xFF, 0xFE]);
+
+        let (data, _, msgs) = assemble(CF_A, 0, "\tbra\t0x20000\n");
+        assert_eq!(msgs, "f:1:1: E008: Cannot encode instruction: no opcode for bra.l\n");
+        assert_eq!(data, vec![0; 6]);
+    }
+
+    #[test]
+    fn assemble_sections() {
+        let file = File::new("f", "\
+        .global start, buf
+        .equ    size, 2 * (val - msg)
+start:  move.l  #size,%d0
+        lea     buf,%a0
+        .data
+msg:    .asciz  \"hi\"
+        .align  4
+val:    .long   msg, -1
+        .section .bss
+buf:    .space  size
+end:
+        .text
+        .word   val - msg
+        .set    n, 1
+        .byte   n
+        .set    n, n + 1
+        .byte   n
+");
+        let src = Expander::new().expand(&file);
+        let asm = Assembler { isa: CF_A, origin: 0x100, order: LE }.assemble(&src);
+        assert_eq!(asm.messages.to_string(), "");
+
+        let s = &asm.sections;
+        assert_eq!(s.len(), 3);
+        assert_eq!((s[0].name, s[0].kind), (".text", SectionKind::Text));
+        assert_eq!((s[1].name, s[1].kind), (".data", SectionKind::Data));
+        assert_eq!((s[2].name, s[2].kind), (".bss",  SectionKind::Bss));
+
+        assert_eq!(s[0].data, vec![
+            0x20, 0x3C, 0, 0, 0, 0x08,          // move.l  #8,%d0
+            0x41, 0xF9, 0, 0, 0x01, 0x00,       // lea     0x100,%a0
+            0x04, 0x00,                         // .word   4
+            0x01, 0x02,                         // .byte   1, 2
+        ]);
+        assert_eq!(s[1].data, vec![
+            b'h', b'i', 0, 0,                   // .asciz  "hi"; .align 4
+            0x00, 0x01, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF,
+        ]);
+        assert_eq!(s[2].data, vec![0; 8]);
+
+        assert_eq!(asm.symbols.to_string(), "\
+00000100 2    g buf
+00000108 2    l end
+00000100 1    l msg
+00000002 abs  l n
+00000008 abs  l size
+00000100 0    g start
+00000104 1    l val
+");
+    }
+
+    #[test]
+    fn assemble_directive_errors() {
+        let (_, syms, msgs) = assemble(CF_A, 0, "\
+        .equ    a, 1
+a:      .byte   a
+        .frob
+        .bss
+        .byte   1
+        .org    0
+        .data
+        .org    0x100000000
+foo:    nop
+        .equ    foo, 5
+");
+        assert_eq!(msgs, "\
+f:2:1: E002: Symbol already defined: a
+f:3:9: E010: Unknown directive: .frob
+f:10:17: E002: Symbol already defined: foo
+f:5:1: E014: Section .bss cannot contain non-zero data.
+f:6:17: E012: Cannot move the location counter backward.
+f:8:17: E024: Location counter would exceed the 32-bit address space.
+");
+        assert_eq!(syms, "00000001 abs  l a\n00000000 2    l foo\n");
+    }
+
+    #[test]
+    fn assemble_macros() {
+        let (data, syms, msgs) = assemble(CF_A, 0, "\
+        .macro  save regs, size=8
+        lea     -\\size(%sp),%sp
+        movem.l \\regs,(%sp)
+        .endm
+        .macro  table from, to
+        .word   \\from, \\to
+        .endm
+entry:  save    %d2-%d3
+        .irp    n, 1, 2
+        moveq   #\\n,%d\\n
+        .endr
+        table   to=2, from=1
+");
+        assert_eq!(msgs, "");
+        assert_eq!(syms, "00000000 0    l entry\n");
+        assert_eq!(data, vec![
+            0x4F, 0xEF, 0xFF, 0xF8,             // lea     -8(%sp),%sp
+            0x48, 0xD7, 0x00, 0x0C,             // movem.l %d2-%d3,(%sp)
+            0x72, 0x01,                         // moveq   #1,%d1
+            0x74, 0x02,                         // moveq   #2,%d2
+            0x00, 0x01, 0x00, 0x02,             // .word   1, 2
+        ]);
+    }
+
+    #[test]
+    fn assemble_conditionals() {
+        let mut e = Expander::new();
+        e.define("BOARD=2");
+
+        let file = File::new("f", "\
+        .if     BOARD == 1
+        nop
+        .else
+        moveq   #BOARD,%d0
+        .endif
+");
+        let src = e.expand(&file);
+        let asm = Assembler::new(CF_A).assemble(&src);
+
+        assert_eq!(asm.messages.to_string(), "");
+        assert_eq!(asm.symbols.to_string(), "00000002 abs  l BOARD\n");
+        assert_eq!(asm.sections[0].data, vec![0x70, 0x02]);
+    }
+
+    #[test]
+    fn assemble_macro_errors() {
+        let (_, _, msgs) = assemble(CF_A, 0, "\
+        .macro  load v
+        moveq   #\\v,%d0
+        .endm
+        load    1
+        load    300
+");
+        assert_eq!(msgs, "\
+f:2:1: E008: Cannot encode instruction: quick immediate 300 out of range (-128 to 127)
+f:5:9: N020: In expansion of macro: load
+");
+    }
+
+    #[test]
+    fn assemble_errors() {
+        let (data, _, msgs) = assemble(CF_A, 0, "\
+a:      nop
+a:      frob    %d0
+        move.l  b,%d0
+        moveq   #300,%d0
+        move.x  %d0,%d1
+");
+        assert_eq!(msgs, "\
+f:2:1: E002: Symbol already defined: a
+f:2:9: E006: Unknown instruction: frob
+f:5:9: E006: Unknown instruction: move.x
+f:3:17: E003: Symbol not defined: b
+f:4:1: E008: Cannot encode instruction: quick immediate 300 out of range (-128 to 127)
+");
+        // Erroneous instructions are filled with zeros to keep addresses
+        assert_eq!(data, vec![0x4E, 0x71, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn assemble_round_trip() {
+        assemble_round_trip_in(&AttStyle::default(), Syntax::Att);
+    }
+
+    #[test]
+    fn assemble_round_trip_mit() {
+        assemble_round_trip_in(&MitStyle::default(), Syntax::Mit);
+    }
+
+    #[test]
+    fn assemble_round_trip_motorola() {
+        assemble_round_trip_in(&MotorolaStyle::default(), Syntax::Motorola);
+    }
+
+    fn assemble_round_trip_in<S: Style<()>>(style: &S, syntax: Syntax) {
+        // Disassemble, assemble the listing, and compare
+        let isa = CF_C | CF_FPU | CF_EMAC;
+
+        let r = round_trip(OPCODES, isa, &EXT_PATTERNS[2..3], |i| {
+            let text = i.styled(style).to_string();
+            let file = File::new("f", text);
+            let src  = Expander { syntax, ..Expander::new() }.expand(&file);
+            let asm  = Assembler { isa, origin: i.addr, order: BE }.assemble(&src);
+            if asm.messages.has_errors() { return Err(asm.messages.to_string()); }
+            Ok(asm.sections[0].data.clone())
+        });
+
+        assert!(r.decoded > 0, "{}", r);
+        assert!(r.is_ok(), "{}{:02x?}", r, r.mismatches.iter().take(20).collect::<Vec<_>>());
+        assert_eq!(r.unsupported_count(), 0, "{}", r);
+    }
+}
@@ -0,0 +1,561 @@
+// Assembly Directives
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::path::Path;
+
+use num::{BigInt, One, ToPrimitive};
+
+use aex::ast::{Expr, Node, UnaryOp};
+use aex::message::Messages;
+use aex::source::Source;
+use aex::util::{ByteOrder, BE};
+
+use super::{eval, Arg, Directive, EvalError, SrcExpr, SrcId, SymbolKind, Value};
+
+/// Size of the address space.  The location counter must stay below it.
+pub const ADDR_LIMIT: u64 = 1 << 32;
+
+/// A directive, with its name resolved and its arguments checked.
+#[derive(Clone, PartialEq, Debug)]
+pub enum DirectiveOp<'l, 'a: 'l> {
+    /// Switch to a section, given its name and flags: `.section`, `.text`,
+    /// `.data`, or `.bss`
+    Section(&'a str, Option<&'l [u8]>),
+
+    /// Advance the location counter to an address, with an optional fill
+    /// byte: `.org`
+    Org(&'l SrcExpr<'a>, Option<&'l SrcExpr<'a>>),
+
+    /// Align the location counter to a multiple of a byte count, with an
+    /// optional fill byte and maximum padding: `.align` or `.balign`
+    Align(&'l SrcExpr<'a>, Option<&'l SrcExpr<'a>>, Option<&'l SrcExpr<'a>>),
+
+    /// Integers of the given size in bytes: `.byte`, `.short`, `.word`, or
+    /// `.long`
+    Int(u8, &'l [Arg<'a>]),
+
+    /// Floating-point numbers of the given size in bytes: `.float` or
+    /// `.double`
+    Float(u8, &'l [Arg<'a>]),
+
+    /// Literal bytes: `.ascii`, `.asciz`, or `.incbin`
+    Bytes(Vec<u8>),
+
+    /// Repeated fill bytes, given a count and an optional fill byte:
+    /// `.space` or `.skip`
+    Space(&'l SrcExpr<'a>, Option<&'l SrcExpr<'a>>),
+
+    /// Repeated values, given a count, a size in bytes, and a value: `.fill`
+    Fill(&'l SrcExpr<'a>, Option<&'l SrcExpr<'a>>, Option<&'l SrcExpr<'a>>),
+
+    /// Symbol definition: `.equ` or `.set`
+    Equate(&'l SrcId<'a>, &'l SrcExpr<'a>, SymbolKind),
+
+    /// Symbol visibility: `.global` (`true`) or `.local` (`false`)
+    Global(Vec<&'l SrcId<'a>>, bool),
+}
+
+/// An error that prevents a directive from generating data.
+#[derive(Clone, PartialEq, Debug)]
+pub enum DirectiveError<'a> {
+    /// An argument cannot be evaluated.
+    Eval(EvalError<'a>),
+
+    /// An argument is not of the expected form.
+    Expected(Source<'a>, &'static str),
+
+    /// A value does not fit in the given number of bytes.
+    Range(Source<'a>, BigInt, u8),
+
+    /// `.org` would move the location counter backward.
+    Backward(Source<'a>),
+
+    /// An alignment is not a power of two.
+    BadAlign(Source<'a>),
+
+    /// The location counter would move past the 32-bit address space.
+    Overflow(Source<'a>),
+
+    /// A file cannot be read, given its path and the reason.
+    Read(Source<'a>, String, String),
+}
+
+impl<'a> From<EvalError<'a>> for DirectiveError<'a> {
+    fn from(e: EvalError<'a>) -> Self {
+        DirectiveError::Eval(e)
+    }
+}
+
+impl<'a> DirectiveError<'a> {
+    /// Adds a message describing the error to the given collection.
+    pub fn report(&self, messages: &mut Messages<'a>) {
+        match *self {
+            DirectiveError::Eval(ref e)           => e.report(messages),
+            DirectiveError::Expected(s, what)     => messages.err_expected(s, what),
+            DirectiveError::Range(s, ref v, n)    => messages.err_value_range(s, v, n),
+            DirectiveError::Backward(s)           => messages.err_org_backward(s),
+            DirectiveError::BadAlign(s)           => messages.err_bad_align(s),
+            DirectiveError::Overflow(s)           => messages.err_addr_overflow(s),
+            DirectiveError::Read(s, ref p, ref e) => messages.err_read_failed(s, p, e),
+        }
+    }
+}
+
+type DResult<'a, T> = Result<T, DirectiveError<'a>>;
+
+/// Resolves the given directive, reporting errors.  Files named by
+/// `.incbin` are read relative to the directory of the source file.
+pub fn resolve_directive<'l, 'a>(d: &'l Directive<'a>, messages: &mut Messages<'a>)
+                                -> Option<DirectiveOp<'l, 'a>> {
+    match resolve(d) {
+        Ok(Some(op)) => Some(op),
+        Ok(None) => {
+            messages.err_unknown_directive(d.name.ann, d.name.name);
+            None
+        },
+        Err(e) => {
+            e.report(messages);
+            None
+        },
+    }
+}
+
+fn resolve<'l, 'a>(d: &'l Directive<'a>) -> DResult<'a, Option<DirectiveOp<'l, 'a>>> {
+    let args = &d.args[..];
+    let name = d.name.name.to_ascii_lowercase();
+
+    Ok(Some(match &*name {
+        ".text"    => { arity(d, 0, 0)?; DirectiveOp::Section(".text", None) },
+        ".data"    => { arity(d, 0, 0)?; DirectiveOp::Section(".data", None) },
+        ".bss"     => { arity(d, 0, 0)?; DirectiveOp::Section(".bss",  None) },
+        ".section" => {
+            arity(d, 1, 2)?;
+            let section = match *expr(&args[0], "section name")? {
+                Expr::Id(ref id) => id.name,
+                ref e            => return Err(DirectiveError::Expected(*e.ann(), "section name")),
+            };
+            let flags = match args.get(1) {
+                Some(a) => Some(string(a)?),
+                None    => None,
+            };
+            DirectiveOp::Section(section, flags)
+        },
+        ".org" => {
+            arity(d, 1, 2)?;
+            DirectiveOp::Org(expr(&args[0], "address")?, opt_expr(args, 1)?)
+        },
+        ".align" | ".balign" => {
+            arity(d, 1, 3)?;
+            DirectiveOp::Align(expr(&args[0], "alignment")?, opt_expr(args, 1)?, opt_expr(args, 2)?)
+        },
+        ".byte"            => { arity(d, 1, !0)?; DirectiveOp::Int(1, exprs(args)?) },
+        ".short" | ".word" => { arity(d, 1, !0)?; DirectiveOp::Int(2, exprs(args)?) },
+        ".long"            => { arity(d, 1, !0)?; DirectiveOp::Int(4, exprs(args)?) },
+        ".float"           => { arity(d, 1, !0)?; DirectiveOp::Float(4, exprs(args)?) },
+        ".double"          => { arity(d, 1, !0)?; DirectiveOp::Float(8, exprs(args)?) },
+        ".ascii" | ".asciz" => {
+            arity(d, 1, !0)?;
+            let zero      = name == ".asciz";
+            let mut bytes = vec![];
+            for a in args {
+                bytes.extend_from_slice(string(a)?);
+                if zero { bytes.push(0); }
+            }
+            DirectiveOp::Bytes(bytes)
+        },
+        ".space" | ".skip" => {
+            arity(d, 1, 2)?;
+            DirectiveOp::Space(expr(&args[0], "count")?, opt_expr(args, 1)?)
+        },
+        ".fill" => {
+            arity(d, 1, 3)?;
+            DirectiveOp::Fill(expr(&args[0], "count")?, opt_expr(args, 1)?, opt_expr(args, 2)?)
+        },
+        ".equ" | ".set" => {
+            arity(d, 2, 2)?;
+            let kind = if name == ".equ" { SymbolKind::Equate } else { SymbolKind::Set };
+            DirectiveOp::Equate(symbol(&args[0])?, expr(&args[1], "value")?, kind)
+        },
+        ".global" | ".globl" | ".local" => {
+            arity(d, 1, !0)?;
+            let names = args.iter().map(symbol).collect::<DResult<_>>()?;
+            DirectiveOp::Global(names, name != ".local")
+        },
+        ".incbin" => {
+            arity(d, 1, 3)?;
+            let skip  = opt_count(args, 1)?.unwrap_or(0);
+            let count = opt_count(args, 2)?;
+            DirectiveOp::Bytes(incbin(&args[0], skip, count)?)
+        },
+        _ => return Ok(None),
+    }))
+}
+
+impl<'l, 'a> DirectiveOp<'l, 'a> {
+    /// Returns the length of the data generated by the directive at the
+    /// given address in the given section, with multi-byte values in the
+    /// given byte order.  Symbols are looked up with the given function.
+    /// Unlike [`data`](#method.data), this does not generate fill bytes.
+    pub fn len<F>(&self, addr: u64, section: usize, order: ByteOrder, lookup: &mut F)
+                 -> DResult<'a, u64>
+    where F: FnMut(&SrcId<'a>) -> Option<Value> {
+        let (len, src) = match *self {
+            DirectiveOp::Org(target, _) => {
+                let v = eval(target, lookup)?;
+                if v.section.is_some() && v.section != Some(section) {
+                    return Err(EvalError::NotAbsolute(*target.ann()).into());
+                }
+                match v.int.to_u64() {
+                    Some(t) if t >= addr => (t - addr, *target.ann()),
+                    _                    => return Err(DirectiveError::Backward(*target.ann())),
+                }
+            },
+            DirectiveOp::Align(align, _, max) => {
+                // Zero requests no alignment, as in GNU as
+                let n = abs_u64(align, lookup)?.max(1);
+                if !n.is_power_of_two() {
+                    return Err(DirectiveError::BadAlign(*align.ann()));
+                }
+                let len = addr.wrapping_neg() & (n - 1);
+                let max = match max {
+                    Some(e) => abs_u64(e, lookup)?,
+                    None    => len,
+                };
+                (if len > max { 0 } else { len }, *align.ann())
+            },
+            DirectiveOp::Space(count, _) => {
+                (abs_u64(count, lookup)?, *count.ann())
+            },
+            DirectiveOp::Fill(count, size, _) => {
+                let n = abs_u64(count, lookup)?;
+                let s = fill_size(size, lookup)? as u64;
+                (n.saturating_mul(s), *count.ann())
+            },
+            _ => {
+                return self.data(addr, section, order, lookup).map(|d| d.len() as u64);
+            },
+        };
+
+        match addr.checked_add(len) {
+            Some(end) if end < ADDR_LIMIT => Ok(len),
+            _                             => Err(DirectiveError::Overflow(src)),
+        }
+    }
+
+    /// Returns the data generated by the directive at the given address in
+    /// the given section, with multi-byte values in the given byte order.
+    /// Symbols are looked up with the given function.
+    pub fn data<F>(&self, addr: u64, section: usize, order: ByteOrder, lookup: &mut F)
+                  -> DResult<'a, Vec<u8>>
+    where F: FnMut(&SrcId<'a>) -> Option<Value> {
+        match *self {
+            DirectiveOp::Org(_, fill) | DirectiveOp::Align(_, fill, _) | DirectiveOp::Space(_, fill) => {
+                let len = self.len(addr, section, order, lookup)?;
+                Ok(vec![fill_byte(fill, lookup)?; len as usize])
+            },
+            DirectiveOp::Fill(_, size, value) => {
+                let len  = self.len(addr, section, order, lookup)?;
+                let size = fill_size(size, lookup)?;
+                let value = match value {
+                    Some(e) => int_bytes(&abs(e, lookup)?, size, order, *e.ann())?,
+                    None    => vec![0; size as usize],
+                };
+                Ok(value.repeat((len / size as u64) as usize))
+            },
+            DirectiveOp::Int(size, args) => {
+                let mut data = vec![];
+                for a in args {
+                    let e = expr(a, "expression")?;
+                    let v = eval(e, lookup)?;
+                    data.extend(int_bytes(&v.int, size, order, *e.ann())?);
+                }
+                Ok(data)
+            },
+            DirectiveOp::Float(size, args) => {
+                let mut data = vec![];
+                for a in args {
+                    let v = float(expr(a, "expression")?, lookup)?;
+                    let b = if size == 4 { (v as f32).to_bits() as u64 } else { v.to_bits() };
+                    data.extend(bits_bytes(b, size, order));
+                }
+                Ok(data)
+            },
+            DirectiveOp::Bytes(ref bytes) => {
+                Ok(bytes.clone())
+            },
+            DirectiveOp::Section(..) | DirectiveOp::Equate(..) | DirectiveOp::Global(..) => {
+                Ok(vec![])
+            },
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Argument checks
+
+fn arity<'a>(d: &Directive<'a>, min: usize, max: usize) -> DResult<'a, ()> {
+    match d.args.len() {
+        n if n < min => Err(DirectiveError::Expected(d.name.ann, "more arguments")),
+        n if n > max => Err(DirectiveError::Expected(d.args[max].src(), "end of line")),
+        _            => Ok(()),
+    }
+}
+
+fn expr<'l, 'a>(a: &'l Arg<'a>, what: &'static str) -> DResult<'a, &'l SrcExpr<'a>> {
+    match *a {
+        Arg::Expr(ref e) => Ok(e),
+        Arg::Str(_, s)   => Err(DirectiveError::Expected(s, what)),
+    }
+}
+
+fn opt_expr<'l, 'a>(args: &'l [Arg<'a>], i: usize) -> DResult<'a, Option<&'l SrcExpr<'a>>> {
+    args.get(i).map(|a| expr(a, "expression")).transpose()
+}
+
+fn exprs<'l, 'a>(args: &'l [Arg<'a>]) -> DResult<'a, &'l [Arg<'a>]> {
+    for a in args { expr(a, "expression")?; }
+    Ok(args)
+}
+
+fn string<'l, 'a>(a: &'l Arg<'a>) -> DResult<'a, &'l [u8]> {
+    match *a {
+        Arg::Str(ref s, _) => Ok(s),
+        Arg::Expr(ref e)   => Err(DirectiveError::Expected(*e.ann(), "string")),
+    }
+}
+
+fn symbol<'l, 'a>(a: &'l Arg<'a>) -> DResult<'a, &'l SrcId<'a>> {
+    match *expr(a, "symbol name")? {
+        Expr::Id(ref id) => Ok(id),
+        ref e            => Err(DirectiveError::Expected(*e.ann(), "symbol name")),
+    }
+}
+
+// Returns a count that must be constant when the directive is resolved.
+fn opt_count<'a>(args: &[Arg<'a>], i: usize) -> DResult<'a, Option<u64>> {
+    match opt_expr(args, i)? {
+        Some(e) => Ok(Some(abs_u64(e, &mut |_: &SrcId<'a>| None)?)),
+        None    => Ok(None),
+    }
+}
+
+fn incbin<'a>(a: &Arg<'a>, skip: u64, count: Option<u64>) -> DResult<'a, Vec<u8>> {
+    let name = String::from_utf8_lossy(string(a)?).into_owned();
+    let src  = a.src();
+
+    // Relative paths are relative to the directory of the source file
+    let path = match src {
+        Source::File { file, .. } => Path::new(file.name()).with_file_name(&name),
+        _                         => Path::new(&name).to_path_buf(),
+    };
+
+    let data = fs::read(&path)
+        .map_err(|e| DirectiveError::Read(src, path.display().to_string(), e.to_string()))?;
+
+    let start = (skip as usize).min(data.len());
+    let end   = match count {
+        Some(n) => start.saturating_add(n as usize).min(data.len()),
+        None    => data.len(),
+    };
+    Ok(data[start..end].to_vec())
+}
+
+// -----------------------------------------------------------------------------
+// Evaluation
+
+fn abs<'a, F>(e: &SrcExpr<'a>, lookup: &mut F) -> DResult<'a, BigInt>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    let v = eval(e, lookup)?;
+    if !v.is_abs() { return Err(EvalError::NotAbsolute(*e.ann()).into()); }
+    Ok(v.int)
+}
+
+fn abs_u64<'a, F>(e: &SrcExpr<'a>, lookup: &mut F) -> DResult<'a, u64>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    abs(e, lookup)?.to_u64()
+        .ok_or(DirectiveError::Expected(*e.ann(), "non-negative count"))
+}
+
+fn fill_byte<'a, F>(e: Option<&SrcExpr<'a>>, lookup: &mut F) -> DResult<'a, u8>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    match e {
+        Some(e) => Ok(int_bytes(&abs(e, lookup)?, 1, BE, *e.ann())?[0]),
+        None    => Ok(0),
+    }
+}
+
+fn fill_size<'a, F>(e: Option<&SrcExpr<'a>>, lookup: &mut F) -> DResult<'a, u8>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    match e {
+        Some(e) => match abs_u64(e, lookup)? {
+            s @ 1..=8 => Ok(s as u8),
+            _         => Err(DirectiveError::Expected(*e.ann(), "size from 1 to 8")),
+        },
+        None => Ok(1),
+    }
+}
+
+fn float<'a, F>(e: &SrcExpr<'a>, lookup: &mut F) -> DResult<'a, f64>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    match *e {
+        Expr::Float(ref x) => {
+            Ok(x.value)
+        },
+        Expr::Unary(ref u) if u.op == UnaryOp::Neg => {
+            Ok(-float(&u.expr, lookup)?)
+        },
+        _ => {
+            abs(e, lookup)?.to_f64()
+                .ok_or(DirectiveError::Expected(*e.ann(), "floating-point number"))
+        },
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Encoding
+
+/// Returns the given integer as the given number of bytes in the given byte
+/// order.  The integer may be signed or unsigned, but must fit in the given
+/// number of bytes.
+pub fn int_bytes<'a>(v: &BigInt, size: u8, order: ByteOrder, src: Source<'a>)
+                    -> DResult<'a, Vec<u8>> {
+    let bits = size as usize * 8;
+    let min  = -(BigInt::one() << (bits - 1));
+    let max  =   BigInt::one() << bits;
+
+    if *v < min || *v >= max {
+        return Err(DirectiveError::Range(src, v.clone(), size));
+    }
+
+    let v = v.to_i64().map(|v| v as u64).or_else(|| v.to_u64()).unwrap_or(0);
+    Ok(bits_bytes(v, size, order))
+}
+
+// Returns the low bytes of the given value in the given byte order.
+fn bits_bytes(v: u64, size: u8, order: ByteOrder) -> Vec<u8> {
+    let be = v.to_be_bytes();
+    let mut bytes = be[8 - size as usize..].to_vec();
+    if order != BE { bytes.reverse(); }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aex::asm::{parse, Stmt, Syntax};
+    use aex::source::File;
+    use aex::util::LE;
+
+    fn data(text: &str, addr: u64, order: ByteOrder) -> Result<Vec<u8>, String> {
+        let     file  = File::new("f", text);
+        let mut m     = Messages::new();
+        let     lines = parse(&file, Syntax::Att, &mut m);
+        assert!(!m.has_errors(), "{}", m);
+
+        let d = match lines[0].stmt {
+            Some(Stmt::Directive(ref d)) => d,
+            _                            => panic!("expected directive"),
+        };
+
+        let op = match resolve_directive(d, &mut m) {
+            Some(op) => op,
+            None     => return Err(m.to_string()),
+        };
+
+        op.data(addr, 0, order, &mut |id: &SrcId| match id.name {
+            "k" => Some(Value::abs(3)),
+            "x" => Some(Value::rel(0x100, 0)),
+            "y" => Some(Value::rel(0x100, 1)),
+            _   => None,
+        }).map_err(|e| {
+            e.report(&mut m);
+            m.to_string()
+        })
+    }
+
+    #[test]
+    fn data_int() {
+        assert_eq!(data(".byte 1, -1, 'A', 255", 0, BE), Ok(vec![1, 0xFF, 0x41, 0xFF]));
+        assert_eq!(data(".word 0x1234, -2",      0, BE), Ok(vec![0x12, 0x34, 0xFF, 0xFE]));
+        assert_eq!(data(".short 0x1234",         0, LE), Ok(vec![0x34, 0x12]));
+        assert_eq!(data(".long x + k",           0, BE), Ok(vec![0, 0, 0x01, 0x03]));
+        assert_eq!(data(".long 0x12345678",      0, LE), Ok(vec![0x78, 0x56, 0x34, 0x12]));
+    }
+
+    #[test]
+    fn data_float() {
+        assert_eq!(data(".float 1.5, -2",  0, BE), Ok(vec![0x3F, 0xC0, 0, 0, 0xC0, 0, 0, 0]));
+        assert_eq!(data(".double 1.0",     0, LE), Ok(vec![0, 0, 0, 0, 0, 0, 0xF0, 0x3F]));
+    }
+
+    #[test]
+    fn data_bytes() {
+        assert_eq!(data(".ascii \"ab\", \"c\"", 0, BE), Ok(b"abc".to_vec()));
+        assert_eq!(data(".asciz \"ab\", \"c\"", 0, BE), Ok(b"ab\0c\0".to_vec()));
+        assert_eq!(data(".space 3, 0xAA",       0, BE), Ok(vec![0xAA; 3]));
+        assert_eq!(data(".fill k, 2, 0x102",    0, LE), Ok(vec![2, 1, 2, 1, 2, 1]));
+        assert_eq!(data(".fill 2",              0, BE), Ok(vec![0, 0]));
+    }
+
+    #[test]
+    fn data_incbin() {
+        let path = std::env::temp_dir().join("aex-directive-incbin.bin");
+        std::fs::write(&path, b"abcdef").unwrap();
+        let path = path.display();
+
+        assert_eq!(data(&format!(".incbin \"{}\"",                  path), 0, BE), Ok(b"abcdef".to_vec()));
+        assert_eq!(data(&format!(".incbin \"{}\", 2, 3",            path), 0, BE), Ok(b"cde".to_vec()));
+        assert_eq!(data(&format!(".incbin \"{}\", 9",               path), 0, BE), Ok(vec![]));
+        assert_eq!(data(&format!(".incbin \"{}\", 1, 0xFFFFFFFFFFFFFFFF", path), 0, BE), Ok(b"bcdef".to_vec()));
+    }
+
+    #[test]
+    fn data_location() {
+        assert_eq!(data(".align 4",          0x101, BE), Ok(vec![0; 3]));
+        assert_eq!(data(".balign 8, 0xFF, 2", 0x101, BE), Ok(vec![]));
+        assert_eq!(data(".align 2",          0x100, BE), Ok(vec![]));
+        assert_eq!(data(".align 0",          0x101, BE), Ok(vec![]));
+        assert_eq!(data(".org x + 2, 1",     0x0FF, BE), Ok(vec![1; 3]));
+        assert_eq!(data(".org 0x100",        0x0FE, BE), Ok(vec![0; 2]));
+    }
+
+    #[test]
+    fn data_errors() {
+        assert_eq!(data(".byte 256",   0, BE), Err("f:1:7: E011: Value 256 does not fit in 1 byte(s).\n".into()));
+        assert_eq!(data(".word -32769", 0, BE), Err("f:1:7: E011: Value -32769 does not fit in 2 byte(s).\n".into()));
+        assert_eq!(data(".align 3",    0, BE), Err("f:1:8: E013: Alignment must be a power of two.\n".into()));
+        assert_eq!(data(".org 0x10",   0x20, BE), Err("f:1:6: E012: Cannot move the location counter backward.\n".into()));
+        assert_eq!(data(".org y",      0, BE), Err("f:1:6: E004: Operation requires absolute (non-relocatable) operands.\n".into()));
+        assert_eq!(data(".space x",    0, BE), Err("f:1:8: E004: Operation requires absolute (non-relocatable) operands.\n".into()));
+        assert_eq!(data(".byte \"a\"", 0, BE), Err("f:1:7: E001: Expected: expression\n".into()));
+        assert_eq!(data(".equ k",      0, BE), Err("f:1:1: E001: Expected: more arguments\n".into()));
+        assert_eq!(data(".text 1",     0, BE), Err("f:1:7: E001: Expected: end of line\n".into()));
+        assert_eq!(data(".bogus",      0, BE), Err("f:1:1: E010: Unknown directive: .bogus\n".into()));
+        assert_eq!(data(".space 0x7FFFFFFFFFFFFFFF", 0, BE),
+                   Err("f:1:8: E024: Location counter would exceed the 32-bit address space.\n".into()));
+        assert_eq!(data(".org 0x100000000",  0, BE),
+                   Err("f:1:6: E024: Location counter would exceed the 32-bit address space.\n".into()));
+        assert_eq!(data(".fill 0x80000000, 2", 0, BE),
+                   Err("f:1:7: E024: Location counter would exceed the 32-bit address space.\n".into()));
+        assert_eq!(data(".align 0x200000000", 1, BE),
+                   Err("f:1:8: E024: Location counter would exceed the 32-bit address space.\n".into()));
+        assert_eq!(data(".incbin \"/nonexistent\"", 0, BE),
+                   Err("f:1:9: E015: Cannot read file /nonexistent: No such file or directory (os error 2)\n".into()));
+    }
+}
@@ -0,0 +1,460 @@
+// ELF Object Writer
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use aex::io::{EncodeWrite, EncodeWriter};
+use aex::target::cf::{Flags, CF_A2, CF_B, CF_C, CF_EMAC, CF_EMAC_B, CF_FPU, CF_MAC};
+use aex::util::BE;
+
+use super::{low_bits, Assembly, Reloc, RelocTarget, SectionKind, Symbol};
+
+/// ColdFire header flag: V4e core.  Set with `EF_M68K_CF_FLOAT`.
+pub const EF_M68K_CFV4E:         u32 = 0x0000_8000;
+
+/// ColdFire header flag: ISA_A.
+pub const EF_M68K_CF_ISA_A:      u32 = 0x02;
+
+/// ColdFire header flag: ISA_A+.
+pub const EF_M68K_CF_ISA_A_PLUS: u32 = 0x03;
+
+/// ColdFire header flag: ISA_B.
+pub const EF_M68K_CF_ISA_B:      u32 = 0x05;
+
+/// ColdFire header flag: ISA_C.
+pub const EF_M68K_CF_ISA_C:      u32 = 0x06;
+
+/// ColdFire header flag: MAC unit.
+pub const EF_M68K_CF_MAC:        u32 = 0x10;
+
+/// ColdFire header flag: EMAC unit.
+pub const EF_M68K_CF_EMAC:       u32 = 0x20;
+
+/// ColdFire header flag: EMAC_B unit.
+pub const EF_M68K_CF_EMAC_B:     u32 = 0x30;
+
+/// ColdFire header flag: floating-point unit.
+pub const EF_M68K_CF_FLOAT:      u32 = 0x40;
+
+// File identification
+const ELFCLASS32:  u8  = 1;
+const ELFDATA2MSB: u8  = 2;
+const EV_CURRENT:  u8  = 1;
+const ET_REL:      u16 = 1;
+const EM_68K:      u16 = 4;
+
+// Sizes of the file header and of table entries
+const EHDR_SIZE: u32 = 52;
+const SHDR_SIZE: u32 = 40;
+const SYM_SIZE:  u32 = 16;
+const RELA_SIZE: u32 = 12;
+
+// Section types
+const SHT_PROGBITS: u32 = 1;
+const SHT_SYMTAB:   u32 = 2;
+const SHT_STRTAB:   u32 = 3;
+const SHT_RELA:     u32 = 4;
+const SHT_NOBITS:   u32 = 8;
+
+// Section flags
+const SHF_WRITE:     u32 = 0x01;
+const SHF_ALLOC:     u32 = 0x02;
+const SHF_EXECINSTR: u32 = 0x04;
+const SHF_INFO_LINK: u32 = 0x40;
+
+// Symbol bindings and types, and special section indexes
+const STB_LOCAL:   u8  = 0;
+const STB_GLOBAL:  u8  = 1;
+const STT_NOTYPE:  u8  = 0;
+const STT_SECTION: u8  = 3;
+const SHN_UNDEF:   u16 = 0;
+const SHN_ABS:     u16 = 0xFFF1;
+
+// Relocation types
+const R_68K_32:   u8 = 1;
+const R_68K_16:   u8 = 2;
+const R_68K_8:    u8 = 3;
+const R_68K_PC32: u8 = 4;
+const R_68K_PC16: u8 = 5;
+const R_68K_PC8:  u8 = 6;
+
+// A section header.  Sections of a relocatable object have no address.
+#[derive(Clone, Copy, Default, Debug)]
+struct Shdr {
+    name:    u32,
+    kind:    u32,
+    flags:   u32,
+    offset:  u32,
+    size:    u32,
+    link:    u32,
+    info:    u32,
+    align:   u32,
+    entsize: u32,
+}
+
+/// Returns the ELF header flags for code of the given ISA profile: the ISA
+/// revision, and the MAC and floating-point units, if any.
+pub fn elf_flags(isa: Flags) -> u32 {
+    let rev = if isa & CF_C != 0 {
+        EF_M68K_CF_ISA_C
+    } else if isa & CF_B != 0 {
+        EF_M68K_CF_ISA_B
+    } else if isa & CF_A2 != 0 {
+        EF_M68K_CF_ISA_A_PLUS
+    } else {
+        EF_M68K_CF_ISA_A
+    };
+
+    let mac = if isa & CF_EMAC_B != 0 {
+        EF_M68K_CF_EMAC_B
+    } else if isa & CF_EMAC != 0 {
+        EF_M68K_CF_EMAC
+    } else if isa & CF_MAC != 0 {
+        EF_M68K_CF_MAC
+    } else {
+        0
+    };
+
+    let fpu = if isa & CF_FPU != 0 { EF_M68K_CFV4E | EF_M68K_CF_FLOAT } else { 0 };
+
+    rev | mac | fpu
+}
+
+/// Writes the given assembly, of code for the given ISA profile, to the
+/// given output as an ELF32 big-endian relocatable object for m68k.
+///
+/// The output sections come first, each at offset 0 whatever its assembly
+/// address, followed by a `.rela` section for each output section with
+/// relocations.  The symbol table holds a symbol for each output section,
+/// then the local symbols, then the global symbols.  A global symbol with
+/// no value is undefined; a local one is left out.
+///
+pub fn write_elf<W: Write>(asm: &Assembly, isa: Flags, out: &mut W) -> io::Result<()> {
+    let count = asm.sections.len();
+
+    let mut body  = vec![];
+    let mut names = vec![0];
+    let mut shdrs = vec![Shdr::default()];
+
+    // Output sections
+    for s in &asm.sections {
+        let (kind, flags) = match s.kind {
+            SectionKind::Text     => (SHT_PROGBITS, SHF_ALLOC | SHF_EXECINSTR),
+            SectionKind::Data     => (SHT_PROGBITS, SHF_ALLOC | SHF_WRITE),
+            SectionKind::ReadOnly => (SHT_PROGBITS, SHF_ALLOC),
+            SectionKind::Bss      => (SHT_NOBITS,   SHF_ALLOC | SHF_WRITE),
+        };
+        let data = if kind == SHT_NOBITS { &[][..] } else { &s.data[..] };
+
+        shdrs.push(Shdr {
+            name:   string(&mut names, s.name),
+            offset: place(&mut body, data),
+            size:   s.data.len() as u32,
+            align:  s.align as u32,
+            kind, flags,
+            ..Shdr::default()
+        });
+    }
+
+    let rela_count = asm.sections.iter().filter(|s| !s.relocs.is_empty()).count();
+    let symtab     = (count + rela_count + 1) as u32;
+
+    // Symbol table: null, sections, locals, then globals
+    let mut syms    = EncodeWriter::new(vec![], BE);
+    let mut strs    = vec![0];
+    let mut indexes = HashMap::new();
+
+    for i in 0..=count {
+        let kind = if i == 0 { STT_NOTYPE } else { STT_SECTION };
+        write_sym(&mut syms, 0, 0, STB_LOCAL, kind, i as u16)?;
+    }
+
+    let mut next  = count + 1;
+    let mut first = next;
+
+    for global in [false, true] {
+        if global { first = next; }
+
+        for sym in asm.symbols.iter().filter(|s| s.global == global) {
+            let (value, shndx) = match sym_place(sym, asm) {
+                Some(p) => p,
+                None    => continue,
+            };
+            let name = string(&mut strs, sym.name);
+            let bind = if global { STB_GLOBAL } else { STB_LOCAL };
+            write_sym(&mut syms, name, value, bind, STT_NOTYPE, shndx)?;
+            indexes.insert(sym.name, next);
+            next += 1;
+        }
+    }
+
+    // Relocation sections
+    for (i, s) in asm.sections.iter().enumerate().filter(|(_, s)| !s.relocs.is_empty()) {
+        let mut w = EncodeWriter::new(vec![], BE);
+
+        for r in &s.relocs {
+            let sym = match r.target {
+                RelocTarget::Section(n)  => n + 1,
+                RelocTarget::Symbol(sym) => indexes.get(sym).cloned().unwrap_or(0),
+            };
+            w.write_u32(r.offset as u32)?;
+            w.write_u32((sym as u32) << 8 | reloc_type(r) as u32)?;
+            w.write_i32(r.addend as i32)?;
+        }
+
+        shdrs.push(Shdr {
+            name:    string(&mut names, &format!(".rela{}", s.name)),
+            kind:    SHT_RELA,
+            flags:   SHF_INFO_LINK,
+            offset:  place(&mut body, w.get_ref()),
+            size:    w.get_ref().len() as u32,
+            link:    symtab,
+            info:    i as u32 + 1,
+            align:   4,
+            entsize: RELA_SIZE,
+        });
+    }
+
+    shdrs.push(Shdr {
+        name:    string(&mut names, ".symtab"),
+        kind:    SHT_SYMTAB,
+        offset:  place(&mut body, syms.get_ref()),
+        size:    syms.get_ref().len() as u32,
+        link:    symtab + 1,
+        info:    first as u32,
+        align:   4,
+        entsize: SYM_SIZE,
+        ..Shdr::default()
+    });
+
+    shdrs.push(Shdr {
+        name:   string(&mut names, ".strtab"),
+        kind:   SHT_STRTAB,
+        offset: place(&mut body, &strs),
+        size:   strs.len() as u32,
+        align:  1,
+        ..Shdr::default()
+    });
+
+    let name = string(&mut names, ".shstrtab");
+    shdrs.push(Shdr {
+        name,
+        kind:   SHT_STRTAB,
+        offset: place(&mut body, &names),
+        size:   names.len() as u32,
+        align:  1,
+        ..Shdr::default()
+    });
+
+    // Section headers follow the contents
+    let shoff = place(&mut body, &[]);
+
+    let mut w = EncodeWriter::new(out, BE);
+
+    w.write_bytes(&[0x7F, b'E', b'L', b'F', ELFCLASS32, ELFDATA2MSB, EV_CURRENT])?;
+    w.write_bytes(&[0; 9])?;
+    w.write_u16(ET_REL)?;
+    w.write_u16(EM_68K)?;
+    w.write_u32(EV_CURRENT as u32)?;
+    w.write_u32(0)?;                            // entry
+    w.write_u32(0)?;                            // program headers
+    w.write_u32(shoff)?;
+    w.write_u32(elf_flags(isa))?;
+    w.write_u16(EHDR_SIZE as u16)?;
+    w.write_u16(0)?;                            // program header size
+    w.write_u16(0)?;                            // program header count
+    w.write_u16(SHDR_SIZE as u16)?;
+    w.write_u16(shdrs.len() as u16)?;
+    w.write_u16(shdrs.len() as u16 - 1)?;       // .shstrtab
+
+    w.write_bytes(&body)?;
+
+    for h in &shdrs {
+        for &v in &[h.name, h.kind, h.flags, 0, h.offset, h.size, h.link, h.info, h.align, h.entsize] {
+            w.write_u32(v)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Returns the value and section index of the given symbol, or `None` if the
+// symbol is left out.
+fn sym_place(sym: &Symbol, asm: &Assembly) -> Option<(u32, u16)> {
+    match sym.value {
+        Some(ref v) => match v.section {
+            Some(n) => Some((low_bits(&v.int).wrapping_sub(asm.sections[n].addr as u32), n as u16 + 1)),
+            None    => Some((low_bits(&v.int), SHN_ABS)),
+        },
+        None if sym.global => Some((0, SHN_UNDEF)),
+        None               => None,
+    }
+}
+
+// Returns the ELF type of the given relocation.
+fn reloc_type(r: &Reloc) -> u8 {
+    match (r.size, r.pc) {
+        (1, false) => R_68K_8,
+        (2, false) => R_68K_16,
+        (_, false) => R_68K_32,
+        (1, true)  => R_68K_PC8,
+        (2, true)  => R_68K_PC16,
+        (_, true)  => R_68K_PC32,
+    }
+}
+
+fn write_sym<W: EncodeWrite>(w: &mut W, name: u32, value: u32, bind: u8, kind: u8, shndx: u16)
+                            -> io::Result<()> {
+    w.write_u32(name)?;
+    w.write_u32(value)?;
+    w.write_u32(0)?;                            // size
+    w.write_u8(bind << 4 | kind)?;
+    w.write_u8(0)?;                             // visibility
+    w.write_u16(shndx)
+}
+
+// Adds the given string to the given string table, returning its offset.
+fn string(table: &mut Vec<u8>, s: &str) -> u32 {
+    let offset = table.len() as u32;
+    table.extend_from_slice(s.as_bytes());
+    table.push(0);
+    offset
+}
+
+// Appends the given contents to the body of the file, aligned to 4 bytes,
+// returning their offset from the start of the file.
+fn place(body: &mut Vec<u8>, data: &[u8]) -> u32 {
+    let len = (body.len() + 3) & !3;
+    body.resize(len, 0);
+    body.extend_from_slice(data);
+    EHDR_SIZE + len as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aex::asm::{Assembler, Expander};
+    use aex::source::File;
+    use aex::target::cf::CF_A;
+
+    fn u16_at(b: &[u8], i: usize) -> u16 { u16::from_be_bytes([b[i], b[i + 1]]) }
+    fn u32_at(b: &[u8], i: usize) -> u32 { u32::from_be_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]]) }
+
+    fn str_at(b: &[u8], i: usize) -> &str {
+        let len = b[i..].iter().position(|&c| c == 0).unwrap();
+        ::std::str::from_utf8(&b[i..i + len]).unwrap()
+    }
+
+    #[test]
+    fn elf_flags_for_isa() {
+        assert_eq!(elf_flags(CF_A),                    0x02);
+        assert_eq!(elf_flags(CF_A2 | CF_MAC),          0x13);
+        assert_eq!(elf_flags(CF_B | CF_EMAC),          0x25);
+        assert_eq!(elf_flags(CF_C | CF_FPU | CF_EMAC), 0x8066);
+        assert_eq!(elf_flags(CF_C | CF_EMAC_B),        0x36);
+    }
+
+    #[test]
+    fn write_object() {
+        let file = File::new("f", "\
+        .global main, puts
+main:   lea     msg,%a0
+        bsr     puts
+        bsr.s   puts
+        bsr.l   puts
+        lea     msg(%pc,%d0),%a1
+        rts
+        .section .rodata
+msg:    .asciz  \"hi\"
+        .balign 4
+        .word   main
+        .byte   main
+        .bss
+buf:    .space  8
+");
+        let src = Expander::new().expand(&file);
+        let asm = Assembler::new(CF_B).assemble(&src);
+        assert_eq!(asm.messages.to_string(), "");
+
+        let mut obj = vec![];
+        write_elf(&asm, CF_B, &mut obj).unwrap();
+
+        // File header
+        assert_eq!(&obj[..8], &[0x7F, b'E', b'L', b'F', 1, 2, 1, 0]);
+        assert_eq!(u16_at(&obj, 16), ET_REL);
+        assert_eq!(u16_at(&obj, 18), EM_68K);
+        assert_eq!(u32_at(&obj, 36), EF_M68K_CF_ISA_B);
+
+        // Section headers
+        let shoff  = u32_at(&obj, 32) as usize;
+        let shnum  = u16_at(&obj, 48) as usize;
+        let shdr   = |i: usize, field: usize| u32_at(&obj, shoff + i * 40 + field * 4) as usize;
+        let strndx = u16_at(&obj, 50) as usize;
+        let names: Vec<_> = (0..shnum).map(|i| str_at(&obj, shdr(strndx, 4) + shdr(i, 0))).collect();
+        assert_eq!(names, vec![
+            "", ".text", ".rodata", ".bss", ".rela.text", ".rela.rodata", ".symtab", ".strtab", ".shstrtab",
+        ]);
+        assert_eq!(shdr(1, 1), SHT_PROGBITS as usize);
+        assert_eq!(shdr(1, 5), 24);
+        assert_eq!(shdr(1, 8), 2);
+        assert_eq!(&obj[shdr(1, 4)..][..4], &[0x41, 0xF9, 0, 0]);
+        assert_eq!(shdr(2, 5), 7);
+        assert_eq!(shdr(2, 8), 4);
+        assert_eq!(shdr(3, 1), SHT_NOBITS as usize);
+        assert_eq!(shdr(3, 5), 8);
+
+        // Symbols: null, 3 sections, buf, msg, main, puts
+        let symtab = shdr(6, 4);
+        let strtab = shdr(7, 4);
+        assert_eq!(shdr(6, 5) / 16, 8);
+        assert_eq!(shdr(6, 7), 6);
+        let syms: Vec<_> = (4..8)
+            .map(|i| symtab + i * 16)
+            .map(|s| (str_at(&obj, strtab + u32_at(&obj, s) as usize), u32_at(&obj, s + 4), obj[s + 12], u16_at(&obj, s + 14)))
+            .collect();
+        assert_eq!(syms, vec![
+            ("buf",  0, 0x00, 3),
+            ("msg",  0, 0x00, 2),
+            ("main", 0, 0x10, 1),
+            ("puts", 0, 0x10, 0),
+        ]);
+
+        // Relocations: offset, symbol, type, and addend of each
+        let relas = |i: usize| -> Vec<(u32, u32, u8, i32)> {
+            assert_eq!(shdr(i, 6), 6);
+            (0..shdr(i, 5) / 12)
+                .map(|n| shdr(i, 4) + n * 12)
+                .map(|r| (u32_at(&obj, r), u32_at(&obj, r + 4) >> 8, obj[r + 7], u32_at(&obj, r + 8) as i32))
+                .collect()
+        };
+        assert_eq!(shdr(4, 7), 1);
+        assert_eq!(relas(4), vec![
+            ( 2, 2, R_68K_32,   0),                 // lea     msg,%a0
+            ( 8, 7, R_68K_PC16, 0),                 // bsr.w   puts
+            (11, 7, R_68K_PC8,  -1),                // bsr.s   puts
+            (14, 7, R_68K_PC32, 0),                 // bsr.l   puts
+            (21, 2, R_68K_PC8,  1),                 // lea     msg(%pc,%d0),%a1
+        ]);
+        assert_eq!(shdr(5, 7), 2);
+        assert_eq!(relas(5), vec![
+            ( 4, 1, R_68K_16,   0),                 // .word   main
+            ( 6, 1, R_68K_8,    0),                 // .byte   main
+        ]);
+    }
+}
@@ -0,0 +1,246 @@
+// Assembly Expression Evaluation
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use num::{BigInt, ToPrimitive, Zero};
+
+use aex::ast::{BinaryOp, Expr, Node, UnaryOp};
+use aex::message::Messages;
+use aex::source::Source;
+
+use super::{local_ref_place, SrcExpr, SrcId};
+
+/// The value of an assembly expression.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Value {
+    /// Integer value.  For a relocatable value, this is the address of the
+    /// value as currently laid out.
+    pub int: BigInt,
+
+    /// Number of the section to which the value is relative, or `None` if
+    /// the value is absolute.
+    pub section: Option<usize>,
+}
+
+/// An error that prevents evaluating an expression.
+#[derive(Clone, PartialEq, Debug)]
+pub enum EvalError<'a> {
+    /// A symbol is not defined.
+    Undefined(SrcId<'a>),
+
+    /// An operation requires absolute operands.
+    NotAbsolute(Source<'a>),
+
+    /// Division or modulo by zero.
+    DivByZero(Source<'a>),
+
+    /// The expression is not a constant integer expression.
+    NotConstant(Source<'a>),
+}
+
+impl Value {
+    /// Returns an absolute value.
+    #[inline]
+    pub fn abs<V: Into<BigInt>>(v: V) -> Self {
+        Value { int: v.into(), section: None }
+    }
+
+    /// Returns a value relative to the given section.
+    #[inline]
+    pub fn rel<V: Into<BigInt>>(v: V, section: usize) -> Self {
+        Value { int: v.into(), section: Some(section) }
+    }
+
+    /// Returns whether the value is absolute.
+    #[inline]
+    pub fn is_abs(&self) -> bool {
+        self.section.is_none()
+    }
+}
+
+impl<'a> EvalError<'a> {
+    /// Adds a message describing the error to the given collection.
+    pub fn report(&self, messages: &mut Messages<'a>) {
+        match *self {
+            EvalError::Undefined(ref id)  => match local_ref_place(id.name) {
+                Some(place) => messages.err_local_undefined(id.ann, id.name, place),
+                None        => messages.err_sym_undefined(id.ann, id.name),
+            },
+            EvalError::NotAbsolute(s)     => messages.err_not_absolute(s),
+            EvalError::DivByZero(s)       => messages.err_div_by_zero(s),
+            EvalError::NotConstant(s)     => messages.err_expected(s, "constant integer expression"),
+        }
+    }
+}
+
+/// Evaluates the given expression, using the given function to look up the
+/// values of symbols.
+///
+/// A relocatable value may be offset by an absolute value, and two values
+/// relative to the same section may be subtracted to yield an absolute
+/// value.  All other operations require absolute operands.
+///
+pub fn eval<'a, F>(e: &SrcExpr<'a>, lookup: &mut F) -> Result<Value, EvalError<'a>>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    match *e {
+        Expr::Int(ref i) => {
+            Ok(Value::abs(i.value.clone()))
+        },
+        Expr::Id(ref id) => {
+            lookup(id).ok_or(EvalError::Undefined(*id))
+        },
+        Expr::Unary(ref u) => {
+            let v = eval(&u.expr, lookup)?;
+            if !v.is_abs() { return Err(EvalError::NotAbsolute(u.ann)); }
+            match u.op {
+                UnaryOp::Neg => Ok(Value::abs(-v.int)),
+                UnaryOp::Not => Ok(Value::abs(!to_bits(&v.int, u.ann)?)),
+                _            => Err(EvalError::NotConstant(u.ann)),
+            }
+        },
+        Expr::Binary(ref b) => {
+            let l = eval(&b.lhs, lookup)?;
+            let r = eval(&b.rhs, lookup)?;
+            binary(b.op, l, r, b.ann)
+        },
+        _ => {
+            Err(EvalError::NotConstant(*e.ann()))
+        },
+    }
+}
+
+fn binary<'a>(op: BinaryOp, l: Value, r: Value, src: Source<'a>) -> Result<Value, EvalError<'a>> {
+    use aex::ast::BinaryOp::*;
+
+    // Relocatable arithmetic
+    match (op, l.section, r.section) {
+        (Add, Some(s), None) => return Ok(Value::rel(l.int + r.int, s)),
+        (Add, None, Some(s)) => return Ok(Value::rel(l.int + r.int, s)),
+        (Sub, Some(s), None) => return Ok(Value::rel(l.int - r.int, s)),
+        (Sub, Some(a), Some(b)) if a == b => return Ok(Value::abs(l.int - r.int)),
+        (_,   None, None)    => (),
+        _                    => return Err(EvalError::NotAbsolute(src)),
+    }
+
+    let (l, r) = (l.int, r.int);
+
+    let v = match op {
+        Add => l + r,
+        Sub => l - r,
+        Mul => l * r,
+        Div | Mod if r.is_zero() => return Err(EvalError::DivByZero(src)),
+        Div => l / r,
+        Mod => l % r,
+        Shl => l << to_shift(&r, src)?,
+        Shr => l >> to_shift(&r, src)?,
+        And => BigInt::from(to_bits(&l, src)? & to_bits(&r, src)?),
+        Xor => BigInt::from(to_bits(&l, src)? ^ to_bits(&r, src)?),
+        Or  => BigInt::from(to_bits(&l, src)? | to_bits(&r, src)?),
+        Eq  => BigInt::from((l == r) as u8),
+        Ne  => BigInt::from((l != r) as u8),
+        Lt  => BigInt::from((l <  r) as u8),
+        Le  => BigInt::from((l <= r) as u8),
+        Gt  => BigInt::from((l >  r) as u8),
+        Ge  => BigInt::from((l >= r) as u8),
+        _   => return Err(EvalError::NotConstant(src)),
+    };
+
+    Ok(Value::abs(v))
+}
+
+// Bitwise operations are performed on 64-bit values.
+fn to_bits<'a>(v: &BigInt, src: Source<'a>) -> Result<i64, EvalError<'a>> {
+    v.to_i64()
+        .or_else(|| v.to_u64().map(|v| v as i64))
+        .ok_or(EvalError::NotConstant(src))
+}
+
+fn to_shift<'a>(v: &BigInt, src: Source<'a>) -> Result<usize, EvalError<'a>> {
+    match v.to_usize() {
+        Some(n) if n < 256 => Ok(n),
+        _                  => Err(EvalError::NotConstant(src)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aex::asm::{parse, Syntax};
+    use aex::asm::{Operand, Stmt};
+    use aex::source::File;
+
+    fn eval_str<'a>(file: &'a File<'a>) -> Result<Value, EvalError<'a>> {
+        let mut m = Messages::new();
+        let lines = parse(file, Syntax::Att, &mut m);
+        assert!(!m.has_errors(), "{}", m);
+
+        let e = match lines[0].stmt {
+            Some(Stmt::Insn(ref i)) => match i.operands[0] {
+                Operand::Imm(ref e) => e.clone(),
+                _                   => panic!("expected immediate"),
+            },
+            _ => panic!("expected instruction"),
+        };
+
+        eval(&e, &mut |id: &SrcId| match id.name {
+            "a" => Some(Value::abs(10)),
+            "x" => Some(Value::rel(0x100, 0)),
+            "y" => Some(Value::rel(0x140, 0)),
+            "z" => Some(Value::rel(0x200, 1)),
+            _   => None,
+        })
+    }
+
+    fn ok(s: &str) -> Value {
+        let f = File::new("f", format!("x #{}", s));
+        eval_str(&f).unwrap()
+    }
+
+    fn err(s: &str) -> String {
+        let     f = File::new("f", format!("x #{}", s));
+        let mut m = Messages::new();
+        eval_str(&f).unwrap_err().report(&mut m);
+        m.to_string()
+    }
+
+    #[test]
+    fn eval_abs() {
+        assert_eq!(ok("1 + 2 * 3"),        Value::abs(7));
+        assert_eq!(ok("-a / 3"),           Value::abs(-3));
+        assert_eq!(ok("a % 3 << 4"),       Value::abs(16));
+        assert_eq!(ok("0xF0 & ~0x30 ! 1"), Value::abs(0xC1));
+        assert_eq!(ok("a ^ 3"),            Value::abs(9));
+        assert_eq!(ok("(a >= 10) + (a < 10)"), Value::abs(1));
+    }
+
+    #[test]
+    fn eval_rel() {
+        assert_eq!(ok("x + 4"),      Value::rel(0x104, 0));
+        assert_eq!(ok("4 + x - 2"),  Value::rel(0x102, 0));
+        assert_eq!(ok("y - x"),      Value::abs(0x40));
+        assert_eq!(ok("(y - x) * 2"), Value::abs(0x80));
+    }
+
+    #[test]
+    fn eval_errors() {
+        assert_eq!(err("b + 1"),  "f:1:4: E003: Symbol not defined: b\n");
+        assert_eq!(err("x * 2"),  "f:1:4: E004: Operation requires absolute (non-relocatable) operands.\n");
+        assert_eq!(err("z - x"),  "f:1:4: E004: Operation requires absolute (non-relocatable) operands.\n");
+        assert_eq!(err("1 / (a - 10)"), "f:1:4: E005: Division by zero.\n");
+        assert_eq!(err("1.5"),    "f:1:4: E001: Expected: constant integer expression\n");
+    }
+}
@@ -0,0 +1,148 @@
+// Assembly Source Lines
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use aex::ast::{Expr, Id, Node, Reg};
+use aex::source::Source;
+use aex::target::cf::{MacHalf, MacShift};
+
+use super::Site;
+
+/// An expression in assembly source, annotated with its source.
+pub type SrcExpr<'a> = Expr<'a, Source<'a>>;
+
+/// An identifier in assembly source, annotated with its source.
+pub type SrcId<'a> = Id<'a, Source<'a>>;
+
+/// A register in assembly source, annotated with its source.
+pub type SrcReg<'a> = Reg<'a, Source<'a>>;
+
+/// A line of assembly source.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Line<'a> {
+    /// Source of the line, excluding the line terminator.
+    pub src: Source<'a>,
+
+    /// Labels defined at the start of the line.
+    pub labels: Vec<SrcId<'a>>,
+
+    /// Statement following the labels, if any.
+    pub stmt: Option<Stmt<'a>>,
+
+    /// Macro invocations that produced the line, innermost first.
+    pub sites: &'a [Site<'a>],
+}
+
+/// An assembly statement.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Stmt<'a> {
+    /// Machine instruction
+    Insn(Insn<'a>),
+
+    /// Assembler directive
+    Directive(Directive<'a>),
+}
+
+/// A machine instruction in assembly source.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Insn<'a> {
+    /// Instruction name, without size suffix.
+    pub name: SrcId<'a>,
+
+    /// Size suffix, without the leading dot, if any.
+    pub size: Option<&'a str>,
+
+    /// Operands, in source order.
+    pub operands: Vec<Operand<'a>>,
+}
+
+/// An assembler directive in assembly source, like `.long 1, 2`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Directive<'a> {
+    /// Directive name, including the leading dot.
+    pub name: SrcId<'a>,
+
+    /// Arguments, in source order.
+    pub args: Vec<Arg<'a>>,
+}
+
+/// A directive argument in assembly source.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Arg<'a> {
+    /// Expression
+    Expr(SrcExpr<'a>),
+
+    /// String literal, with escapes replaced
+    Str(Vec<u8>, Source<'a>),
+}
+
+impl<'a> Arg<'a> {
+    /// Returns the source of the argument.
+    pub fn src(&self) -> Source<'a> {
+        match *self {
+            Arg::Expr(ref e) => *e.ann(),
+            Arg::Str(_, s)   => s,
+        }
+    }
+}
+
+/// An instruction operand in assembly source.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Operand<'a> {
+    /// Bare expression: an address or branch target
+    Expr(SrcExpr<'a>),
+
+    /// Immediate: `#expr`
+    Imm(SrcExpr<'a>),
+
+    /// Absolute address with explicit size: `expr.w` or `expr.l` (`true`)
+    Abs(SrcExpr<'a>, bool),
+
+    /// Register: `%r`
+    Reg(SrcReg<'a>),
+
+    /// Register half: `%r.u` or `%r.l`
+    RegHalf(SrcReg<'a>, MacHalf),
+
+    /// Register pair: `%r:%r`
+    RegPair(SrcReg<'a>, SrcReg<'a>),
+
+    /// Register list: `%r-%r/%r`.  Each item is a register, or an inclusive
+    /// range of registers from the first to the second.
+    RegList(Vec<(SrcReg<'a>, Option<SrcReg<'a>>)>),
+
+    /// Register indirect: `(%r)`
+    Ind(SrcReg<'a>),
+
+    /// Register indirect with pre-decrement: `-(%r)`
+    PreDec(SrcReg<'a>),
+
+    /// Register indirect with post-increment: `(%r)+`
+    PostInc(SrcReg<'a>),
+
+    /// Register indirect with displacement: `d(%r)`
+    Disp(SrcReg<'a>, SrcExpr<'a>),
+
+    /// Register indirect with scaled index and displacement: `d(%r,%x*s)`
+    DispIdx(SrcReg<'a>, SrcExpr<'a>, SrcReg<'a>, u8),
+
+    /// MAC product shift: `<<` or `>>`
+    Shift(MacShift),
+
+    /// Operand with MAC mask flag: `operand&`
+    Masked(Box<Operand<'a>>),
+}
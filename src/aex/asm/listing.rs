@@ -0,0 +1,177 @@
+// Assembly Listings
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::{self, Write};
+
+use aex::ast::Id;
+use aex::fmt::{Style, ToStyled};
+use aex::source::Source;
+use aex::target::cf::Size;
+
+use super::{low_bits, Assembly, Expansion};
+
+/// Number of generated bytes shown on each line of a listing.
+pub const LISTING_BYTES: usize = 8;
+
+// Width of the bytes column: two hex digits per byte, and a space between
+// each pair of bytes.
+const BYTES_WIDTH: usize = LISTING_BYTES * 5 / 2 - 1;
+
+/// Writes a listing of the given assembly of the given source, in the given
+/// code style, to the given output.
+///
+/// Each line of the listing shows the address and bytes generated by a line
+/// of source, the number of the line in the file, and the text of the line.
+/// Bytes beyond the first `LISTING_BYTES` continue on lines of their own.  A
+/// line produced by macro expansion shows its expanded text, marked with
+/// `+`, and the number of the line that invoked the macro.  A branch
+/// without a size suffix is followed by the size chosen by relaxation.  A
+/// table of symbols follows, with the value, section, and binding of each
+/// symbol.
+///
+pub fn write_listing<S, W>(source: &Expansion, asm: &Assembly, style: &S, out: &mut W)
+                          -> io::Result<()>
+where S: Style<()> + ?Sized, W: Write {
+    for (line, place) in source.lines.iter().zip(&asm.lines) {
+        let section = &asm.sections[place.section];
+        let start   = place.addr.wrapping_sub(section.addr) as usize;
+        let bytes   = section.data.get(start..start + place.len as usize).unwrap_or(&[]);
+
+        // An expanded line is numbered by the line that invoked it
+        let (num, mark, text) = match line.sites.last() {
+            Some(site) => (line_number(site.src), '+', &*line.text),
+            None       => (line_number(line.src), ' ', line.src.as_ref()),
+        };
+
+        let mut rows = bytes.chunks(LISTING_BYTES);
+        match rows.next() {
+            Some(row) => write!(out, "{:08x}  {:<w$}", place.addr, hex(row), w = BYTES_WIDTH)?,
+            None      => write!(out, "{:<w$}", "", w = 10 + BYTES_WIDTH)?,
+        }
+        write!(out, "  {:>5}{} {}", num, mark, text)?;
+        match place.branch {
+            Some(Size::Byte) => writeln!(out, "  | relaxed: .s")?,
+            Some(size)       => writeln!(out, "  | relaxed: .{}", size.suffix())?,
+            None             => writeln!(out)?,
+        }
+
+        for (i, row) in rows.enumerate() {
+            let addr = place.addr.wrapping_add(((i + 1) * LISTING_BYTES) as u64);
+            writeln!(out, "{:08x}  {}", addr, hex(row))?;
+        }
+    }
+
+    writeln!(out)?;
+    writeln!(out, "Symbols:")?;
+
+    for sym in &asm.symbols {
+        let (value, section) = match sym.value {
+            Some(ref v) => (
+                format!("{:08x}", low_bits(&v.int)),
+                v.section.map_or("*ABS*", |n| asm.sections[n].name),
+            ),
+            None => ("????????".to_string(), "*UND*"),
+        };
+
+        writeln!(
+            out, "{} {:<8} {} {}",
+            value, section,
+            if sym.global { 'g' } else { 'l' },
+            Id::new(sym.name).styled(style),
+        )?;
+    }
+
+    Ok(())
+}
+
+// Formats bytes as hex digits, with a space between each pair of bytes.
+fn hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(BYTES_WIDTH);
+    for (i, b) in bytes.iter().enumerate() {
+        if i != 0 && i % 2 == 0 { hex.push(' '); }
+        hex.push_str(&format!("{:02x}", b));
+    }
+    hex
+}
+
+// Returns the number of the line containing the given source.
+fn line_number(src: Source) -> u32 {
+    match src {
+        Source::File { pos, .. } => pos.line,
+        Source::BuiltIn          => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aex::asm::{Assembler, Expander};
+    use aex::fmt::AttStyle;
+    use aex::source::File;
+    use aex::target::cf::CF_A;
+
+    #[test]
+    fn listing() {
+        let file = File::new("f", "\
+        nop
+        .macro  pair a, b
+        moveq   #\\a,%d0
+        moveq   #\\b,%d1
+        .endm
+        .equ    n, 3
+        .global start, ext
+start:  pair    1, n
+        .ascii  \"0123456789\"
+        bne     start
+        bra.s   start
+        .bss
+buf:    .space  2
+");
+        let src = Expander::new().expand(&file);
+        let asm = Assembler::new(CF_A).assemble(&src);
+        assert_eq!(asm.messages.to_string(), "");
+
+        let mut out = vec![];
+        write_listing(&src, &asm, &AttStyle::default(), &mut out).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "\
+00000000  4e71                     1  nop
+                                   2          .macro  pair a, b
+                                   3          moveq   #\\a,%d0
+                                   4          moveq   #\\b,%d1
+                                   5          .endm
+                                   6          .equ    n, 3
+                                   7          .global start, ext
+                                   8  start:  pair    1, n
+00000002  7001                     8+         moveq   #1,%d0
+00000004  7203                     8+         moveq   #n,%d1
+00000006  3031 3233 3435 3637      9          .ascii  \"0123456789\"
+0000000e  3839
+00000010  66f0                    10          bne     start  | relaxed: .s
+00000012  60ee                    11          bra.s   start
+                                  12          .bss
+00000000  0000                    13  buf:    .space  2
+
+Symbols:
+00000000 .bss     l buf
+???????? *UND*    g ext
+00000003 *ABS*    l n
+00000002 .text    g start
+");
+    }
+}
@@ -0,0 +1,179 @@
+// Assembly Local Labels
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+use super::Value;
+
+/// Prefix of a scoped local label name.
+pub const SCOPED_PREFIX: &str = ".L";
+
+/// A table of local labels, which are not symbols of the object file.
+///
+/// * A numeric label, like `1:`, may be defined any number of times.  A
+///   reference `1b` is to the nearest definition at or before the line
+///   of the reference, and `1f` to the nearest definition after it.
+///
+/// * A scoped label, like `.Lloop:`, is visible only from the lines
+///   between the non-local labels around its definition.
+///
+#[derive(Clone, Default, Debug)]
+pub struct LocalLabels<'a> {
+    // Definitions of each numeric label, by line index, in line order
+    numeric: HashMap<&'a str, Vec<(usize, Option<Value>)>>,
+
+    // Definitions of scoped labels, by scope and name
+    scoped: HashMap<(&'a str, &'a str), Option<Value>>,
+}
+
+impl<'a> LocalLabels<'a> {
+    /// Creates an empty table of local labels.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defines a local label with the given name on the line with the given
+    /// index, with no value.  Lines must be defined in order.  Returns
+    /// `false` if a scoped label is already defined in the given scope.
+    pub fn define(&mut self, name: &'a str, line: usize, scope: &'a str) -> bool {
+        if name.starts_with(SCOPED_PREFIX) {
+            return match self.scoped.entry((scope, name)) {
+                Entry::Vacant(e)   => { e.insert(None); true },
+                Entry::Occupied(_) => false,
+            };
+        }
+        self.numeric.entry(name).or_default().push((line, None));
+        true
+    }
+
+    /// Sets the value of the local label with the given name defined on the
+    /// line with the given index and in the given scope.  Returns whether
+    /// the value changed, or `None` if the label is not defined there.
+    pub fn set_value(&mut self, name: &'a str, line: usize, scope: &'a str, value: Value)
+                    -> Option<bool> {
+        let slot = if name.starts_with(SCOPED_PREFIX) {
+            self.scoped.get_mut(&(scope, name))
+        } else {
+            self.numeric.get_mut(name).and_then(|defs| {
+                let i = defs.binary_search_by_key(&line, |d| d.0).ok()?;
+                Some(&mut defs[i].1)
+            })
+        };
+
+        let slot = slot?;
+        if slot.as_ref() == Some(&value) { return Some(false); }
+        *slot = Some(value);
+        Some(true)
+    }
+
+    /// Returns the value of the local label referenced by the given name
+    /// from the line with the given index, if defined and known.
+    pub fn get(&self, name: &'a str, line: usize, scope: &'a str) -> Option<Value> {
+        if name.starts_with(SCOPED_PREFIX) {
+            return self.scoped.get(&(scope, name))?.clone();
+        }
+
+        let (num, back) = numeric_ref(name)?;
+        let defs        = self.numeric.get(num)?;
+        let n           = defs.partition_point(|d| d.0 <= line);
+
+        let def = if back { defs.get(n.checked_sub(1)?) } else { defs.get(n) };
+        def?.1.clone()
+    }
+}
+
+/// Returns whether the given label name defines a local label.
+pub fn is_local_label(name: &str) -> bool {
+    name.starts_with(SCOPED_PREFIX)
+        || (!name.is_empty() && name.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Returns where a definition of the local label referenced by the given
+/// name must be, or `None` if the name does not reference a local label.
+pub fn local_ref_place(name: &str) -> Option<&'static str> {
+    if name.starts_with(SCOPED_PREFIX) {
+        return Some("in this scope");
+    }
+    match numeric_ref(name)? {
+        (_, true)  => Some("before this line"),
+        (_, false) => Some("after this line"),
+    }
+}
+
+// Splits a numeric local label reference, like `1b`, into the label name
+// and whether the reference is backward.
+fn numeric_ref(name: &str) -> Option<(&str, bool)> {
+    let (num, dir) = name.split_at(name.len().checked_sub(1)?);
+    if !is_local_label(num) || num.starts_with(SCOPED_PREFIX) { return None; }
+    match dir {
+        "b" => Some((num, true)),
+        "f" => Some((num, false)),
+        _   => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_labels() {
+        let mut t = LocalLabels::new();
+
+        assert!( t.define("1",      2, ""));
+        assert!( t.define(".Lloop", 3, "f"));
+        assert!( t.define("1",      5, "f"));
+        assert!( t.define(".Lloop", 6, "g"));
+        assert!(!t.define(".Lloop", 7, "g"));
+
+        assert_eq!(t.get("1b", 4, "f"), None);
+        assert_eq!(t.set_value("1",      2, "",  Value::rel(0x10, 0)), Some(true));
+        assert_eq!(t.set_value("1",      2, "",  Value::rel(0x10, 0)), Some(false));
+        assert_eq!(t.set_value("1",      5, "f", Value::rel(0x20, 0)), Some(true));
+        assert_eq!(t.set_value(".Lloop", 3, "f", Value::rel(0x14, 0)), Some(true));
+        assert_eq!(t.set_value(".Lloop", 3, "h", Value::rel(0x14, 0)), None);
+        assert_eq!(t.set_value("1",      3, "",  Value::rel(0x14, 0)), None);
+
+        assert_eq!(t.get("1b",     1, ""),  None);
+        assert_eq!(t.get("1f",     1, ""),  Some(Value::rel(0x10, 0)));
+        assert_eq!(t.get("1b",     2, ""),  Some(Value::rel(0x10, 0)));
+        assert_eq!(t.get("1f",     2, ""),  Some(Value::rel(0x20, 0)));
+        assert_eq!(t.get("1b",     5, "f"), Some(Value::rel(0x20, 0)));
+        assert_eq!(t.get("1f",     5, "f"), None);
+        assert_eq!(t.get("2b",     5, "f"), None);
+        assert_eq!(t.get(".Lloop", 4, "f"), Some(Value::rel(0x14, 0)));
+        assert_eq!(t.get(".Lloop", 7, "g"), None);
+        assert_eq!(t.get(".Lloop", 1, ""),  None);
+    }
+
+    #[test]
+    fn local_names() {
+        assert!( is_local_label("12"));
+        assert!( is_local_label(".Lx"));
+        assert!(!is_local_label("x1"));
+        assert!(!is_local_label(""));
+
+        assert_eq!(local_ref_place("12b"), Some("before this line"));
+        assert_eq!(local_ref_place("1f"),  Some("after this line"));
+        assert_eq!(local_ref_place(".Lx"), Some("in this scope"));
+        assert_eq!(local_ref_place("1"),   None);
+        assert_eq!(local_ref_place("b"),   None);
+        assert_eq!(local_ref_place("x1b"), None);
+    }
+}
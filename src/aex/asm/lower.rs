@@ -0,0 +1,831 @@
+// Assembly Lowering
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+// Lowering translates source operands into the operands of a particular
+// opcode, as directed by its operand forms.  Expressions are evaluated
+// first, so that lowering sees only constants, except branch targets in
+// other sections, which relocations supply.
+
+use num::{BigInt, ToPrimitive};
+
+use aex::ast::{Binary, Expr, Float, Id, Int, Node, Unary, UnaryOp};
+use aex::io::EncodeWriter;
+use aex::message::Messages;
+use aex::source::Source;
+use aex::target::cf::{self, AddrReg, Condition, CtlReg, DataReg, FpCondition, FpReg, FpRegList, MacReg};
+use aex::target::cf::{EncodeError, Encoder, Index, IndexReg, Mnemonic, Mode, Opcode};
+use aex::target::cf::{OperandForm, RegList, Scale, Size, EXT_WORD};
+use aex::util::BE;
+
+use super::{eval, EvalError, Insn, Operand, SrcExpr, SrcId, SrcReg, Value};
+
+/// An error that prevents lowering an instruction.
+#[derive(Debug)]
+pub enum LowerError<'a> {
+    /// An operand expression cannot be evaluated.
+    Eval(EvalError<'a>),
+
+    /// A register name is not recognized.
+    UnknownReg(SrcReg<'a>),
+
+    /// No opcode accepts the operands.
+    Encode(EncodeError),
+}
+
+/// A condition folded into an instruction name, like `eq` in `beq` or
+/// `fbeq`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NameCond {
+    /// Integer condition of a `bcc` or `scc`.
+    Int(Condition),
+
+    /// Floating-point condition of an `fbcc`.
+    Fp(FpCondition),
+}
+
+/// The location of an instruction: its address and the number of its output
+/// section.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Location {
+    /// Address of the instruction.
+    pub addr: u64,
+
+    /// Number of the output section that contains the instruction.
+    pub section: usize,
+}
+
+/// A field of an encoded instruction that holds the value of a source
+/// operand.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Field {
+    /// Index of the source operand.
+    pub operand: usize,
+
+    /// Offset of the field from the start of the instruction.
+    pub offset: u64,
+
+    /// Size of the field in bytes.
+    pub size: u8,
+
+    /// For a PC-relative field, the offset from the start of the instruction
+    /// of the address to which the field is relative.
+    pub pc: Option<u64>,
+}
+
+impl<'a> From<EvalError<'a>> for LowerError<'a> {
+    fn from(e: EvalError<'a>) -> Self {
+        LowerError::Eval(e)
+    }
+}
+
+impl<'a> LowerError<'a> {
+    /// Adds a message describing the error to the given collection.  `src`
+    /// is the source of the instruction.
+    pub fn report(&self, src: Source<'a>, messages: &mut Messages<'a>) {
+        match *self {
+            LowerError::Eval(ref e)       => e.report(messages),
+            LowerError::UnknownReg(ref r) => messages.err_unknown_reg(r.ann, r.name),
+            LowerError::Encode(ref e)     => messages.err_encode(src, e),
+        }
+    }
+}
+
+/// Sizes tried, in order, for an instruction without a size suffix.
+static SIZES: [Size; 6] = [
+    Size::Zero, Size::Long, Size::Word, Size::Byte, Size::Double, Size::Single
+];
+
+/// Returns the instruction with the given name, and the condition folded
+/// into the name, if any.  A `bcc`, `scc`, or `fbcc` is written with its
+/// condition in place of `cc`, like `beq`, `sne`, or `fbogt`.
+pub fn resolve_name(name: &str) -> Option<(Mnemonic, Option<NameCond>)> {
+    if name.len() > 1 {
+        let (op, cond) = name.split_at(1);
+        let cond       = Condition::with_name(cond);
+
+        match (op, cond) {
+            ("b", Some(c)) | ("B", Some(c)) if c.encode() > 1 => return Some((Mnemonic::Bcc, Some(NameCond::Int(c)))),
+            ("s", Some(c)) | ("S", Some(c))                   => return Some((Mnemonic::Scc, Some(NameCond::Int(c)))),
+            _                                                 => (),
+        }
+    }
+
+    if name.len() > 2 && name[..2].eq_ignore_ascii_case("fb") {
+        if let Some(c) = FpCondition::with_name(&name[2..]) {
+            return Some((Mnemonic::Fbcc, Some(NameCond::Fp(c))));
+        }
+    }
+
+    Mnemonic::with_name(name).map(|m| (m, None))
+}
+
+/// Returns the size denoted by the given suffix for the given instruction.
+/// The suffix `s` denotes a short branch or a single-precision operation.
+pub fn resolve_size(name: Mnemonic, suffix: &str) -> Option<Size> {
+    match suffix {
+        "b" | "B" => Some(Size::Byte),
+        "w" | "W" => Some(Size::Word),
+        "l" | "L" => Some(Size::Long),
+        "d" | "D" => Some(Size::Double),
+        "s" | "S" if is_branch(name) => Some(Size::Byte),
+        "s" | "S" => Some(Size::Single),
+        _         => None,
+    }
+}
+
+/// Returns whether the given instruction is a PC-relative branch, whose
+/// size can be chosen by branch relaxation.
+#[inline]
+pub fn is_branch(name: Mnemonic) -> bool {
+    matches!(name, Mnemonic::Bra | Mnemonic::Bsr | Mnemonic::Bcc | Mnemonic::Fbcc)
+}
+
+/// Returns the displacement of a branch at the given address to the given
+/// target: the target relative to the address plus 2.  Addresses are 32
+/// bits, so the displacement wraps around the address space.
+pub fn branch_disp(target: &BigInt, addr: u64) -> BigInt {
+    let pc = addr.wrapping_add(2);
+    match target.to_i64().map(|t| t as u64).or_else(|| target.to_u64()) {
+        Some(t) => BigInt::from(t.wrapping_sub(pc) as u32 as i32),
+        None    => target - BigInt::from(pc),
+    }
+}
+
+/// Returns whether the given name is a register name.
+#[inline]
+pub fn is_reg_name(name: &str) -> bool {
+    reg_operand(name).is_some()
+}
+
+/// Encodes the given instruction at the given location.
+///
+/// If `size` is `None`, each size is tried in turn, and the first that
+/// encodes is used.  Symbols are looked up with the given function.  A
+/// PC-relative operand whose value lies in another section is encoded as a
+/// zero displacement, for a relocation to supply.
+///
+pub fn encode_insn<'a, F>(encoder:  &Encoder,
+                          insn:     &Insn<'a>,
+                          name:     Mnemonic,
+                          cond:     Option<NameCond>,
+                          size:     Option<Size>,
+                          at:       Location,
+                          lookup:   &mut F)
+                         -> Result<Vec<u8>, LowerError<'a>>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    encode_insn_fields(encoder, insn, name, cond, size, at, lookup).map(|(bytes, _)| bytes)
+}
+
+/// Encodes the given instruction like `encode_insn`, also returning the
+/// fields of the encoding that hold the values of source operands.  An
+/// operand encoded within the opcode word, like a quick immediate, has no
+/// field.
+pub fn encode_insn_fields<'a, F>(encoder:  &Encoder,
+                                 insn:     &Insn<'a>,
+                                 name:     Mnemonic,
+                                 cond:     Option<NameCond>,
+                                 size:     Option<Size>,
+                                 at:       Location,
+                                 lookup:   &mut F)
+                                -> Result<(Vec<u8>, Vec<Field>), LowerError<'a>>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    // PC-relative displacements are relative to the first extension word,
+    // which follows the second opcode word, if any.
+    let branch = is_branch(name);
+    let args2  = resolve_all(&insn.operands, at, 2, branch, lookup)?;
+    let args4  = resolve_all(&insn.operands, at, 4, branch, lookup)?;
+
+    let args_for = |o: &Opcode| if o.flags & EXT_WORD != 0 { &args4 } else { &args2 };
+
+    let encode = |size| encoder.encode_opcode_with(name, size, |o| lower(o, cond, args_for(o)))
+        .map(|(bytes, o)| (bytes, fields(o, cond, args_for(o))));
+
+    if let Some(size) = size {
+        return encode(size).map_err(LowerError::Encode);
+    }
+
+    let mut error = None;
+
+    for &size in &SIZES {
+        if encoder.opcodes(name, size).next().is_none() { continue; }
+
+        match encode(size) {
+            Ok(bytes) => return Ok(bytes),
+            Err(e)    => if error.is_none() { error = Some(e); },
+        }
+    }
+
+    Err(LowerError::Encode(error.unwrap_or(EncodeError::NoOpcode(name, Size::Zero))))
+}
+
+// -----------------------------------------------------------------------------
+// Resolution: evaluate expressions and check register names
+
+// Resolves the operands of an instruction at the given location, with
+// PC-relative displacements relative to the given offset from it.  The
+// target of a branch, its last operand, resolves to its displacement.
+fn resolve_all<'a, F>(ops: &[Operand<'a>], at: Location, pc: u64, branch: bool, lookup: &mut F)
+                     -> Result<Vec<Operand<'a>>, LowerError<'a>>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    let pc = at.addr.wrapping_add(pc);
+
+    ops.iter().enumerate().map(|(i, op)| match *op {
+        Operand::Expr(ref e) if branch && i + 1 == ops.len() => {
+            Ok(Operand::Expr(branch_target(e, at, lookup)?))
+        },
+        _ => resolve(op, pc, at.section, lookup),
+    }).collect()
+}
+
+fn resolve<'a, F>(op: &Operand<'a>, pc: u64, section: usize, lookup: &mut F)
+                 -> Result<Operand<'a>, LowerError<'a>>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    Ok(match *op {
+        Operand::Expr(ref e) => {
+            Operand::Expr(value(e, lookup)?)
+        },
+        Operand::Imm(ref e) => {
+            Operand::Imm(match float(e) { Some(x) => x, None => value(e, lookup)? })
+        },
+        Operand::Abs(ref e, long) => {
+            Operand::Abs(value(e, lookup)?, long)
+        },
+        Operand::Reg(ref r)        |
+        Operand::RegHalf(ref r, _) |
+        Operand::Ind(ref r)        |
+        Operand::PreDec(ref r)     |
+        Operand::PostInc(ref r)    => {
+            check_reg(r)?;
+            op.clone()
+        },
+        Operand::RegPair(ref a, ref b) => {
+            check_reg(a)?;
+            check_reg(b)?;
+            op.clone()
+        },
+        Operand::RegList(ref items) => {
+            for (a, b) in items {
+                check_reg(a)?;
+                if let Some(b) = b { check_reg(b)?; }
+            }
+            op.clone()
+        },
+        Operand::Disp(ref r, ref e) => {
+            check_reg(r)?;
+            Operand::Disp(*r, disp(r, e, pc, section, lookup)?)
+        },
+        Operand::DispIdx(ref r, ref e, ref x, scale) => {
+            check_reg(r)?;
+            check_reg(x)?;
+            Operand::DispIdx(*r, disp(r, e, pc, section, lookup)?, *x, scale)
+        },
+        Operand::Shift(_) => {
+            op.clone()
+        },
+        Operand::Masked(ref op) => {
+            Operand::Masked(Box::new(resolve(op, pc, section, lookup)?))
+        },
+    })
+}
+
+fn value<'a, F>(e: &SrcExpr<'a>, lookup: &mut F) -> Result<SrcExpr<'a>, EvalError<'a>>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    let v = eval(e, lookup)?;
+    Ok(Expr::Int(Int::new_with_ann(v.int, *e.ann())))
+}
+
+// A displacement from the PC to a relocatable address is computed here; an
+// absolute displacement is used as-is.  An address in another section is
+// unknown until link time, so its displacement is left zero.
+fn disp<'a, F>(base: &SrcReg<'a>, e: &SrcExpr<'a>, pc: u64, section: usize, lookup: &mut F)
+              -> Result<SrcExpr<'a>, EvalError<'a>>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    let v = eval(e, lookup)?;
+    let d = match v.section {
+        Some(s) if is_pc(base) && s == section => v.int - BigInt::from(pc),
+        Some(_) if is_pc(base)                 => BigInt::from(0),
+        _                                      => v.int,
+    };
+    Ok(Expr::Int(Int::new_with_ann(d, *e.ann())))
+}
+
+// Returns the displacement of a branch at the given location to the given
+// target.  A target in another section is unknown until link time, so it
+// remains unresolved.
+fn branch_target<'a, F>(e: &SrcExpr<'a>, at: Location, lookup: &mut F)
+                       -> Result<SrcExpr<'a>, EvalError<'a>>
+where F: FnMut(&SrcId<'a>) -> Option<Value> {
+    let v = eval(e, lookup)?;
+    match v.section {
+        Some(s) if s != at.section => Ok(e.clone()),
+        _                          => Ok(Expr::Int(Int::new_with_ann(branch_disp(&v.int, at.addr), *e.ann()))),
+    }
+}
+
+fn float<'a>(e: &SrcExpr<'a>) -> Option<SrcExpr<'a>> {
+    match *e {
+        Expr::Float(_) => {
+            Some(e.clone())
+        },
+        Expr::Unary(ref u) if u.op == UnaryOp::Neg => match *u.expr {
+            Expr::Float(ref x) => Some(Expr::Float(Float::new_with_ann(-x.value, u.ann))),
+            _                  => None,
+        },
+        _ => None,
+    }
+}
+
+fn check_reg<'a>(r: &SrcReg<'a>) -> Result<(), LowerError<'a>> {
+    if is_reg_name(r.name) { Ok(()) } else { Err(LowerError::UnknownReg(*r)) }
+}
+
+#[inline]
+fn is_pc(r: &SrcReg) -> bool {
+    r.name.eq_ignore_ascii_case("pc")
+}
+
+// -----------------------------------------------------------------------------
+// Lowering: translate resolved operands into the forms of an opcode
+
+fn lower<'a>(o: &Opcode, cond: Option<NameCond>, args: &[Operand<'a>])
+            -> Result<Vec<cf::Operand<'a>>, EncodeError> {
+    Ok(lower_indexed(o, cond, args)?.into_iter().map(|(op, _)| op).collect())
+}
+
+// Lowers operands like `lower`, pairing each with the index of the source
+// operand from which it was lowered, if any.
+fn lower_indexed<'a>(o: &Opcode, cond: Option<NameCond>, args: &[Operand<'a>])
+                    -> Result<Vec<(cf::Operand<'a>, Option<usize>)>, EncodeError> {
+    let mut forms = o.args.as_slice().iter();
+    let mut args  = args.iter().enumerate().peekable();
+    let mut out   = vec![];
+
+    // Condition folded into the name
+    if let Some(c) = cond {
+        forms.next();
+        out.push((match c {
+            NameCond::Int(c) => cf::Operand::Cond(c),
+            NameCond::Fp(c)  => cf::Operand::FpCond(c),
+        }, None));
+    }
+
+    for &form in forms {
+        // Omitted MAC shift factor
+        if let OperandForm::MacShift(_) = form {
+            match args.peek() {
+                Some(&(_, &Operand::Shift(_))) => (),
+                _ => {
+                    out.push((cf::Operand::MacShift(cf::MacShift::None), None));
+                    continue;
+                },
+            }
+        }
+
+        let (i, arg) = args.next().ok_or(EncodeError::Mismatch)?;
+        out.push((lower_form(form, arg)?, Some(i)));
+    }
+
+    if args.next().is_some() {
+        return Err(EncodeError::Mismatch);
+    }
+
+    Ok(out)
+}
+
+fn lower_form<'a>(form: OperandForm, arg: &Operand<'a>)
+                 -> Result<cf::Operand<'a>, EncodeError> {
+    match (form, arg) {
+        (OperandForm::Branch8(_), &Operand::Expr(ref e)) |
+        (OperandForm::Branch16,   &Operand::Expr(ref e)) |
+        (OperandForm::Branch32,   &Operand::Expr(ref e)) => {
+            // Resolved to the displacement already, unless in another section
+            Ok(cf::Operand::Branch(symbolic(e)?))
+        },
+        (OperandForm::FpCond(_), Operand::Expr(e)) => {
+            match int(e)?.to_u8() {
+                Some(c) if c < 0x20 => Ok(cf::Operand::FpCond(FpCondition::decode(c))),
+                _                   => Err(EncodeError::Mismatch),
+            }
+        },
+        (OperandForm::FpRegList, _) => {
+            Ok(cf::Operand::FpRegList(fp_reg_list(arg)?))
+        },
+        (OperandForm::CtlReg(_), Operand::Reg(r)) => {
+            // Some control registers share names with other registers
+            CtlReg::with_name(r.name).map(cf::Operand::CtlReg).ok_or(EncodeError::Mismatch)
+        },
+        (OperandForm::RegList, Operand::Reg(r)) => {
+            Ok(cf::Operand::RegList(RegList(1 << reg_bit(r)?)))
+        },
+        (OperandForm::RegList, Operand::Imm(e)) => {
+            Ok(cf::Operand::RegList(RegList(int(e)?.to_u16().ok_or(EncodeError::Mismatch)?)))
+        },
+        (OperandForm::MacMem(..), _) => {
+            match lower_operand(arg)? {
+                cf::Operand::Mode(m) => Ok(cf::Operand::MaskedMode(m, false)),
+                op                   => Ok(op),
+            }
+        },
+        _ => {
+            lower_operand(arg)
+        },
+    }
+}
+
+fn lower_operand<'a>(arg: &Operand<'a>) -> Result<cf::Operand<'a>, EncodeError> {
+    use aex::target::cf::Operand as Op;
+
+    Ok(match *arg {
+        Operand::Expr(ref e)      => Op::Mode(Mode::Abs32(plain(e)?)),
+        Operand::Imm(ref e)       => Op::Mode(Mode::Imm(plain(e)?)),
+        Operand::Abs(ref e, true) => Op::Mode(Mode::Abs32(plain(e)?)),
+        Operand::Abs(ref e, _)    => Op::Mode(Mode::Abs16(plain(e)?)),
+
+        Operand::Reg(ref r) => {
+            reg_operand(r.name).ok_or(EncodeError::Mismatch)?
+        },
+        Operand::RegHalf(ref r, half) => {
+            Op::RegHalf(reg_mode(r.name).ok_or(EncodeError::Mismatch)?, half)
+        },
+        Operand::RegPair(ref a, ref b) => {
+            match (DataReg::with_name(a.name), DataReg::with_name(b.name)) {
+                (Some(a), Some(b)) => Op::DataRegPair(a, b),
+                _                  => return Err(EncodeError::Mismatch),
+            }
+        },
+        Operand::RegList(ref items) => {
+            let mut bits = 0;
+            for (first, last) in items {
+                let first = reg_bit(first)?;
+                let last  = match last { Some(r) => reg_bit(r)?, None => first };
+
+                // Registers must be of the same kind (bit 3) and ascending
+                if (first ^ last) & 8 != 0 || first > last {
+                    return Err(EncodeError::Mismatch);
+                }
+
+                for n in first..=last { bits |= 1 << n; }
+            }
+            Op::RegList(RegList(bits))
+        },
+
+        Operand::Ind     (ref r) => Op::Mode(Mode::AddrInd    (addr_reg(r)?)),
+        Operand::PreDec  (ref r) => Op::Mode(Mode::AddrPreDec (addr_reg(r)?)),
+        Operand::PostInc (ref r) => Op::Mode(Mode::AddrPostInc(addr_reg(r)?)),
+
+        Operand::Disp(ref r, ref e) => {
+            if is_pc(r) {
+                Op::Mode(Mode::PcDisp(plain(e)?))
+            } else {
+                Op::Mode(Mode::AddrDisp(addr_reg(r)?, plain(e)?))
+            }
+        },
+        Operand::DispIdx(ref r, ref e, ref x, scale) => {
+            let reg = match (DataReg::with_name(x.name), AddrReg::with_name(x.name)) {
+                (Some(d), _) => IndexReg::Data(d),
+                (_, Some(a)) => IndexReg::Addr(a),
+                _            => return Err(EncodeError::Mismatch),
+            };
+            let scale = Scale::with_size(scale).ok_or(EncodeError::Mismatch)?;
+            let index = Index { reg, scale };
+
+            if is_pc(r) {
+                Op::Mode(Mode::PcIdxDisp(index, plain(e)?))
+            } else {
+                Op::Mode(Mode::AddrIdxDisp(addr_reg(r)?, index, plain(e)?))
+            }
+        },
+
+        Operand::Shift(s) => {
+            Op::MacShift(s)
+        },
+        Operand::Masked(ref arg) => {
+            match lower_operand(arg)? {
+                Op::Mode(m) => Op::MaskedMode(m, true),
+                _           => return Err(EncodeError::Mismatch),
+            }
+        },
+    })
+}
+
+// Returns the fields of the encoding, by the given opcode, of the given
+// operands.  Operand extension words follow the opcode words in operand
+// order.
+fn fields<'a>(o: &Opcode, cond: Option<NameCond>, args: &[Operand<'a>]) -> Vec<Field> {
+    // The operands lowered once already
+    let ops = match lower_indexed(o, cond, args) {
+        Ok(ops) => ops,
+        Err(_)  => return vec![],
+    };
+
+    // PC-relative modes are relative to the first extension word
+    let pc      = if o.flags & EXT_WORD != 0 { 4 } else { 2 };
+    let mut ext = pc;
+    let mut out = vec![];
+
+    for (&form, (op, arg)) in o.args.as_slice().iter().zip(&ops) {
+        let mut words = o.bits;
+        let mut w     = EncodeWriter::new(vec![], BE);
+        if form.encode(op, o.size, &mut words, &mut w).is_err() { break; }
+        let len = w.get_ref().len() as u64;
+
+        let field = match (form, op) {
+            (OperandForm::Branch8(_), _) => Some((1,   1, Some(2))),
+            (OperandForm::Branch16,   _) => Some((ext, 2, Some(2))),
+            (OperandForm::Branch32,   _) => Some((ext, 4, Some(2))),
+            (_, &cf::Operand::Mode(ref m))          |
+            (_, &cf::Operand::MaskedMode(ref m, _)) if len != 0 => match *m {
+                Mode::PcDisp(..)      => Some((ext,     2, Some(pc))),
+                Mode::PcIdxDisp(..)   => Some((ext + 1, 1, Some(pc))),
+                Mode::AddrIdxDisp(..) => Some((ext + 1, 1, None)),
+                Mode::Imm(..) if form != OperandForm::ImmWord && o.size == Size::Byte
+                                      => Some((ext + 1, 1, None)),
+                _                     => Some((ext, len as u8, None)),
+            },
+            _ => None,
+        };
+
+        if let (Some((offset, size, pc)), &Some(operand)) = (field, arg) {
+            out.push(Field { operand, offset, size, pc });
+        }
+
+        ext += len;
+    }
+
+    out
+}
+
+/// Returns the operand denoted by the given register name, if any.  Debug
+/// registers are not named, as no instruction accepts them; their names
+/// remain available for symbols.
+fn reg_operand(name: &str) -> Option<cf::Operand<'static>> {
+    use aex::target::cf::Operand as Op;
+
+    if let Some(m) = reg_mode(name) {
+        return Some(Op::Mode(m));
+    }
+
+    let op = match name.to_ascii_lowercase().as_str() {
+        "ccr"   => Op::Ccr,
+        "sr"    => Op::Sr,
+        "fpcr"  => Op::Fpcr,
+        "fpsr"  => Op::Fpsr,
+        "fpiar" => Op::Fpiar,
+        "dc"    => Op::CacheSel(1),
+        "ic"    => Op::CacheSel(2),
+        "bc"    => Op::CacheSel(3),
+        _       => return FpReg ::with_name(name).map(Op::FpReg)
+               .or_else(|| MacReg::with_name(name).map(Op::MacReg))
+               .or_else(|| CtlReg::with_name(name).map(Op::CtlReg)),
+    };
+
+    Some(op)
+}
+
+/// Returns the register direct mode for the given data or address register
+/// name, if any.
+fn reg_mode(name: &str) -> Option<Mode<'static>> {
+    DataReg::with_name(name).map(Mode::Data).or_else(||
+    AddrReg::with_name(name).map(Mode::Addr))
+}
+
+fn addr_reg(r: &SrcReg) -> Result<AddrReg, EncodeError> {
+    AddrReg::with_name(r.name).ok_or(EncodeError::Mismatch)
+}
+
+// Returns the bit number of the given register in a register list.
+fn reg_bit(r: &SrcReg) -> Result<u8, EncodeError> {
+    DataReg::with_name(r.name).map(|r| r.num()).or_else(||
+    AddrReg::with_name(r.name).map(|r| r.num() + 8))
+        .ok_or(EncodeError::Mismatch)
+}
+
+// Returns the mask of a floating-point register list, which is written as
+// an immediate mask or as registers (bit 7 = fp0, bit 0 = fp7).
+fn fp_reg_list(arg: &Operand) -> Result<FpRegList, EncodeError> {
+    let fp = |r: &SrcReg| FpReg::with_name(r.name).map(|r| r.num()).ok_or(EncodeError::Mismatch);
+
+    match *arg {
+        Operand::Imm(ref e) => {
+            int(e)?.to_u8().map(FpRegList).ok_or(EncodeError::Mismatch)
+        },
+        Operand::Reg(ref r) => {
+            Ok(FpRegList(0x80 >> fp(r)?))
+        },
+        Operand::RegList(ref items) => {
+            let mut bits = 0;
+            for (first, last) in items {
+                let first = fp(first)?;
+                let last  = match last { Some(r) => fp(r)?, None => first };
+                if first > last { return Err(EncodeError::Mismatch); }
+                for n in first..=last { bits |= 0x80 >> n; }
+            }
+            Ok(FpRegList(bits))
+        },
+        _ => Err(EncodeError::Mismatch),
+    }
+}
+
+fn int<'e>(e: &'e SrcExpr) -> Result<&'e BigInt, EncodeError> {
+    match *e {
+        Expr::Int(ref i) => Ok(&i.value),
+        _                => Err(EncodeError::Mismatch),
+    }
+}
+
+// Removes the source annotations from a resolved expression, or from an
+// expression left unresolved for a relocation.
+fn symbolic<'a>(e: &SrcExpr<'a>) -> Result<Expr<'a>, EncodeError> {
+    match *e {
+        Expr::Id    (ref i) => Ok(Expr::Id(Id::new(i.name))),
+        Expr::Unary (ref u) => Ok(Expr::Unary(Unary::new(u.op, symbolic(&u.expr)?))),
+        Expr::Binary(ref b) => Ok(Expr::Binary(Binary::new(b.op, symbolic(&b.lhs)?, symbolic(&b.rhs)?))),
+        _                   => plain(e),
+    }
+}
+
+// Removes the source annotation from a resolved expression.
+fn plain<'a>(e: &SrcExpr<'a>) -> Result<Expr<'a>, EncodeError> {
+    match *e {
+        Expr::Int  (ref i) => Ok(Expr::Int  (Int  ::new(i.value.clone()))),
+        Expr::Float(ref x) => Ok(Expr::Float(Float::new(x.value))),
+        _                  => Err(EncodeError::NotConstant("operand")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aex::target::cf::{CF_A, CF_B, CF_FPU, CF_MAC, OPCODES};
+    use aex::asm::{parse, Stmt, Syntax};
+    use aex::source::File;
+
+    fn encode_at(isa: cf::Flags, addr: u64, text: &str) -> Result<Vec<u8>, String> {
+        encode_fields_at(isa, addr, text).map(|(bytes, _)| bytes)
+    }
+
+    fn encode_fields_at(isa: cf::Flags, addr: u64, text: &str) -> Result<(Vec<u8>, Vec<Field>), String> {
+        let     file  = File::new("f", text);
+        let mut m     = Messages::new();
+        let     lines = parse(&file, Syntax::Att, &mut m);
+        assert!(!m.has_errors(), "{}", m);
+
+        let insn = match lines[0].stmt {
+            Some(Stmt::Insn(ref i)) => i,
+            _                       => panic!("expected instruction"),
+        };
+
+        let (name, cond) = resolve_name(insn.name.name).expect("unknown instruction");
+        let size         = insn.size.map(|s| resolve_size(name, s).expect("bad size"));
+
+        let encoder = Encoder::new(OPCODES, isa);
+        let at      = Location { addr, section: 0 };
+        let result  = encode_insn_fields(&encoder, insn, name, cond, size, at, &mut |id: &SrcId| {
+            match id.name {
+                "here"  => Some(Value::rel(addr, 0)),
+                "there" => Some(Value::rel(0x1010, 0)),
+                "k"     => Some(Value::abs(3)),
+                "ext"   => Some(Value::rel(0x1000, 1)),
+                _       => None,
+            }
+        });
+
+        result.map_err(|e| { e.report(lines[0].src, &mut m); m.to_string() })
+    }
+
+    fn encode(text: &str) -> Vec<u8> {
+        encode_at(CF_A | CF_B | CF_FPU | CF_MAC, 0x1000, text).unwrap()
+    }
+
+    fn fields(text: &str) -> Vec<(usize, u64, u8, Option<u64>)> {
+        encode_fields_at(CF_A | CF_B | CF_FPU | CF_MAC, 0x1000, text).unwrap().1.iter()
+            .map(|f| (f.operand, f.offset, f.size, f.pc))
+            .collect()
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(resolve_name("move"), Some((Mnemonic::Move, None)));
+        assert_eq!(resolve_name("beq"),  Some((Mnemonic::Bcc,  Some(NameCond::Int(Condition::Eq)))));
+        assert_eq!(resolve_name("BHS"),  Some((Mnemonic::Bcc,  Some(NameCond::Int(Condition::Cc)))));
+        assert_eq!(resolve_name("bcc"),  Some((Mnemonic::Bcc,  Some(NameCond::Int(Condition::Cc)))));
+        assert_eq!(resolve_name("st"),   Some((Mnemonic::Scc,  Some(NameCond::Int(Condition::T)))));
+        assert_eq!(resolve_name("fbeq"), Some((Mnemonic::Fbcc, Some(NameCond::Fp(FpCondition::Eq)))));
+        assert_eq!(resolve_name("FBNGLE"), Some((Mnemonic::Fbcc, Some(NameCond::Fp(FpCondition::Ngle)))));
+        assert_eq!(resolve_name("fbcc"), Some((Mnemonic::Fbcc, None)));
+        assert_eq!(resolve_name("bra"),  Some((Mnemonic::Bra,  None)));
+        assert_eq!(resolve_name("bt"),   None);
+        assert_eq!(resolve_name("bogus"), None);
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(resolve_size(Mnemonic::Bra,   "s"), Some(Size::Byte));
+        assert_eq!(resolve_size(Mnemonic::Fmove, "s"), Some(Size::Single));
+        assert_eq!(resolve_size(Mnemonic::Move,  "L"), Some(Size::Long));
+        assert_eq!(resolve_size(Mnemonic::Move,  "x"), None);
+    }
+
+    #[test]
+    fn reg_names() {
+        assert!( is_reg_name("d0"));
+        assert!( is_reg_name("SP"));
+        assert!( is_reg_name("fp7"));
+        assert!( is_reg_name("acc0"));
+        assert!( is_reg_name("vbr"));
+        assert!( is_reg_name("pc"));
+        assert!(!is_reg_name("foo"));
+        assert!(!is_reg_name("csr"));
+    }
+
+    #[test]
+    fn lower_modes() {
+        assert_eq!(encode("move.l 8(%fp),%d0"),            vec![0x20, 0x2E, 0x00, 0x08]);
+        assert_eq!(encode("move.l #k*2+1,(%a1)+"),         vec![0x22, 0xFC, 0, 0, 0, 7]);
+        assert_eq!(encode("lea -4(%pc,%d0*1),%a0"),        vec![0x41, 0xFB, 0x08, 0xFC]);
+        assert_eq!(encode("movem.l %d0-%d7,(%sp)"),        vec![0x48, 0xD7, 0x00, 0xFF]);
+        assert_eq!(encode("movem.l (%sp),%a0"),            vec![0x4C, 0xD7, 0x01, 0x00]);
+        assert_eq!(encode("movec %a1,%rambar1"),           vec![0x4E, 0x7B, 0x9C, 0x05]);
+        assert_eq!(encode("movec %d0,%sr"),                vec![0x4E, 0x7B, 0x08, 0x0E]);
+        assert_eq!(encode("movec %d1,%macsr"),             vec![0x4E, 0x7B, 0x18, 0x04]);
+        assert_eq!(encode("clr.w 0x1234.w"),               vec![0x42, 0x78, 0x12, 0x34]);
+        assert_eq!(encode("fmove.s #1.5,%fp0")[4..].to_vec(), vec![0x3F, 0xC0, 0x00, 0x00]);
+        assert_eq!(encode("fmovem.d %fp0-%fp3/%fp5,(%sp)"), vec![0xF2, 0x17, 0xF0, 0xF4]);
+    }
+
+    #[test]
+    fn lower_unsized() {
+        assert_eq!(encode("nop"),          vec![0x4E, 0x71]);
+        assert_eq!(encode("moveq #-1,d0"), vec![0x70, 0xFF]);
+        assert_eq!(encode("seq %d0"),      vec![0x57, 0xC0]);
+    }
+
+    #[test]
+    fn lower_pc_rel() {
+        // Relocatable target: displacement from the extension word
+        assert_eq!(encode("lea there(%pc),%a0"), vec![0x41, 0xFA, 0x00, 0x0E]);
+        assert_eq!(encode("lea here(%pc),%a0"),  vec![0x41, 0xFA, 0xFF, 0xFE]);
+
+        // Target in another section: left to a relocation
+        assert_eq!(encode("lea ext(%pc),%a0"),       vec![0x41, 0xFA, 0x00, 0x00]);
+        assert_eq!(encode("lea ext(%pc,%d0),%a0"),   vec![0x41, 0xFB, 0x08, 0x00]);
+    }
+
+    #[test]
+    fn lower_branch() {
+        assert_eq!(encode("bne.s there"), vec![0x66, 0x0E]);
+        assert_eq!(encode("bra.w here"),  vec![0x60, 0x00, 0xFF, 0xFE]);
+        assert_eq!(encode("fbcc.w 1,there"), vec![0xF2, 0x81, 0x00, 0x0E]);
+        assert_eq!(encode("fbeq.w there"),   vec![0xF2, 0x81, 0x00, 0x0E]);
+        assert_eq!(encode("fbogt.l there"),  vec![0xF2, 0xC2, 0x00, 0x00, 0x00, 0x0E]);
+        assert_eq!(encode("bsr.s ext"),      vec![0x61, 0x00]);
+        assert_eq!(encode("bra.w ext+4"),    vec![0x60, 0x00, 0x00, 0x00]);
+        assert_eq!(encode("fbeq.l ext"),     vec![0xF2, 0xC1, 0x00, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn lower_fields() {
+        assert_eq!(fields("move.l #here,4(%a0)"),   vec![(0, 2, 4, None), (1, 6, 2, None)]);
+        assert_eq!(fields("move.b #k,(there).w"),   vec![(0, 3, 1, None), (1, 4, 2, None)]);
+        assert_eq!(fields("lea there(%pc,%d0),%a0"), vec![(0, 3, 1, Some(2))]);
+        assert_eq!(fields("bne.s there"),           vec![(0, 1, 1, Some(2))]);
+        assert_eq!(fields("bsr.l there"),           vec![(0, 2, 4, Some(2))]);
+        assert_eq!(fields("moveq #k,%d0"),          vec![]);
+        assert_eq!(fields("mac.l %d0,%d1,4(%a0)&,%a1"), vec![(2, 4, 2, None)]);
+    }
+
+    #[test]
+    fn lower_errors() {
+        assert_eq!(
+            encode_at(CF_A, 0, "moveq #k*100,%d0").unwrap_err(),
+            "f:1:1: E008: Cannot encode instruction: quick immediate 300 out of range (-128 to 127)\n"
+        );
+        assert_eq!(
+            encode_at(CF_A, 0, "move.l %q0,%d0").unwrap_err(),
+            "f:1:8: E007: Unknown register: q0\n"
+        );
+        assert_eq!(
+            encode_at(CF_A, 0, "move.l nowhere,%d0").unwrap_err(),
+            "f:1:8: E003: Symbol not defined: nowhere\n"
+        );
+        assert_eq!(
+            encode_at(CF_A, 0, "lea %d0,%a0").unwrap_err(),
+            "f:1:1: E008: Cannot encode instruction: operands do not match any form of the instruction\n"
+        );
+    }
+}
@@ -0,0 +1,1086 @@
+// Assembly Macro Expansion
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+// Macro expansion is textual, like that of GNU `as`, and happens before
+// lines are parsed.  Conditional assembly happens at the same time, so that
+// conditionals can control macro definitions and expansions, and the
+// reverse.  Each expanded line remembers the line of source from
+// which it came and the macro invocations that produced it, so that
+// diagnostics can point to both.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use num::{BigInt, ToPrimitive};
+
+use aex::ast::Expr;
+use aex::message::{Message, Messages};
+use aex::source::{File, Pos, Source};
+
+use super::{eval, parse_line, split_lines, Arg, SrcId, Stmt, Syntax, Value};
+
+/// Maximum depth of nested macro expansion.
+pub const MAX_DEPTH: usize = 64;
+
+/// Maximum count of a `.rept` repetition.
+pub const MAX_REPEAT: usize = 0x10000;
+
+/// Assembly source after macro expansion.
+#[derive(Clone, Debug)]
+pub struct Expansion<'a> {
+    /// Source file.
+    pub file: &'a File<'a>,
+
+    /// Lines after expansion.  Each line of the file appears in order,
+    /// followed by the lines of any expansion it invokes.
+    pub lines: Vec<ExpandedLine<'a>>,
+
+    /// Syntax of the lines.
+    pub syntax: Syntax,
+
+    /// Symbols defined before the source, with their values.
+    pub defines: Vec<(String, BigInt)>,
+
+    /// Diagnostic messages.
+    pub messages: Messages<'a>,
+}
+
+/// A line of assembly source after macro expansion.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ExpandedLine<'a> {
+    /// Text of the line, with macro arguments substituted.
+    pub text: Cow<'a, str>,
+
+    /// Source of the line in the file, excluding the line terminator.  For
+    /// an expanded line, this is the line of the macro body.
+    pub src: Source<'a>,
+
+    /// Map from offsets in `text` to offsets in `src`, where they differ.
+    pub map: Vec<Segment>,
+
+    /// Macro invocations that produced the line, innermost first.
+    pub sites: Vec<Site<'a>>,
+}
+
+/// A segment of an expanded line, beginning at an offset in the text and
+/// ending at the next segment.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Segment {
+    /// Offset of the segment in the expanded text.
+    pub at: usize,
+
+    /// Offset in the source at which the segment begins.
+    pub start: usize,
+
+    /// Offset in the source at which the segment ends.
+    pub end: usize,
+
+    /// Whether the segment is copied from the source.  Otherwise, the
+    /// segment is substituted for the source.
+    pub literal: bool,
+}
+
+/// A macro invocation.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Site<'a> {
+    /// Source of the invoking line.
+    pub src: Source<'a>,
+
+    /// Name of the macro, or of the repetition directive, invoked.
+    pub name: String,
+}
+
+/// Macro expander.
+#[derive(Clone, Default, Debug)]
+pub struct Expander {
+    /// Syntax of the source, in which `.rept` counts and conditions are
+    /// parsed.
+    pub syntax: Syntax,
+
+    /// Symbols defined before the source, with their values, as by `-D`
+    /// options on a command line.
+    pub defines: Vec<(String, BigInt)>,
+}
+
+// A macro definition.
+#[derive(Debug)]
+struct Macro<'a> {
+    params: Vec<(String, Option<String>)>,
+    body:   Vec<ExpandedLine<'a>>,
+}
+
+// A conditional block whose `.endif` is not yet reached.
+#[derive(Debug)]
+struct Cond<'a> {
+    src:   Source<'a>,  // source of the opening directive
+    outer: bool,        // whether lines around the block are assembled
+    taken: bool,        // whether any branch has been assembled
+    on:    bool,        // whether the current branch is assembled
+    last:  bool,        // whether the current branch is `.else`
+}
+
+// Expansion state.
+struct State<'a> {
+    macros:   HashMap<String, Rc<Macro<'a>>>,
+    consts:   HashMap<String, Value>,
+    defined:  HashSet<String>,
+    conds:    Vec<Cond<'a>>,
+    syntax:   Syntax,
+    count:    usize,
+    lines:    Vec<ExpandedLine<'a>>,
+    messages: Messages<'a>,
+}
+
+// How a sequence of lines ends.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Flow { Done, Exit }
+
+/// Returns the offset in the source of the given offset in an expanded
+/// line, given the line's map.  If `end` is true, the offset is the end of
+/// a range, and maps to the end of any substituted segment before it.
+pub fn source_offset(map: &[Segment], off: usize, end: bool) -> usize {
+    let seg = match map.iter().rev().find(|s| s.at < off || (!end && s.at == off)) {
+        Some(s) => s,
+        None    => return off,
+    };
+
+    match (seg.literal, end) {
+        (true,  _)     => seg.start + (off - seg.at),
+        (false, true)  => seg.end,
+        (false, false) => seg.start,
+    }
+}
+
+impl<'a> ExpandedLine<'a> {
+    fn new(text: &'a str, src: Source<'a>) -> Self {
+        ExpandedLine { text: Cow::Borrowed(text), src, map: vec![], sites: vec![] }
+    }
+
+    // Returns the line with its text truncated to the given length.
+    fn truncate(&self, len: usize) -> Self {
+        let text = match self.text {
+            Cow::Borrowed(s)  => Cow::Borrowed(&s[..len]),
+            Cow::Owned(ref s) => Cow::Owned(s[..len].to_string()),
+        };
+        ExpandedLine { text, src: self.src, map: self.map.clone(), sites: self.sites.clone() }
+    }
+
+    // Returns the line with each `\name` replaced by the value of the given
+    // parameter, `\@` replaced by the given count, and `\()` removed.
+    fn substitute(&self, args: &[(&str, &str)], count: usize, sites: &[Site<'a>]) -> Self {
+        let text     = &*self.text;
+        let mut out  = String::new();
+        let mut map  = vec![];
+        let mut lit  = 0;
+        let mut i    = 0;
+
+        while let Some(n) = text[i..].find('\\') {
+            let at   = i + n;
+            let rest = &text[at + 1..];
+
+            let (value, len) = if rest.starts_with('@') {
+                (Cow::Owned(count.to_string()), 2)
+            } else if rest.starts_with("()") {
+                (Cow::Borrowed(""), 3)
+            } else {
+                let name = &rest[..rest.find(|c| !is_name_char(c)).unwrap_or(rest.len())];
+                match args.iter().find(|a| a.0 == name) {
+                    Some(a) if !name.is_empty() => (Cow::Borrowed(a.1), 1 + name.len()),
+                    _                           => { i = at + 1; continue; },
+                }
+            };
+
+            self.copy(lit, at, &mut out, &mut map);
+            map.push(Segment {
+                at:      out.len(),
+                start:   source_offset(&self.map, at, false),
+                end:     source_offset(&self.map, at + len, true),
+                literal: false,
+            });
+            out.push_str(&value);
+
+            i   = at + len;
+            lit = i;
+        }
+
+        self.copy(lit, text.len(), &mut out, &mut map);
+
+        ExpandedLine { text: Cow::Owned(out), src: self.src, map, sites: sites.to_vec() }
+    }
+
+    // Copies the given range of the text, and its map, to the given output.
+    fn copy(&self, start: usize, end: usize, out: &mut String, map: &mut Vec<Segment>) {
+        if start == end { return; }
+
+        let base = out.len();
+        let seg  = |off: usize| match self.map.iter().rev().find(|s| s.at <= off) {
+            Some(s) if s.literal => Segment { at: base + off - start, start: s.start + (off - s.at), end: 0, literal: true  },
+            Some(s)              => Segment { at: base + off - start, start: s.start, end: s.end,    literal: false },
+            None                 => Segment { at: base + off - start, start: off,     end: 0,        literal: true  },
+        };
+
+        map.push(seg(start));
+        for s in &self.map {
+            if s.at > start && s.at < end { map.push(seg(s.at)); }
+        }
+
+        out.push_str(&self.text[start..end]);
+    }
+}
+
+impl Expander {
+    /// Creates a macro expander for source in AT&T syntax.
+    pub fn new() -> Self {
+        Expander { syntax: Syntax::Att, defines: vec![] }
+    }
+
+    /// Defines a symbol from an option like `-D` on a command line, given
+    /// without the option: `name` to define a symbol with value 1, or
+    /// `name=value` with a decimal or `0x`-prefixed hexadecimal value.
+    /// Returns whether the definition is valid.
+    pub fn define(&mut self, def: &str) -> bool {
+        let (name, value) = match def.find('=') {
+            Some(n) => (&def[..n], parse_int(&def[n + 1..])),
+            None    => (def,       Some(BigInt::from(1))),
+        };
+
+        match value {
+            Some(v) if is_name(name) => { self.defines.push((name.to_string(), v)); true },
+            _                        => false,
+        }
+    }
+
+    /// Expands macros in the given file.
+    ///
+    /// The following directives are recognized:
+    ///
+    /// * `.macro name param, param=default` … `.endm` defines a macro, whose
+    ///   body refers to parameters as `\param`.  An invocation passes
+    ///   arguments by position or as `param=value`.  Within the body, `\@`
+    ///   is the number of macros expanded so far, and `\()` separates a
+    ///   parameter reference from text that follows it.
+    /// * `.exitm` ends the expansion of a macro early.
+    /// * `.rept count` … `.endr` repeats lines.
+    /// * `.irp param, value, value` … `.endr` repeats lines once for each
+    ///   value, and `.irpc param, chars` once for each character.
+    ///
+    /// * `.if expr` … `.elseif expr` … `.else` … `.endif` assembles the
+    ///   lines after the first condition that is nonzero, or those after
+    ///   `.else` if none is.  `.ifdef name` and `.ifndef name` test whether
+    ///   a symbol is defined by a label, `.equ`, or `.set` on an earlier
+    ///   line, or by the expander.  Conditionals may nest.
+    /// * `.error "text"` and `.warning "text"` report a message where they
+    ///   are assembled.
+    ///
+    /// A count or condition must be a constant expression of symbols defined
+    /// by the expander, or by `.equ` or `.set` on earlier lines.
+    ///
+    pub fn expand<'a>(&self, file: &'a File<'a>) -> Expansion<'a> {
+        let mut state = State {
+            macros:   HashMap::new(),
+            consts:   self.defines.iter().map(|d| (d.0.clone(), Value::abs(d.1.clone()))).collect(),
+            defined:  self.defines.iter().map(|d| d.0.clone()).collect(),
+            conds:    vec![],
+            syntax:   self.syntax,
+            count:    0,
+            lines:    vec![],
+            messages: Messages::new(),
+        };
+
+        let lines = split_lines(file).into_iter()
+            .map(|(text, src)| ExpandedLine::new(text, src))
+            .collect::<Vec<_>>();
+
+        state.run(&lines, 0, false);
+
+        Expansion {
+            file,
+            lines:    state.lines,
+            syntax:   self.syntax,
+            defines:  self.defines.clone(),
+            messages: state.messages,
+        }
+    }
+}
+
+impl<'a> State<'a> {
+    // Expands the given lines.  Returns `Flow::Exit` if `.exitm` ends a macro.
+    fn run(&mut self, lines: &[ExpandedLine<'a>], depth: usize, in_macro: bool) -> Flow {
+        let base = self.conds.len();
+        let flow = self.run_lines(lines, depth, in_macro, base);
+
+        // Conditionals end within the lines that begin them
+        let errs = self.messages.as_slice().len();
+        for cond in self.conds.drain(base..) {
+            if flow == Flow::Done { self.messages.err_expected(cond.src, ".endif"); }
+        }
+        if let Some(line) = lines.first() {
+            note_sites(&mut self.messages, errs, &line.sites);
+        }
+
+        flow
+    }
+
+    // Expands the given lines, within which conditionals above the given
+    // depth are open.
+    fn run_lines(&mut self, lines: &[ExpandedLine<'a>], depth: usize, in_macro: bool, base: usize)
+                -> Flow {
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = &lines[i];
+            let errs = self.messages.as_slice().len();
+            i += 1;
+
+            let (labels, start, word, rest) = scan(&line.text);
+            let word_src = span(line.src, &line.map, start, start + word.len());
+            let args     = split_args(rest);
+
+            // Lines produced by the directive or invocation, if any
+            let mut runs = vec![];
+
+            match &*word.to_ascii_lowercase() {
+                ".if" | ".ifdef" | ".ifndef" | ".elseif" | ".else" | ".endif" => {
+                    self.cond(line, word, word_src, &args, labels, base);
+                },
+                _ if !self.is_on() => {
+                    self.placeholders(&lines[i - 1..i]);
+                },
+                ".error" | ".warning" => {
+                    self.report(line, word, word_src);
+                    self.placeholders(&lines[i - 1..i]);
+                },
+                ".macro" => {
+                    let (body, end) = self.block(lines, i, &[".macro"], ".endm", word_src);
+                    self.define(word_src, &args, body);
+                    self.placeholders(&lines[i - 1..end]);
+                    i = end;
+                },
+                ".rept" | ".irp" | ".irpc" => {
+                    let (body, end) = self.block(lines, i, &[".rept", ".irp", ".irpc"], ".endr", word_src);
+                    self.placeholders(&lines[i - 1..end]);
+                    i = end;
+                    runs = self.repeat(line, word, word_src, &args, &body, depth);
+                },
+                ".exitm" if in_macro => {
+                    self.placeholders(&lines[i - 1..i]);
+                    return Flow::Exit;
+                },
+                ".endm" | ".endr" | ".exitm" => {
+                    self.messages.err_unmatched(word_src, word);
+                    self.placeholders(&lines[i - 1..i]);
+                },
+                _ => match self.macros.get(word).cloned() {
+                    Some(m) => {
+                        // Labels remain on the invoking line
+                        let head = line.truncate(labels);
+                        self.track(&head);
+                        self.lines.push(head);
+                        runs = self.invoke(line, word, word_src, &m, &args, depth);
+                    },
+                    None => {
+                        self.track(line);
+                        self.lines.push(line.clone());
+                    },
+                },
+            }
+
+            note_sites(&mut self.messages, errs, &line.sites);
+
+            // An invoked macro ends at .exitm, but a repetition ends only the
+            // macro containing it
+            let is_macro = !word.starts_with('.');
+            for body in runs {
+                if self.run(&body, depth + 1, in_macro || is_macro) == Flow::Exit {
+                    if is_macro { break; }
+                    return Flow::Exit;
+                }
+            }
+        }
+
+        Flow::Done
+    }
+
+    // Returns the lines following the given index up to the matching end
+    // directive, and the index after the end directive.
+    fn block(&mut self, lines: &[ExpandedLine<'a>], start: usize, open: &[&str], close: &str,
+             src: Source<'a>) -> (Vec<ExpandedLine<'a>>, usize) {
+        let mut depth = 0;
+
+        for (i, line) in lines.iter().enumerate().skip(start) {
+            let word = scan(&line.text).2.to_ascii_lowercase();
+            if open.contains(&&*word) {
+                depth += 1;
+            } else if word == close {
+                if depth == 0 { return (lines[start..i].to_vec(), i + 1); }
+                depth -= 1;
+            }
+        }
+
+        self.messages.err_expected(src, close);
+        (lines[start..].to_vec(), lines.len())
+    }
+
+    // Defines a macro.
+    fn define(&mut self, src: Source<'a>, args: &[&str], body: Vec<ExpandedLine<'a>>) {
+        let mut words = args.iter().flat_map(|a| a.split_whitespace());
+
+        let name = match words.next() {
+            Some(n) if is_name(n) => n.to_string(),
+            _                     => return self.messages.err_expected(src, "macro name"),
+        };
+
+        let mut params = vec![];
+        for word in words {
+            let (param, default) = match word.find('=') {
+                Some(n) => (&word[..n], Some(word[n + 1..].to_string())),
+                None    => (word, None),
+            };
+            if !is_name(param) {
+                return self.messages.err_expected(src, "parameter name");
+            }
+            params.push((param.to_string(), default));
+        }
+
+        if self.macros.contains_key(&name) {
+            return self.messages.err_macro_redefined(src, &name);
+        }
+
+        self.macros.insert(name, Rc::new(Macro { params, body }));
+    }
+
+    // Returns the body of the given macro for an invocation.
+    fn invoke(&mut self, line: &ExpandedLine<'a>, name: &str, src: Source<'a>, m: &Macro<'a>,
+              args: &[&str], depth: usize) -> Vec<Vec<ExpandedLine<'a>>> {
+        if depth >= MAX_DEPTH {
+            self.messages.err_macro_depth(src, MAX_DEPTH);
+            return vec![];
+        }
+
+        let mut values: Vec<Option<&str>> = vec![None; m.params.len()];
+
+        for (i, arg) in args.iter().enumerate() {
+            let keyword = arg.find('=')
+                .and_then(|n| m.params.iter().position(|p| p.0 == arg[..n].trim()).map(|p| (p, n)));
+
+            match keyword {
+                Some((p, n))               => values[p] = Some(arg[n + 1..].trim()),
+                None if i < m.params.len() => values[i] = Some(arg),
+                None                       => {
+                    self.messages.err_macro_args(src, name);
+                    return vec![];
+                },
+            }
+        }
+
+        let args: Vec<(&str, &str)> = m.params.iter().zip(values)
+            .map(|(p, v)| (&*p.0, v.or(p.1.as_deref()).unwrap_or("")))
+            .collect();
+
+        let sites = site(line, src, name);
+        let body  = m.body.iter()
+            .map(|l| l.substitute(&args, self.count, &sites))
+            .collect();
+
+        self.count += 1;
+        vec![body]
+    }
+
+    // Returns the bodies produced by a repetition directive.
+    fn repeat(&mut self, line: &ExpandedLine<'a>, word: &str, src: Source<'a>, args: &[&str],
+              body: &[ExpandedLine<'a>], depth: usize) -> Vec<Vec<ExpandedLine<'a>>> {
+        if depth >= MAX_DEPTH {
+            self.messages.err_macro_depth(src, MAX_DEPTH);
+            return vec![];
+        }
+
+        let name = word.to_ascii_lowercase();
+        let runs: Vec<Vec<(&str, String)>> = match &*name {
+            ".rept" => match self.count_arg(line, src) {
+                Some(n) if n > MAX_REPEAT => {
+                    self.messages.err_repeat_count(src, MAX_REPEAT);
+                    return vec![];
+                },
+                Some(n) => vec![vec![]; n],
+                None    => return vec![],
+            },
+            _ => {
+                let param = match args.first() {
+                    Some(p) if is_name(p) => *p,
+                    _                     => {
+                        self.messages.err_expected(src, "parameter name");
+                        return vec![];
+                    },
+                };
+                if name == ".irp" {
+                    args[1..].iter().map(|v| vec![(param, v.to_string())]).collect()
+                } else {
+                    let chars = args.get(1).map_or("", |s| s.trim_matches('"'));
+                    chars.chars().map(|c| vec![(param, c.to_string())]).collect()
+                }
+            },
+        };
+
+        let sites = site(line, src, &name);
+
+        runs.iter().map(|run| {
+            let args: Vec<(&str, &str)> = run.iter().map(|a| (a.0, &*a.1)).collect();
+            body.iter().map(|l| l.substitute(&args, self.count, &sites)).collect()
+        }).collect()
+    }
+
+    // Evaluates the count argument of `.rept`.
+    fn count_arg(&mut self, line: &ExpandedLine<'a>, src: Source<'a>) -> Option<usize> {
+        let errs  = self.messages.error_count();
+        let count = self.const_arg(line).and_then(|v| v.to_usize());
+
+        if count.is_none() && self.messages.error_count() == errs {
+            self.messages.err_expected(src, "constant count");
+        }
+        count
+    }
+
+    // Evaluates the only argument of a directive as a constant expression.
+    fn const_arg(&mut self, line: &ExpandedLine<'a>) -> Option<BigInt> {
+        let stmt   = self.parse(line);
+        let consts = &self.consts;
+        match stmt {
+            Some(Stmt::Directive(ref d)) if d.args.len() == 1 => match d.args[0] {
+                Arg::Expr(ref e) => eval(e, &mut |id: &SrcId| consts.get(id.name).cloned()).ok()
+                    .filter(Value::is_abs)
+                    .map(|v| v.int),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // Returns whether lines at the current point are assembled.
+    fn is_on(&self) -> bool {
+        self.conds.last().is_none_or(|c| c.on)
+    }
+
+    // Handles a conditional directive.  Conditionals above the given depth
+    // are open within the lines being expanded.
+    fn cond(&mut self, line: &ExpandedLine<'a>, word: &str, src: Source<'a>, args: &[&str],
+            labels: usize, base: usize) {
+        let name  = word.to_ascii_lowercase();
+        let outer = match self.conds.last() {
+            Some(c) if self.conds.len() > base && !name.starts_with(".if") => c.outer,
+            _                                                            => self.is_on(),
+        };
+
+        // Labels remain if the lines around the conditional are assembled
+        let head = line.truncate(if outer { labels } else { 0 });
+        if outer { self.track(&head); }
+
+        if name.starts_with(".if") {
+            let on = outer && self.test(line, &name, src, args);
+            self.conds.push(Cond { src, outer, taken: on, on, last: false });
+        } else if self.conds.len() == base {
+            self.messages.err_unmatched(src, word);
+        } else if name == ".endif" {
+            self.conds.pop();
+        } else if self.conds.last().is_some_and(|c| c.last) {
+            self.messages.err_expected(src, ".endif");
+        } else {
+            let taken = self.conds.last().is_some_and(|c| c.taken);
+            let on    = outer && !taken && (name == ".else" || self.test(line, &name, src, args));
+            if let Some(c) = self.conds.last_mut() {
+                c.on     = on;
+                c.taken |= on;
+                c.last   = name == ".else";
+            }
+        }
+
+        self.lines.push(head);
+    }
+
+    // Evaluates the condition of `.if`, `.elseif`, `.ifdef`, or `.ifndef`.
+    fn test(&mut self, line: &ExpandedLine<'a>, name: &str, src: Source<'a>, args: &[&str]) -> bool {
+        if name == ".ifdef" || name == ".ifndef" {
+            return match *args {
+                [sym] if is_name(sym) => self.defined.contains(sym) == (name == ".ifdef"),
+                _                     => { self.messages.err_expected(src, "symbol name"); false },
+            };
+        }
+
+        let errs = self.messages.error_count();
+        match self.const_arg(line) {
+            Some(v) => v != BigInt::from(0),
+            None    => {
+                if self.messages.error_count() == errs {
+                    self.messages.err_expected(src, "constant condition");
+                }
+                false
+            },
+        }
+    }
+
+    // Parses a line consumed by a directive.  Reports any syntax error at its
+    // position in the source.
+    fn parse<'b>(&mut self, line: &'b ExpandedLine<'a>) -> Option<Stmt<'b>> {
+        let mut messages = Messages::new();
+        let stmt = parse_line(line, self.syntax, &mut messages).stmt;
+
+        for m in messages.as_slice() {
+            // The parser derives every source from that of the line
+            let source = match (line.src, m.source) {
+                (Source::File { file, .. }, Source::File { pos, len, .. }) => Source::File { file, pos, len },
+                (src, _)                                                   => src,
+            };
+            self.messages.push(Message { source, ..m.clone() });
+        }
+        stmt
+    }
+
+    // Reports the message of `.error` or `.warning`.
+    fn report(&mut self, line: &ExpandedLine<'a>, word: &str, src: Source<'a>) {
+        let error = word.eq_ignore_ascii_case(".error");
+        let text  = match self.parse(line) {
+            Some(Stmt::Directive(ref d)) => match *d.args {
+                []                   => format!("{} directive", if error { "Error" } else { "Warning" }),
+                [Arg::Str(ref s, _)] => String::from_utf8_lossy(s).into_owned(),
+                _                    => return self.messages.err_expected(src, "string"),
+            },
+            Some(_) => return self.messages.err_expected(src, "string"),
+            None    => return,
+        };
+
+        if error {
+            self.messages.err_user(src, text);
+        } else {
+            self.messages.warn_user(src, text);
+        }
+    }
+
+    // Remembers the symbols defined by a line: its labels, and any symbol
+    // defined by `.equ` or `.set`, with its value if constant.
+    fn track(&mut self, line: &ExpandedLine<'a>) {
+        let (labels, _, word, _) = scan(&line.text);
+
+        for name in line.text[..labels].split(':').map(str::trim).filter(|n| !n.is_empty()) {
+            self.defined.insert(name.to_string());
+        }
+
+        let word = word.to_ascii_lowercase();
+        if word != ".equ" && word != ".set" { return; }
+
+        // Errors are reported when the line itself is parsed for assembly
+        let consts = &mut self.consts;
+        if let Some(Stmt::Directive(ref d)) = parse_line(line, self.syntax, &mut Messages::new()).stmt {
+            if let (Some(Arg::Expr(Expr::Id(id))), Some(Arg::Expr(e))) = (d.args.first(), d.args.get(1)) {
+                self.defined.insert(id.name.to_string());
+                if let Ok(v) = eval(e, &mut |id: &SrcId| consts.get(id.name).cloned()) {
+                    consts.insert(id.name.to_string(), v);
+                }
+            }
+        }
+    }
+
+    // Adds lines consumed by a directive, without their text, so that they
+    // appear in listings but assemble to nothing.
+    fn placeholders(&mut self, lines: &[ExpandedLine<'a>]) {
+        for line in lines {
+            self.lines.push(line.truncate(0));
+        }
+    }
+}
+
+/// Adds a note for each of the given macro invocations, if messages have
+/// been added since the given count.
+pub fn note_sites<'a>(messages: &mut Messages<'a>, since: usize, sites: &[Site<'a>]) {
+    if messages.as_slice().len() == since { return; }
+
+    for site in sites {
+        messages.note_macro_site(site.src, &site.name);
+    }
+}
+
+// Returns the sites of an expansion invoked by the given line.
+fn site<'a>(line: &ExpandedLine<'a>, src: Source<'a>, name: &str) -> Vec<Site<'a>> {
+    let mut sites = vec![Site { src, name: name.to_string() }];
+    sites.extend(line.sites.iter().cloned());
+    sites
+}
+
+/// Returns the source of the given range of a line, given the source of the
+/// line and its map from expanded text.
+pub fn span<'a>(src: Source<'a>, map: &[Segment], start: usize, end: usize) -> Source<'a> {
+    match src {
+        Source::File { file, pos, len } => {
+            let text  = &file.data()[pos.byte..pos.byte + len];
+            let empty = end <= start;
+            let start = source_offset(map, start, false).min(len);
+            let end   = if empty { start } else { source_offset(map, end, true).clamp(start, len) };
+            let pos   = Pos {
+                byte:   pos.byte + start,
+                line:   pos.line,
+                column: pos.column + text.get(..start).map_or(0, |t| t.chars().count()) as u32,
+            };
+            Source::File { file, pos, len: end - start }
+        },
+        src => src,
+    }
+}
+
+// Splits a line into the offset after any labels, the offset of the word
+// that follows, the word, and the text after the word, excluding comments.
+fn scan(text: &str) -> (usize, usize, &str, &str) {
+    if text.starts_with('#') || text.starts_with('*') {
+        return (0, 0, "", "");
+    }
+
+    let mut i = 0;
+    loop {
+        let start = i + (text[i..].len() - text[i..].trim_start().len());
+        let len   = text[start..].find(|c| !is_name_char(c)).unwrap_or(text.len() - start);
+        let word  = &text[start..start + len];
+
+        if !word.is_empty() && text[start + len..].starts_with(':') {
+            i = start + len + 1;
+            continue;
+        }
+
+        let rest = &text[start + len..];
+        let rest = match find_unquoted(rest, '|') {
+            Some(n) => &rest[..n],
+            None    => rest,
+        };
+        return (i, start, word, rest);
+    }
+}
+
+// Splits macro arguments at commas outside of quotes and parentheses.
+fn split_args(text: &str) -> Vec<&str> {
+    let mut args = vec![];
+    let mut rest = text.trim();
+
+    while !rest.is_empty() {
+        let n = find_unquoted(rest, ',').unwrap_or(rest.len());
+        args.push(rest[..n].trim());
+        rest = if n < rest.len() { rest[n + 1..].trim_start() } else { "" };
+    }
+
+    args
+}
+
+// Returns the offset of the first of the given character outside of quotes
+// and parentheses.
+fn find_unquoted(text: &str, target: char) -> Option<usize> {
+    let mut quote = false;
+    let mut depth = 0;
+    let mut chars = text.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if quote          => { chars.next(); },
+            '"'                    => quote = !quote,
+            '(' if !quote          => depth += 1,
+            ')' if !quote          => depth -= 1,
+            c if c == target && !quote && depth <= 0 => return Some(i),
+            _                      => (),
+        }
+    }
+
+    None
+}
+
+// Parses a decimal or `0x`-prefixed hexadecimal integer.
+fn parse_int(s: &str) -> Option<BigInt> {
+    let (neg, s) = match s.strip_prefix('-') {
+        Some(s) => (true,  s),
+        None    => (false, s),
+    };
+
+    let v = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(s) => BigInt::parse_bytes(s.as_bytes(), 16),
+        None    => BigInt::parse_bytes(s.as_bytes(), 10),
+    }?;
+
+    Some(if neg { -v } else { v })
+}
+
+fn is_name(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '.')
+        && s.chars().all(is_name_char)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '$'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(text: &str) -> (String, String) {
+        expand_with(&Expander::new(), text)
+    }
+
+    fn expand_with(expander: &Expander, text: &str) -> (String, String) {
+        let file = File::new("f", text);
+        let exp  = expander.expand(&file);
+        let text = exp.lines.iter()
+            .filter(|l| !l.text.trim().is_empty())
+            .map(|l| format!("{}\n", l.text.trim()))
+            .collect();
+        (text, exp.messages.to_string())
+    }
+
+    #[test]
+    fn expand_macro() {
+        let (text, msgs) = expand("\
+        .macro  push reg, size=l
+        move.\\size \\reg,-(%sp)
+        .endm
+x:      push    %d0
+        push    size=w, reg=%a1
+        nop
+");
+        assert_eq!(msgs, "");
+        assert_eq!(text, "\
+x:
+move.l %d0,-(%sp)
+move.w %a1,-(%sp)
+nop
+");
+    }
+
+    #[test]
+    fn expand_counter_exitm() {
+        let (text, msgs) = expand("\
+        .macro  m n
+l\\@:    .long   \\n
+        .exitm
+        .long   0
+        .endm
+        m       1
+        m       2
+");
+        assert_eq!(msgs, "");
+        assert_eq!(text, "l0:    .long   1\nl1:    .long   2\n");
+    }
+
+    #[test]
+    fn expand_repeat() {
+        let (text, msgs) = expand("\
+        .equ    n, 2
+        .rept   n * 2 - 1
+        nop
+        .endr
+        .irp    r, %d0, %a1
+        clr.l   \\r
+        .endr
+        .irpc   c, ab
+        .byte   '\\c'
+        .endr
+");
+        assert_eq!(msgs, "");
+        assert_eq!(text, "\
+.equ    n, 2
+nop
+nop
+nop
+clr.l   %d0
+clr.l   %a1
+.byte   'a'
+.byte   'b'
+");
+    }
+
+    #[test]
+    fn expand_conditional() {
+        let mut e = Expander::new();
+        assert!( e.define("BOARD=2"));
+        assert!( e.define("DEBUG"));
+        assert!( e.define("BASE=0x100"));
+        assert!(!e.define("2x=1"));
+        assert!(!e.define("X=y"));
+
+        let (text, msgs) = expand_with(&e, "\
+        .if     BOARD == 1
+        .long   1
+        .elseif (BOARD == 2) & (BASE >= 256)
+        .long   2
+        .if     DEBUG
+        .long   3
+        .else
+        .long   4
+        .endif
+        .elseif BOARD == 2
+        .long   5
+        .else
+        .long   6
+        .endif
+start:  .ifdef  start
+        .long   7
+        .endif
+        .ifndef later
+        .long   8
+        .endif
+later:  .ifdef  nope
+        .error  \"skipped\"
+        .if     undefined
+        .endif
+        .endif
+        .warning \"board \\x32\"
+");
+        assert_eq!(msgs, "f:26:9: W022: board 2\n");
+        assert_eq!(text, "\
+.long   2
+.long   3
+start:
+.long   7
+.long   8
+later:
+");
+    }
+
+    #[test]
+    fn expand_conditional_macro() {
+        let (text, msgs) = expand("\
+        .macro  fill n
+        .if     \\n <= 0
+        .exitm
+        .endif
+        .long   \\n
+        fill    \\n-1
+        .endm
+        fill    2
+        .equ    big, 1
+        .rept   2
+        .if     big
+        .long   9
+        .endif
+        .endr
+");
+        assert_eq!(msgs, "");
+        assert_eq!(text, "\
+.long   2
+.long   2-1
+.equ    big, 1
+.long   9
+.long   9
+");
+    }
+
+    #[test]
+    fn expand_conditional_errors() {
+        let (_, msgs) = expand("\
+        .endif
+        .if     1
+        .else
+        .else
+        .endif
+        .if     x
+        .endif
+        .if     (1
+        .endif
+        .ifdef  1
+        .endif
+        .error  \"stop\"
+        .warning 2
+        .macro  m
+        .if     1
+        .endm
+        m
+        .if     0
+");
+        assert_eq!(msgs, "\
+f:1:1: E017: Unmatched directive: .endif
+f:4:9: E001: Expected: .endif
+f:6:9: E001: Expected: constant condition
+f:8:19: E001: Expected: ')'
+f:10:9: E001: Expected: symbol name
+f:12:9: E021: stop
+f:13:9: E001: Expected: string
+f:15:9: E001: Expected: .endif
+f:17:9: N020: In expansion of macro: m
+f:18:9: E001: Expected: .endif
+");
+    }
+
+    #[test]
+    fn expand_nested() {
+        let (text, msgs) = expand("\
+        .macro  outer a
+        .irp    x, 1, 2
+        inner   \\a\\()\\x
+        .endr
+        .endm
+        .macro  inner v
+        .word   \\v
+        .endm
+        outer   7
+");
+        assert_eq!(msgs, "");
+        assert_eq!(text, ".word   71\n.word   72\n");
+    }
+
+    #[test]
+    fn expand_errors() {
+        let (_, msgs) = expand("\
+        .macro  m
+        .endm
+        .macro  m
+        .endm
+        .endr
+        m       1
+        .rept   x
+        .endr
+        .rept   2 *
+        .endr
+        .rept   0xFFFFFFFF
+        .endr
+        .macro  r
+        r
+        .endm
+        r
+        .rept   1
+");
+        assert_eq!(msgs, "\
+f:3:9: E016: Macro already defined: m
+f:5:9: E017: Unmatched directive: .endr
+f:6:9: E018: Too many arguments for macro: m
+f:7:9: E001: Expected: constant count
+f:9:20: E001: Expected: expression
+f:11:9: E025: Repetition count exceeds 65536.
+f:14:9: E019: Macro expansion exceeds 64 levels.
+".to_string() + &"f:14:9: N020: In expansion of macro: r\n".repeat(63)
+              + "f:16:9: N020: In expansion of macro: r\n"
+              + "f:17:9: E001: Expected: .endr\n");
+    }
+
+    #[test]
+    fn source_offsets() {
+        let file = File::new("f", "\t.macro m a\n\tmove.l \\a,%d1\n\t.endm\n\tm %d0\n");
+        let exp  = Expander::new().expand(&file);
+        let line = &exp.lines[4];
+
+        assert_eq!(line.text, "\tmove.l %d0,%d1");
+        assert_eq!(line.src.to_string(), "f:2:1");
+        assert_eq!(line.sites.len(), 1);
+        assert_eq!(line.sites[0].src.to_string(), "f:4:2");
+        assert_eq!(line.sites[0].name, "m");
+        assert_eq!(source_offset(&line.map, 8, false), 8);      // \a
+        assert_eq!(source_offset(&line.map, 11, true), 10);     // end of \a
+        assert_eq!(source_offset(&line.map, 12, false), 11);    // %d1
+    }
+}
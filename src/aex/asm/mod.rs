@@ -0,0 +1,41 @@
+// Assembler
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+mod assembler;
+mod directive;
+mod elf;
+mod eval;
+mod line;
+mod listing;
+mod locals;
+mod lower;
+mod macros;
+mod parse;
+mod symbols;
+
+pub use self::assembler::*;
+pub use self::directive::*;
+pub use self::elf::*;
+pub use self::eval::*;
+pub use self::line::*;
+pub use self::listing::*;
+pub use self::locals::*;
+pub use self::lower::*;
+pub use self::macros::*;
+pub use self::parse::*;
+pub use self::symbols::*;
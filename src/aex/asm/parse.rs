@@ -0,0 +1,1280 @@
+// Assembly Parser
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use num::BigInt;
+
+use aex::ast::{Binary, BinaryOp, Expr, Float, HasPrec, Id, Int, Node, Prec, Reg, Unary, UnaryOp};
+use aex::message::Messages;
+use aex::source::{File, Pos, Source};
+use aex::target::cf::{MacHalf, MacShift};
+
+use super::{is_reg_name, note_sites, resolve_name, span, Arg, Directive, ExpandedLine, Expansion, Insn, Line};
+use super::{Operand, Segment, Site, SrcExpr, SrcReg, Stmt};
+
+/// Syntaxes of ColdFire assembly.  The syntaxes differ in how operands and
+/// numbers are written; all share the same instructions, directives,
+/// expressions, and comments.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Syntax {
+    /// AT&T syntax, that of GNU `as`: `4(%a0,%d1*4)`, `(%a0)+`, `foo.w`
+    #[default]
+    Att,
+
+    /// MIT syntax: `%a0@(4,%d1:l:4)`, `%a0@+`, `foo:w`.  An instruction
+    /// size suffix may follow the name without a dot, as in `movel`.
+    Mit,
+
+    /// Motorola syntax: `(4,a0,d1.l*4)`, `(a0)+`, `(foo).w`.  Numbers may
+    /// be written `$1F` in hexadecimal, `%101` in binary, or `@17` in octal.
+    Motorola,
+}
+
+/// Parses the given file, in the given syntax, into lines of assembly.
+/// Lines that do not parse are reported to `messages` and yield no labels
+/// or statement.
+///
+/// In every syntax:
+///
+/// * A comment begins with `|`, or with `#` or `*` at the start of a line.
+/// * Registers may be written with a `%` prefix, which may be omitted if
+///   the name is not otherwise ambiguous.
+/// * Bitwise OR is written `!`.
+/// * A label may be a decimal number, as in `1:`, referenced backward as
+///   `1b` or forward as `1f`.
+/// * A directive begins with `.`, and its arguments are expressions or
+///   string literals.
+///
+pub fn parse<'a>(file: &'a File<'a>, syntax: Syntax, messages: &mut Messages<'a>) -> Vec<Line<'a>> {
+    split_lines(file).into_iter()
+        .map(|(text, src)| LineParser::new(text, src, &[], &[], syntax).parse(messages))
+        .collect()
+}
+
+/// Parses the given macro expansion into lines of assembly, like `parse`,
+/// in the syntax of the expansion.  Messages for an expanded line are
+/// followed by notes of the invocations that produced it.
+pub fn parse_expansion<'a>(exp: &'a Expansion<'a>, messages: &mut Messages<'a>) -> Vec<Line<'a>> {
+    exp.lines.iter().map(|line| {
+        let errs = messages.as_slice().len();
+        let line = parse_line(line, exp.syntax, messages);
+        note_sites(messages, errs, line.sites);
+        line
+    }).collect()
+}
+
+/// Parses the given expanded line, like `parse`.
+pub fn parse_line<'a>(line: &'a ExpandedLine<'a>, syntax: Syntax, messages: &mut Messages<'a>) -> Line<'a> {
+    LineParser::new(&line.text, line.src, &line.map, &line.sites, syntax).parse(messages)
+}
+
+/// Splits the given file into lines, excluding line terminators.  Returns
+/// the text and source of each line.
+pub fn split_lines<'a>(file: &'a File<'a>) -> Vec<(&'a str, Source<'a>)> {
+    let     data  = file.data();
+    let mut lines = vec![];
+    let mut pos   = Pos::bof();
+
+    for text in data.split('\n') {
+        let next = pos.byte + text.len() + 1;
+        if next > data.len() && text.is_empty() { break; }
+
+        let text = text.trim_end_matches('\r');
+        lines.push((text, Source::File { file, pos, len: text.len() }));
+
+        pos.byte = next;
+        pos.newline();
+    }
+
+    lines
+}
+
+type PResult<'a, T> = Result<T, (Source<'a>, &'static str)>;
+
+struct LineParser<'a> {
+    text:   &'a str,
+    src:    Source<'a>,
+    map:    &'a [Segment],
+    sites:  &'a [Site<'a>],
+    syntax: Syntax,
+    i:      usize,
+}
+
+impl<'a> LineParser<'a> {
+    fn new(text:   &'a str,
+           src:    Source<'a>,
+           map:    &'a [Segment],
+           sites:  &'a [Site<'a>],
+           syntax: Syntax)
+          -> Self {
+        LineParser { text, src, map, sites, syntax, i: 0 }
+    }
+
+    fn parse(mut self, messages: &mut Messages<'a>) -> Line<'a> {
+        let src   = self.src;
+        let sites = self.sites;
+
+        match self.line() {
+            Ok((labels, stmt)) => {
+                Line { src, labels, stmt, sites }
+            },
+            Err((s, expected)) => {
+                messages.err_expected(s, expected);
+                Line { src, labels: vec![], stmt: None, sites }
+            },
+        }
+    }
+
+    fn line(&mut self) -> PResult<'a, (Vec<Id<'a, Source<'a>>>, Option<Stmt<'a>>)> {
+        let mut labels = vec![];
+
+        if let Some('#') | Some('*') = self.peek() {
+            return Ok((labels, None));
+        }
+
+        loop {
+            self.skip_ws();
+            let start = self.i;
+            match self.ident().or_else(|| self.digits()) {
+                Some(name) if self.eat(':') => {
+                    labels.push(Id::new_with_ann(name, self.src(start, start + name.len())));
+                },
+                Some(_) => {
+                    self.i = start;
+                    break;
+                },
+                None => break,
+            }
+        }
+
+        if self.at_end() {
+            return Ok((labels, None));
+        }
+
+        let stmt = self.insn()?;
+
+        self.skip_ws();
+        if !self.at_end() {
+            return Err(self.expected("end of line"));
+        }
+
+        Ok((labels, Some(stmt)))
+    }
+
+    fn insn(&mut self) -> PResult<'a, Stmt<'a>> {
+        let start = self.i;
+        let word  = match self.ident() {
+            Some(w) => w,
+            None    => return Err(self.expected("instruction")),
+        };
+
+        if word.starts_with('.') {
+            return self.directive(start, word);
+        }
+
+        let (name, size) = match word.rfind('.') {
+            Some(n) if n > 0 && is_size_suffix(&word[n + 1..]) => (&word[..n], Some(&word[n + 1..])),
+            _ if self.syntax == Syntax::Mit                     => split_size(word),
+            _                                                   => (word, None),
+        };
+
+        let name         = Id::new_with_ann(name, self.src(start, start + name.len()));
+        let mut operands = vec![];
+
+        self.skip_ws();
+        if !self.at_end() {
+            loop {
+                operands.push(self.operand()?);
+                self.skip_ws();
+                if !self.eat(',') { break; }
+                self.skip_ws();
+            }
+        }
+
+        Ok(Stmt::Insn(Insn { name, size, operands }))
+    }
+
+    fn directive(&mut self, start: usize, word: &'a str) -> PResult<'a, Stmt<'a>> {
+        let name     = Id::new_with_ann(word, self.src(start, self.i));
+        let mut args = vec![];
+
+        self.skip_ws();
+        if !self.at_end() {
+            loop {
+                args.push(if self.peek() == Some('"') { self.string()? } else { Arg::Expr(self.expr()?) });
+                self.skip_ws();
+                if !self.eat(',') { break; }
+                self.skip_ws();
+            }
+        }
+
+        Ok(Stmt::Directive(Directive { name, args }))
+    }
+
+    /// Parses a string literal, replacing escapes: `\n`, `\t`, `\r`, `\b`,
+    /// `\f`, `\\`, `\"`, `\'`, octal `\ooo`, and hexadecimal `\xhh`.
+    fn string(&mut self) -> PResult<'a, Arg<'a>> {
+        let start     = self.i;
+        let mut bytes = vec![];
+
+        self.i += 1;
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None    => return Err(self.expected("'\"'")),
+            };
+            self.i += c.len_utf8();
+
+            match c {
+                '"'  => break,
+                '\\' => bytes.push(self.escape()?),
+                c    => {
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                },
+            }
+        }
+
+        Ok(Arg::Str(bytes, self.src(start, self.i)))
+    }
+
+    fn escape(&mut self) -> PResult<'a, u8> {
+        let c = match self.peek() {
+            Some(c) => c,
+            None    => return Err(self.expected("escape sequence")),
+        };
+        self.i += 1;
+
+        Ok(match c {
+            'n'  => b'\n',
+            't'  => b'\t',
+            'r'  => b'\r',
+            'b'  => 0x08,
+            'f'  => 0x0C,
+            '\\' | '"' | '\'' => c as u8,
+            '0'..='7' => {
+                let mut v = c as u32 - '0' as u32;
+                for _ in 0..2 {
+                    match self.peek() {
+                        Some(d @ '0'..='7') => { v = v * 8 + (d as u32 - '0' as u32); self.i += 1; },
+                        _                   => break,
+                    }
+                }
+                v as u8
+            },
+            'x' => {
+                let digits = self.i;
+                while self.i < digits + 2 && self.peek().is_some_and(|d| d.is_ascii_hexdigit()) {
+                    self.i += 1;
+                }
+                match u8::from_str_radix(&self.text[digits..self.i], 16) {
+                    Ok(v)  => v,
+                    Err(_) => return Err(self.expected("hexadecimal digits")),
+                }
+            },
+            _ => {
+                self.i -= 1;
+                return Err(self.expected("escape sequence"));
+            },
+        })
+    }
+
+    // -------------------------------------------------------------------------
+    // Operands
+
+    fn operand(&mut self) -> PResult<'a, Operand<'a>> {
+        let op = self.base_operand()?;
+
+        self.skip_ws();
+        if self.eat('&') {
+            return Ok(Operand::Masked(Box::new(op)));
+        }
+
+        Ok(op)
+    }
+
+    fn base_operand(&mut self) -> PResult<'a, Operand<'a>> {
+        let mit = self.syntax == Syntax::Mit;
+
+        // Immediate
+        if self.eat('#') {
+            return Ok(Operand::Imm(self.expr()?));
+        }
+
+        // MAC shift
+        if self.eat_str("<<") { return Ok(Operand::Shift(MacShift::Left));  }
+        if self.eat_str(">>") { return Ok(Operand::Shift(MacShift::Right)); }
+
+        // Pre-decrement
+        if !mit && self.peek() == Some('-') {
+            let start = self.i;
+            self.i += 1;
+            self.skip_ws();
+            if self.eat('(') {
+                if let Some(r) = self.reg_then(')')? {
+                    return Ok(Operand::PreDec(r));
+                }
+            }
+            self.i = start;
+        }
+
+        // Indirect, post-increment, or indexed without displacement
+        if !mit && self.peek() == Some('(') {
+            let start = self.i;
+            self.i += 1;
+            self.skip_ws();
+            if let Some((r, None)) = self.reg()? {
+                self.skip_ws();
+                if self.eat(')') {
+                    return Ok(if self.eat('+') { Operand::PostInc(r) } else { Operand::Ind(r) });
+                }
+                if self.eat(',') {
+                    let zero = Expr::Int(Int::new_with_ann(0, r.ann));
+                    return self.index(r, zero);
+                }
+            }
+            self.i = start;
+
+            // Motorola displacement first: `(d,%r)` or `(d,%r,%x*s)`
+            if self.syntax == Syntax::Motorola {
+                self.i += 1;
+                let disp = self.expr()?;
+                self.skip_ws();
+                if self.eat(',') {
+                    return self.base_index(disp);
+                }
+                self.i = start;
+            }
+        }
+
+        // Register, register half, register pair, or register list
+        if let Some((r, suffix)) = self.reg()? {
+            if let Some(s) = suffix {
+                return match half(s) {
+                    Some(h) => Ok(Operand::RegHalf(r, h)),
+                    None    => Err((r.ann, "register half (.u or .l)")),
+                };
+            }
+
+            if mit && self.eat('@') {
+                return self.mit_indirect(r);
+            }
+
+            if self.eat(':') {
+                return match self.reg()? {
+                    Some((s, None)) => Ok(Operand::RegPair(r, s)),
+                    _               => Err(self.expected("register")),
+                };
+            }
+
+            if let Some('-') | Some('/') = self.peek() {
+                return self.reg_list(r);
+            }
+
+            return Ok(Operand::Reg(r));
+        }
+
+        // Expression-based
+        let mut e = self.expr()?;
+
+        self.skip_ws();
+        if mit {
+            if self.eat(':') {
+                return match self.size_letter() {
+                    Some(long) => Ok(Operand::Abs(e, long)),
+                    None       => Err(self.expected("absolute size (w or l)")),
+                };
+            }
+            return Ok(Operand::Expr(e));
+        }
+
+        if self.eat('(') {
+            self.skip_ws();
+            return self.base_index(e);
+        }
+
+        if let Some(long) = self.abs_suffix(&mut e) {
+            return Ok(Operand::Abs(e, long));
+        }
+
+        Ok(Operand::Expr(e))
+    }
+
+    /// Parses the remainder of an MIT-syntax indirect operand after `%r@`:
+    /// nothing, `+`, `-`, `(d)`, or `(d,%x:l:s)`.
+    fn mit_indirect(&mut self, base: SrcReg<'a>) -> PResult<'a, Operand<'a>> {
+        if self.eat('+') { return Ok(Operand::PostInc(base)); }
+        if self.eat('-') { return Ok(Operand::PreDec (base)); }
+
+        if !self.eat('(') {
+            return Ok(Operand::Ind(base));
+        }
+
+        // Index without displacement
+        self.skip_ws();
+        let start = self.i;
+        if self.reg()?.is_some() {
+            self.i = start;
+            let zero = Expr::Int(Int::new_with_ann(0, base.ann));
+            return self.index(base, zero);
+        }
+
+        let disp = self.expr()?;
+        self.skip_ws();
+        if self.eat(',') {
+            return self.index(base, disp);
+        }
+        if !self.eat(')') {
+            return Err(self.expected("')'"));
+        }
+        Ok(Operand::Disp(base, disp))
+    }
+
+    /// Parses the remainder of an operand with displacement, after the
+    /// displacement and its following `(` or `,`: a base register, then
+    /// `)` or an index.
+    fn base_index(&mut self, disp: SrcExpr<'a>) -> PResult<'a, Operand<'a>> {
+        self.skip_ws();
+        let base = match self.reg()? {
+            Some((r, None)) => r,
+            _               => return Err(self.expected("base register")),
+        };
+        self.skip_ws();
+        if self.eat(',') {
+            return self.index(base, disp);
+        }
+        if !self.eat(')') {
+            return Err(self.expected("')'"));
+        }
+        Ok(Operand::Disp(base, disp))
+    }
+
+    /// Parses an index register and scale, then `)`: `%x.l*s`, or `%x:l:s`
+    /// in MIT syntax.
+    fn index(&mut self, base: SrcReg<'a>, disp: SrcExpr<'a>) -> PResult<'a, Operand<'a>> {
+        let mit = self.syntax == Syntax::Mit;
+
+        self.skip_ws();
+        let index = match self.reg()? {
+            Some((r, None))                                       => r,
+            Some((r, Some(s))) if !mit && s.eq_ignore_ascii_case("l") => r,
+            _ => return Err(self.expected("index register")),
+        };
+
+        self.skip_ws();
+        let mut has_scale = self.eat(if mit { ':' } else { '*' });
+        if mit && has_scale {
+            let start = self.i;
+            match self.size_letter() {
+                Some(true)  => has_scale = self.eat(':'),
+                Some(false) => { self.i = start; return Err(self.expected("index size (l)")); },
+                None        => (),
+            }
+        }
+
+        let scale = if has_scale {
+            self.skip_ws();
+            match self.peek() {
+                Some(c @ '1') | Some(c @ '2') | Some(c @ '4') | Some(c @ '8') => {
+                    self.i += 1;
+                    c as u8 - b'0'
+                },
+                _ => return Err(self.expected("scale factor (1, 2, 4, or 8)")),
+            }
+        } else {
+            1
+        };
+
+        self.skip_ws();
+        if !self.eat(')') {
+            return Err(self.expected("')'"));
+        }
+
+        Ok(Operand::DispIdx(base, disp, index, scale))
+    }
+
+    fn reg_list(&mut self, first: SrcReg<'a>) -> PResult<'a, Operand<'a>> {
+        let mut items = vec![];
+        let mut reg   = first;
+
+        loop {
+            let last = if self.eat('-') {
+                match self.reg()? {
+                    Some((r, None)) => Some(r),
+                    _               => return Err(self.expected("register")),
+                }
+            } else {
+                None
+            };
+
+            items.push((reg, last));
+
+            if !self.eat('/') { break; }
+
+            reg = match self.reg()? {
+                Some((r, None)) => r,
+                _               => return Err(self.expected("register")),
+            };
+        }
+
+        Ok(Operand::RegList(items))
+    }
+
+    /// Parses a register, then the given character, restoring the position
+    /// if either is absent.
+    fn reg_then(&mut self, c: char) -> PResult<'a, Option<SrcReg<'a>>> {
+        let start = self.i;
+        self.skip_ws();
+        if let Some((r, None)) = self.reg()? {
+            self.skip_ws();
+            if self.eat(c) { return Ok(Some(r)); }
+        }
+        self.i = start;
+        Ok(None)
+    }
+
+    /// Parses a register with an optional suffix, like `%d0.u`.  A register
+    /// without the `%` prefix must have a known name.
+    fn reg(&mut self) -> PResult<'a, Option<(SrcReg<'a>, Option<&'a str>)>> {
+        if self.number_prefix().is_some() {
+            return Ok(None);
+        }
+
+        let start  = self.i;
+        let prefix = self.eat('%');
+
+        let word = match self.ident() {
+            Some(w) => w,
+            None if prefix => return Err(self.expected("register name")),
+            None           => return Ok(None),
+        };
+
+        let (name, suffix) = match word.find('.') {
+            Some(n) => (&word[..n], Some(&word[n + 1..])),
+            None    => (word, None),
+        };
+
+        if !prefix && !is_reg_name(name) {
+            self.i = start;
+            return Ok(None);
+        }
+
+        let name_start = start + prefix as usize;
+        let src        = self.src(start, name_start + name.len());
+        Ok(Some((Reg::new_with_ann(name, src), suffix)))
+    }
+
+    /// Consumes a size letter not followed by an identifier character.
+    /// Returns `Some(true)` for `l`, `Some(false)` for `w`, or `None` if
+    /// there is no such letter.
+    fn size_letter(&mut self) -> Option<bool> {
+        let long = match self.peek() {
+            Some('w') | Some('W') => false,
+            Some('l') | Some('L') => true,
+            _                     => return None,
+        };
+        if self.text[self.i + 1..].starts_with(is_ident_char) {
+            return None;
+        }
+        self.i += 1;
+        Some(long)
+    }
+
+    /// Removes an absolute address size suffix (`.w` or `.l`) following an
+    /// expression.  Returns `Some(true)` for `.l`, `Some(false)` for `.w`,
+    /// or `None` if there is no suffix.
+    fn abs_suffix(&mut self, e: &mut SrcExpr<'a>) -> Option<bool> {
+        // Suffix after a number or parenthesized expression
+        for &(s, long) in &[(".w", false), (".l", true), (".W", false), (".L", true)] {
+            if self.text[self.i..].starts_with(s) && !self.text[self.i + 2..].starts_with(is_ident_char) {
+                self.i += 2;
+                return Some(long);
+            }
+        }
+
+        // Suffix lexed as part of the rightmost identifier
+        let mut e = e;
+        loop {
+            match *e {
+                Expr::Binary(ref mut b) => e = &mut b.rhs,
+                Expr::Id(ref mut id) => {
+                    let n = id.name.len();
+                    if n < 3 || id.name.as_bytes()[n - 2] != b'.' { return None; }
+                    let long = match id.name.as_bytes()[n - 1] {
+                        b'w' | b'W' => false,
+                        b'l' | b'L' => true,
+                        _           => return None,
+                    };
+                    id.name = &id.name[..n - 2];
+                    if let Source::File { ref mut len, .. } = id.ann { *len -= 2; }
+                    return Some(long);
+                },
+                _ => return None,
+            }
+        }
+    }
+
+    // -------------------------------------------------------------------------
+    // Expressions
+
+    fn expr(&mut self) -> PResult<'a, SrcExpr<'a>> {
+        self.binary(None)
+    }
+
+    /// Parses a binary expression whose operators have precedence higher
+    /// than `above`, if given.
+    fn binary(&mut self, above: Option<Prec>) -> PResult<'a, SrcExpr<'a>> {
+        let mut lhs = self.unary()?;
+
+        loop {
+            self.skip_ws();
+            let (op, len) = match self.peek_binary_op() {
+                Some(x) => x,
+                None    => break,
+            };
+            if let Some(p) = above {
+                if op.prec() <= p { break; }
+            }
+
+            self.i += len;
+            self.skip_ws();
+
+            let rhs = self.binary(Some(op.prec()))?;
+            let src = *lhs.ann() | *rhs.ann();
+            lhs = Expr::Binary(Binary::new_with_ann(op, lhs, rhs, src));
+        }
+
+        Ok(lhs)
+    }
+
+    fn peek_binary_op(&self) -> Option<(BinaryOp, usize)> {
+        static OPS: [(&str, BinaryOp); 17] = [
+            ("<<", BinaryOp::Shl), (">>", BinaryOp::Shr),
+            ("<=", BinaryOp::Le ), (">=", BinaryOp::Ge ),
+            ("==", BinaryOp::Eq ), ("!=", BinaryOp::Ne ), ("<>", BinaryOp::Ne),
+            ("<",  BinaryOp::Lt ), (">",  BinaryOp::Gt ),
+            ("*",  BinaryOp::Mul), ("/",  BinaryOp::Div), ("%",  BinaryOp::Mod),
+            ("+",  BinaryOp::Add), ("-",  BinaryOp::Sub),
+            ("&",  BinaryOp::And), ("^",  BinaryOp::Xor), ("!",  BinaryOp::Or ),
+        ];
+
+        let rest = &self.text[self.i..];
+
+        // A trailing & marks a masked operand, not an AND
+        if rest.starts_with('&') && self.is_operand_end(self.i + 1) {
+            return None;
+        }
+
+        OPS.iter()
+            .find(|&&(s, _)| rest.starts_with(s))
+            .map(|&(s, op)| (op, s.len()))
+    }
+
+    fn unary(&mut self) -> PResult<'a, SrcExpr<'a>> {
+        self.skip_ws();
+        let start = self.i;
+
+        let op = match self.peek() {
+            Some('-') => Some(UnaryOp::Neg),
+            Some('~') => Some(UnaryOp::Not),
+            Some('+') => None,
+            _         => return self.atom(),
+        };
+
+        self.i += 1;
+        let e = self.unary()?;
+        match op {
+            Some(op) => {
+                let src = self.src(start, start + 1) | *e.ann();
+                Ok(Expr::Unary(Unary::new_with_ann(op, e, src)))
+            },
+            None => Ok(e),
+        }
+    }
+
+    fn atom(&mut self) -> PResult<'a, SrcExpr<'a>> {
+        let start = self.i;
+
+        match self.peek() {
+            Some('(') => {
+                self.i += 1;
+                let e = self.expr()?;
+                self.skip_ws();
+                if !self.eat(')') { return Err(self.expected("')'")); }
+                Ok(e)
+            },
+            Some('\'') => {
+                self.i += 1;
+                let c = match self.peek() {
+                    Some(c) => c,
+                    None    => return Err(self.expected("character")),
+                };
+                self.i += c.len_utf8();
+                if !self.eat('\'') { return Err(self.expected("'")); }
+                let src = self.src(start, self.i);
+                Ok(Expr::Int(Int::new_with_ann(c as u32, src)))
+            },
+            Some(c) if c.is_ascii_digit() => {
+                self.number()
+            },
+            _ if self.number_prefix().is_some() => {
+                self.number()
+            },
+            _ => match self.ident() {
+                Some(name) => {
+                    let src = self.src(start, self.i);
+                    Ok(Expr::Id(Id::new_with_ann(name, src)))
+                },
+                None => Err(self.expected("expression")),
+            },
+        }
+    }
+
+    fn number(&mut self) -> PResult<'a, SrcExpr<'a>> {
+        let start = self.i;
+        let rest  = &self.text[start..];
+
+        let (radix, prefix) = match rest.as_bytes().get(..2) {
+            Some(b"0x") | Some(b"0X") => (16, 2),
+            Some(b"0b") | Some(b"0B")
+                if rest[2..].starts_with(['0', '1']) => (2, 2),
+            _ => match self.number_prefix() {
+                Some(radix) => (radix, 1),
+                None        => (10, 0),
+            },
+        };
+
+        self.i += prefix;
+
+        let digits = self.i;
+        while let Some(c) = self.peek() {
+            if !c.is_digit(radix) { break; }
+            self.i += 1;
+        }
+
+        if self.i == digits {
+            return Err(self.expected("digits"));
+        }
+
+        // Numeric local label reference
+        if prefix == 0 && self.text[self.i..].starts_with(['b', 'f'])
+                       && !self.text[self.i + 1..].starts_with(is_ident_char) {
+            self.i += 1;
+            let src = self.src(start, self.i);
+            return Ok(Expr::Id(Id::new_with_ann(&self.text[start..self.i], src)));
+        }
+
+        // Floating-point literal
+        if radix == 10 && self.is_float_tail() {
+            if self.peek() == Some('.') {
+                self.i += 1;
+                while let Some('0'..='9') = self.peek() { self.i += 1; }
+            }
+            if let Some('e') | Some('E') = self.peek() {
+                self.i += 1;
+                if let Some('+') | Some('-') = self.peek() { self.i += 1; }
+                while let Some('0'..='9') = self.peek() { self.i += 1; }
+            }
+            let src = self.src(start, self.i);
+            return match self.text[start..self.i].parse::<f64>() {
+                Ok(v)  => Ok(Expr::Float(Float::new_with_ann(v, src))),
+                Err(_) => Err((src, "floating-point number")),
+            };
+        }
+
+        let src   = self.src(start, self.i);
+        let value = BigInt::parse_bytes(&self.text.as_bytes()[digits..self.i], radix)
+            .ok_or((src, "number"))?;
+
+        Ok(Expr::Int(Int::new_with_ann(value, src)))
+    }
+
+    /// Returns the radix of a Motorola-syntax number prefix at the current
+    /// position, followed by a digit: `$` for hexadecimal, `%` for binary,
+    /// or `@` for octal.
+    fn number_prefix(&self) -> Option<u32> {
+        if self.syntax != Syntax::Motorola { return None; }
+
+        let mut chars = self.text[self.i..].chars();
+        let radix = match chars.next() {
+            Some('$') => 16,
+            Some('%') => 2,
+            Some('@') => 8,
+            _         => return None,
+        };
+
+        chars.next().filter(|c| c.is_digit(radix)).map(|_| radix)
+    }
+
+    fn is_float_tail(&self) -> bool {
+        let rest = &self.text.as_bytes()[self.i..];
+        match rest.first() {
+            Some(&b'.')               => rest.get(1).is_some_and(u8::is_ascii_digit),
+            Some(&b'e') | Some(&b'E') => match rest.get(1) {
+                Some(&b'+') | Some(&b'-') => rest.get(2).is_some_and(u8::is_ascii_digit),
+                Some(c)                   => c.is_ascii_digit(),
+                None                      => false,
+            },
+            _ => false,
+        }
+    }
+
+    // -------------------------------------------------------------------------
+    // Characters
+
+    fn ident(&mut self) -> Option<&'a str> {
+        let text  = self.text;
+        let start = self.i;
+
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '.' => (),
+            _ => return None,
+        }
+
+        self.i += 1;
+        while let Some(c) = self.peek() {
+            if !is_ident_char(c) { break; }
+            self.i += 1;
+        }
+
+        Some(&text[start..self.i])
+    }
+
+    fn digits(&mut self) -> Option<&'a str> {
+        let text  = self.text;
+        let start = self.i;
+
+        while let Some('0'..='9') = self.peek() {
+            self.i += 1;
+        }
+
+        if self.i == start { None } else { Some(&text[start..self.i]) }
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<char> {
+        self.text[self.i..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.i += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        if self.text[self.i..].starts_with(s) {
+            self.i += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() { break; }
+            self.i += c.len_utf8();
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        matches!(self.peek(), None | Some('|'))
+    }
+
+    fn is_operand_end(&self, i: usize) -> bool {
+        matches!(self.text[i..].trim_start().chars().next(), None | Some(',') | Some('|'))
+    }
+
+    // -------------------------------------------------------------------------
+    // Sources and errors
+
+    fn src(&self, start: usize, end: usize) -> Source<'a> {
+        span(self.src, self.map, start, end)
+    }
+
+    fn expected(&self, what: &'static str) -> (Source<'a>, &'static str) {
+        let len = self.peek().map_or(0, char::len_utf8);
+        (self.src(self.i, self.i + len), what)
+    }
+
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '$'
+}
+
+fn is_size_suffix(s: &str) -> bool {
+    matches!(s, "b" | "w" | "l" | "s" | "d" |
+                "B" | "W" | "L" | "S" | "D")
+}
+
+// Splits an MIT-syntax instruction name like `movel`, which is not itself a
+// known name, into a name and size suffix.
+fn split_size(word: &str) -> (&str, Option<&str>) {
+    let n = word.len();
+    if n > 1 && resolve_name(word).is_none()
+             && is_size_suffix(&word[n - 1..])
+             && resolve_name(&word[..n - 1]).is_some() {
+        (&word[..n - 1], Some(&word[n - 1..]))
+    } else {
+        (word, None)
+    }
+}
+
+fn half(s: &str) -> Option<MacHalf> {
+    match s {
+        "u" | "U" => Some(MacHalf::Upper),
+        "l" | "L" => Some(MacHalf::Lower),
+        _         => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aex::ast::Id;
+    use aex::fmt::{AttStyle, ToStyled};
+
+    fn parse_one<'a>(file: &'a File<'a>) -> Line<'a> {
+        parse_one_in(file, Syntax::Att)
+    }
+
+    fn parse_one_in<'a>(file: &'a File<'a>, syntax: Syntax) -> Line<'a> {
+        let mut m = Messages::new();
+        let mut lines = parse(file, syntax, &mut m);
+        assert!(!m.has_errors(), "{}", m);
+        assert_eq!(lines.len(), 1);
+        lines.remove(0)
+    }
+
+    fn operands<'l, 'a>(line: &'l Line<'a>) -> &'l [Operand<'a>] {
+        match line.stmt {
+            Some(Stmt::Insn(ref i)) => &i.operands,
+            _                       => panic!("no instruction"),
+        }
+    }
+
+    fn strip(e: &SrcExpr) -> String {
+        e.styled(&AttStyle::default()).to_string()
+    }
+
+    // Summarizes an operand without its source.
+    fn shape(op: &Operand) -> String {
+        match *op {
+            Operand::Expr(ref e)                     => strip(e),
+            Operand::Imm(ref e)                      => format!("#{}", strip(e)),
+            Operand::Abs(ref e, long)                => format!("abs.{} {}", if long { 'l' } else { 'w' }, strip(e)),
+            Operand::Reg(ref r)                      => r.name.to_string(),
+            Operand::Ind(ref r)                      => format!("ind {}", r.name),
+            Operand::PreDec(ref r)                   => format!("predec {}", r.name),
+            Operand::PostInc(ref r)                  => format!("postinc {}", r.name),
+            Operand::Disp(ref r, ref d)              => format!("disp {} {}", r.name, strip(d)),
+            Operand::DispIdx(ref r, ref d, ref x, s) => format!("idx {} {} {} {}", r.name, strip(d), x.name, s),
+            _                                        => format!("{:?}", op),
+        }
+    }
+
+    #[test]
+    fn parse_lines() {
+        let f = File::new("f", "start: loop:\n  nop | comment\n# comment\n\n  rts\n");
+        let mut m = Messages::new();
+        let lines = parse(&f, Syntax::Att, &mut m);
+
+        assert!(!m.has_errors());
+        assert_eq!(lines.len(), 5);
+
+        let names: Vec<_> = lines[0].labels.iter().map(|l| l.name).collect();
+        assert_eq!(names, vec!["start", "loop"]);
+        assert_eq!(lines[0].stmt, None);
+        assert_eq!(lines[4].src.to_string(), "f:5:1");
+
+        match lines[1].stmt {
+            Some(Stmt::Insn(ref i)) => {
+                assert_eq!(i.name.name, "nop");
+                assert_eq!(i.name.ann.to_string(), "f:2:3");
+                assert_eq!(i.size, None);
+            },
+            _ => panic!("expected instruction"),
+        }
+
+        assert_eq!(lines[2].stmt, None);
+        assert_eq!(lines[3].stmt, None);
+    }
+
+    #[test]
+    fn parse_size() {
+        let f = File::new("f", "\tmove.l %d0,%d1");
+        match parse_one(&f).stmt {
+            Some(Stmt::Insn(ref i)) => {
+                assert_eq!(i.name.name, "move");
+                assert_eq!(i.size, Some("l"));
+            },
+            _ => panic!("expected instruction"),
+        }
+    }
+
+    #[test]
+    fn parse_modes() {
+        let f = File::new("f",
+            "x %d0,a1,(%a2),(%a3)+,-(%sp),-8(%fp),4(%a0,%d1*4),(%a0,%a1.l),\
+             16(%pc),#-1,0x1234.w,foo.l,foo"
+        );
+        let line = parse_one(&f);
+        let ops  = operands(&line);
+        assert_eq!(ops.len(), 13);
+
+        match ops[0]  { Operand::Reg(ref r)  => assert_eq!(r.name, "d0"), _ => panic!() }
+        match ops[1]  { Operand::Reg(ref r)  => assert_eq!(r.name, "a1"), _ => panic!() }
+        match ops[2]  { Operand::Ind(ref r)  => assert_eq!(r.name, "a2"), _ => panic!() }
+        match ops[3]  { Operand::PostInc(ref r) => assert_eq!(r.name, "a3"), _ => panic!() }
+        match ops[4]  { Operand::PreDec(ref r)  => assert_eq!(r.name, "sp"), _ => panic!() }
+        match ops[5]  {
+            Operand::Disp(ref r, ref d) => { assert_eq!(r.name, "fp"); assert_eq!(strip(d), "-8"); },
+            _ => panic!(),
+        }
+        match ops[6]  {
+            Operand::DispIdx(ref r, ref d, ref x, s) => {
+                assert_eq!((r.name, x.name, s), ("a0", "d1", 4));
+                assert_eq!(strip(d), "4");
+            },
+            _ => panic!(),
+        }
+        match ops[7]  {
+            Operand::DispIdx(ref r, ref d, ref x, s) => {
+                assert_eq!((r.name, x.name, s), ("a0", "a1", 1));
+                assert_eq!(strip(d), "0");
+            },
+            _ => panic!(),
+        }
+        match ops[8]  { Operand::Disp(ref r, _) => assert_eq!(r.name, "pc"), _ => panic!() }
+        match ops[9]  { Operand::Imm(ref e) => assert_eq!(strip(e), "-1"), _ => panic!() }
+        match ops[10] { Operand::Abs(ref e, false) => assert_eq!(strip(e), "4660"), _ => panic!() }
+        match ops[11] {
+            Operand::Abs(Expr::Id(ref i), true) => {
+                assert_eq!(i.name, "foo");
+                assert_eq!(i.ann.as_ref(), "foo");
+            },
+            _ => panic!(),
+        }
+        match ops[12] { Operand::Expr(Expr::Id(ref i)) => assert_eq!(i.name, "foo"), _ => panic!() }
+    }
+
+    #[test]
+    fn parse_syntaxes() {
+        let shapes = vec![
+            "d0", "ind a0", "postinc a1", "predec sp", "disp fp -8", "idx a0 4 d1 4",
+            "idx a0 0 a1 1", "disp pc 16", "#-1", "abs.w 4660", "abs.l foo", "foo",
+        ];
+        let texts = [
+            (Syntax::Att,
+             "x %d0,(%a0),(%a1)+,-(%sp),-8(%fp),4(%a0,%d1*4),(%a0,%a1.l),\
+              16(%pc),#-1,0x1234.w,foo.l,foo"),
+            (Syntax::Mit,
+             "x %d0,%a0@,%a1@+,%sp@-,%fp@(-8),%a0@(4,%d1:l:4),%a0@(%a1:l),\
+              %pc@(16),#-1,0x1234:w,foo:l,foo"),
+            (Syntax::Motorola,
+             "x d0,(a0),(a1)+,-(sp),(-8,fp),(4,a0,d1.l*4),(a0,a1.l),\
+              (16,pc),#-1,$1234.w,(foo).l,foo"),
+        ];
+
+        for &(syntax, text) in &texts {
+            let f = File::new("f", text);
+            let line = parse_one_in(&f, syntax);
+            let ops: Vec<_> = operands(&line).iter().map(shape).collect();
+            assert_eq!(ops, shapes, "{:?}", syntax);
+        }
+    }
+
+    #[test]
+    fn parse_mit_sizes() {
+        let f = File::new("f", "movel %d0,%d1\nbras x\nmoveq #1,%d0\nextbl %d0\nmovew.w %d0,%d1\n");
+        let mut m = Messages::new();
+        let lines = parse(&f, Syntax::Mit, &mut m);
+        assert!(!m.has_errors(), "{}", m);
+
+        let insns: Vec<_> = lines.iter().map(|l| match l.stmt {
+            Some(Stmt::Insn(ref i)) => (i.name.name, i.size),
+            _                       => panic!("expected instruction"),
+        }).collect();
+
+        assert_eq!(insns, vec![
+            ("move",  Some("l")),
+            ("bra",   Some("s")),
+            ("moveq", None),
+            ("extb",  Some("l")),
+            ("movew", Some("w")),
+        ]);
+    }
+
+    #[test]
+    fn parse_motorola_numbers() {
+        let f = File::new("f", "x #$1F, #%101, #@17, #7%3, %d0, #$10+@10");
+        let line = parse_one_in(&f, Syntax::Motorola);
+        let ops: Vec<_> = operands(&line).iter().map(shape).collect();
+        assert_eq!(ops, vec!["#31", "#5", "#15", "#7 % 3", "d0", "#16 + 8"]);
+
+        let f = File::new("f", "x #$1F");
+        let mut m = Messages::new();
+        parse(&f, Syntax::Att, &mut m);
+        assert_eq!(m.to_string(), "f:1:4: E001: Expected: expression\n");
+    }
+
+    #[test]
+    fn parse_syntax_errors() {
+        let cases = [
+            (Syntax::Mit,      "x %a0@(4,%d1:w)", "f:1:14: E001: Expected: index size (l)\n"),
+            (Syntax::Mit,      "x foo:q",         "f:1:7: E001: Expected: absolute size (w or l)\n"),
+            (Syntax::Mit,      "x %a0@(4",        "f:1:9: E001: Expected: ')'\n"),
+            (Syntax::Motorola, "x (4,foo)",       "f:1:6: E001: Expected: base register\n"),
+            (Syntax::Motorola, "x (4,a0,d1*3)",   "f:1:12: E001: Expected: scale factor (1, 2, 4, or 8)\n"),
+        ];
+
+        for &(syntax, text, expected) in &cases {
+            let f = File::new("f", text);
+            let mut m = Messages::new();
+            parse(&f, syntax, &mut m);
+            assert_eq!(m.to_string(), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_regs() {
+        let f = File::new("f", "x %d0-%d3/%a2/%a5-%fp, %d1:%d2, %a0.u, d3.l, <<, (%a1)&, >>");
+        let line = parse_one(&f);
+        let ops  = operands(&line);
+
+        match ops[0] {
+            Operand::RegList(ref items) => {
+                let names: Vec<_> = items.iter()
+                    .map(|(a, b)| (a.name, b.as_ref().map(|b| b.name)))
+                    .collect();
+                assert_eq!(names, vec![("d0", Some("d3")), ("a2", None), ("a5", Some("fp"))]);
+            },
+            _ => panic!(),
+        }
+        match ops[1] {
+            Operand::RegPair(ref a, ref b) => assert_eq!((a.name, b.name), ("d1", "d2")),
+            _ => panic!(),
+        }
+        match ops[2] {
+            Operand::RegHalf(ref r, MacHalf::Upper) => assert_eq!(r.name, "a0"),
+            _ => panic!(),
+        }
+        match ops[3] {
+            Operand::RegHalf(ref r, MacHalf::Lower) => assert_eq!(r.name, "d3"),
+            _ => panic!(),
+        }
+        assert_eq!(ops[4], Operand::Shift(MacShift::Left));
+        match ops[5] {
+            Operand::Masked(ref o) => match **o { Operand::Ind(ref r) => assert_eq!(r.name, "a1"), _ => panic!() },
+            _ => panic!(),
+        }
+        assert_eq!(ops[6], Operand::Shift(MacShift::Right));
+    }
+
+    #[test]
+    fn parse_exprs() {
+        let f = File::new("f", "x #1+2*3, #(1+2)*3, #a-b-c, #0x1F!0b10&~'A', #1.5, #-2.5e3, #1<<4>=x");
+        let line = parse_one(&f);
+        let ops: Vec<_> = operands(&line).iter()
+            .map(|o| match *o { Operand::Imm(ref e) => strip(e), _ => panic!() })
+            .collect();
+
+        assert_eq!(ops, vec![
+            "1 + 2 * 3",
+            "(1 + 2) * 3",
+            "a - b - c",
+            "31 | 2 & ~65",
+            "1.5",
+            "-2500.0",
+            "1 << 4 >= x",
+        ]);
+    }
+
+    #[test]
+    fn parse_local_labels() {
+        let f = File::new("f", "1: .Lx: 23: x 1b, 1f+2, #0b, #0b10, 23f, .Lx");
+        let line = parse_one(&f);
+        let names: Vec<_> = line.labels.iter().map(|l| l.name).collect();
+        assert_eq!(names, vec!["1", ".Lx", "23"]);
+
+        let ops: Vec<_> = operands(&line).iter().map(shape).collect();
+        assert_eq!(ops, vec!["1b", "1f + 2", "#0b", "#2", "23f", ".Lx"]);
+        assert_eq!(line.labels[2].ann.to_string(), "f:1:9");
+
+        let f = File::new("f", "x 1bc");
+        let mut m = Messages::new();
+        parse(&f, Syntax::Att, &mut m);
+        assert_eq!(m.to_string(), "f:1:4: E001: Expected: end of line\n");
+    }
+
+    #[test]
+    fn parse_directive() {
+        let f = File::new("f", "msg: .ascii \"a\\tb\\\"|\\101\\x42\", 1+2 | comment");
+        let line = parse_one(&f);
+
+        match line.stmt {
+            Some(Stmt::Directive(ref d)) => {
+                assert_eq!(d.name.name, ".ascii");
+                assert_eq!(d.args.len(), 2);
+                match d.args[0] {
+                    Arg::Str(ref s, src) => {
+                        assert_eq!(s, b"a\tb\"|AB");
+                        assert_eq!(src.to_string(), "f:1:13");
+                    },
+                    _ => panic!("expected string"),
+                }
+                match d.args[1] {
+                    Arg::Expr(ref e) => assert_eq!(strip(e), "1 + 2"),
+                    _                => panic!("expected expression"),
+                }
+            },
+            _ => panic!("expected directive"),
+        }
+
+        let f = File::new("f", ".byte \"abc");
+        let mut m = Messages::new();
+        parse(&f, Syntax::Att, &mut m);
+        assert_eq!(m.to_string(), "f:1:11: E001: Expected: '\"'\n");
+    }
+
+    #[test]
+    fn parse_error() {
+        let f = File::new("f", "a: move.l (%d0,\nnop\n");
+        let mut m = Messages::new();
+        let lines = parse(&f, Syntax::Att, &mut m);
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].labels, vec![]);
+        assert_eq!(lines[0].stmt, None);
+        assert!(lines[1].stmt.is_some());
+        assert_eq!(m.to_string(), "f:1:16: E001: Expected: index register\n");
+    }
+
+    #[test]
+    fn parse_label_src() {
+        let f = File::new("f", "  here: nop");
+        let line = parse_one(&f);
+        assert_eq!(line.labels, vec![Id::new_with_ann("here", line.labels[0].ann)]);
+        assert_eq!(line.labels[0].ann.as_ref(), "here");
+        assert_eq!(line.labels[0].ann.to_string(), "f:1:3");
+    }
+}
@@ -0,0 +1,261 @@
+// Assembly Symbol Table
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::collections::btree_map::{Entry, Values};
+use std::fmt::{self, Display, Formatter};
+
+use num::{BigInt, ToPrimitive};
+
+use aex::source::Source;
+
+use super::Value;
+
+/// An assembly symbol.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Symbol<'a> {
+    /// Name of the symbol.
+    pub name: &'a str,
+
+    /// Source of the symbol's definition.
+    pub src: Source<'a>,
+
+    /// Value of the symbol, or `None` if not yet known.
+    pub value: Option<Value>,
+
+    /// How the symbol is defined.
+    pub kind: SymbolKind,
+
+    /// Whether the symbol is visible outside the source file.
+    pub global: bool,
+}
+
+/// Kinds of assembly symbol definitions.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SymbolKind {
+    /// Declared, as by `.global`, but not defined
+    Undefined,
+
+    /// Label: `name:`
+    Label,
+
+    /// Equate, which cannot be redefined: `.equ name, value`
+    Equate,
+
+    /// Equate, which can be redefined: `.set name, value`
+    Set,
+}
+
+/// A table of assembly symbols, ordered by name.
+#[derive(Clone, Default, Debug)]
+pub struct SymbolTable<'a> {
+    map: BTreeMap<&'a str, Symbol<'a>>,
+}
+
+impl<'a> SymbolTable<'a> {
+    /// Creates an empty symbol table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defines a symbol of the given kind with the given name and source,
+    /// with no value.  If the symbol is already defined, returns the existing
+    /// symbol as an error, unless both definitions are `.set`.
+    pub fn define(&mut self, name: &'a str, src: Source<'a>, kind: SymbolKind)
+                 -> Result<(), &Symbol<'a>> {
+        match self.map.entry(name) {
+            Entry::Vacant(e) => {
+                e.insert(Symbol { name, src, value: None, kind, global: false });
+                Ok(())
+            },
+            Entry::Occupied(e) => {
+                let sym = e.into_mut();
+                match (sym.kind, kind) {
+                    (SymbolKind::Undefined, _) => {
+                        sym.src  = src;
+                        sym.kind = kind;
+                        Ok(())
+                    },
+                    (SymbolKind::Set, SymbolKind::Set) => {
+                        Ok(())
+                    },
+                    _ => {
+                        Err(sym)
+                    },
+                }
+            },
+        }
+    }
+
+    /// Sets whether the symbol with the given name is global, declaring the
+    /// symbol if it is not yet defined.
+    pub fn set_global(&mut self, name: &'a str, src: Source<'a>, global: bool) {
+        self.map.entry(name)
+            .or_insert(Symbol { name, src, value: None, kind: SymbolKind::Undefined, global })
+            .global = global;
+    }
+
+    /// Returns the symbol with the given name, if defined.
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&Symbol<'a>> {
+        self.map.get(name)
+    }
+
+    /// Sets the value of the symbol with the given name.  Returns whether
+    /// the value changed.
+    ///
+    /// # Panics
+    /// * The symbol is not defined.
+    ///
+    pub fn set_value(&mut self, name: &str, value: Value) -> bool {
+        let sym = self.map.get_mut(name).expect("symbol not defined");
+        if sym.value.as_ref() == Some(&value) { return false; }
+        sym.value = Some(value);
+        true
+    }
+
+    /// Forgets the value of the symbol with the given name, if defined.
+    pub fn forget_value(&mut self, name: &str) {
+        if let Some(sym) = self.map.get_mut(name) { sym.value = None; }
+    }
+
+    /// Returns the number of symbols in the table.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns whether the table contains no symbols.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns an iterator over the symbols, in name order.
+    #[inline]
+    pub fn iter(&self) -> Values<'_, &'a str, Symbol<'a>> {
+        self.map.values()
+    }
+}
+
+impl<'s, 'a> IntoIterator for &'s SymbolTable<'a> {
+    type Item     = &'s Symbol<'a>;
+    type IntoIter = Values<'s, &'a str, Symbol<'a>>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> Display for SymbolTable<'a> {
+    /// Formats the table with one symbol per line: value, section number or
+    /// `abs`, binding (`l` for local or `g` for global), and name.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for sym in self {
+            match sym.value {
+                Some(ref v) => {
+                    write!(f, "{:08x} ", low_bits(&v.int))?;
+                    match v.section {
+                        Some(n) => write!(f, "{:<4}", n)?,
+                        None    => f.write_str("abs ")?,
+                    }
+                },
+                None => {
+                    f.write_str("???????? ??? ")?;
+                },
+            }
+            writeln!(f, " {} {}", if sym.global { 'g' } else { 'l' }, sym.name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the low 32 bits of the given value, in two's complement.
+pub fn low_bits(v: &BigInt) -> u32 {
+    v.to_i64().map(|v| v as u32)
+        .or_else(|| v.to_u64().map(|v| v as u32))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn define() {
+        let mut t = SymbolTable::new();
+
+        assert!(t.is_empty());
+        assert!(t.define("b", Source::BuiltIn, SymbolKind::Label).is_ok());
+        assert!(t.define("a", Source::BuiltIn, SymbolKind::Equate).is_ok());
+        assert_eq!(t.define("b", Source::BuiltIn, SymbolKind::Label).unwrap_err().name, "b");
+        assert_eq!(t.define("a", Source::BuiltIn, SymbolKind::Set).unwrap_err().name, "a");
+        assert_eq!(t.len(), 2);
+        assert_eq!(t.get("a").unwrap().value, None);
+        assert_eq!(t.get("c"), None);
+    }
+
+    #[test]
+    fn define_set() {
+        let mut t = SymbolTable::new();
+
+        assert!(t.define("s", Source::BuiltIn, SymbolKind::Set).is_ok());
+        assert!(t.define("s", Source::BuiltIn, SymbolKind::Set).is_ok());
+        assert!(t.define("s", Source::BuiltIn, SymbolKind::Label).is_err());
+    }
+
+    #[test]
+    fn set_global() {
+        let mut t = SymbolTable::new();
+
+        t.set_global("a", Source::BuiltIn, true);
+        assert_eq!(t.get("a").unwrap().kind, SymbolKind::Undefined);
+        assert!(t.define("a", Source::BuiltIn, SymbolKind::Label).is_ok());
+        assert_eq!(t.get("a").unwrap().kind, SymbolKind::Label);
+        assert!(t.get("a").unwrap().global);
+    }
+
+    #[test]
+    fn set_value() {
+        let mut t = SymbolTable::new();
+        t.define("a", Source::BuiltIn, SymbolKind::Label).unwrap();
+
+        assert!( t.set_value("a", Value::rel(4, 0)));
+        assert!(!t.set_value("a", Value::rel(4, 0)));
+        assert!( t.set_value("a", Value::rel(6, 0)));
+        assert_eq!(t.get("a").unwrap().value, Some(Value::rel(6, 0)));
+    }
+
+    #[test]
+    fn fmt() {
+        let mut t = SymbolTable::new();
+        t.define("start", Source::BuiltIn, SymbolKind::Label).unwrap();
+        t.define("count", Source::BuiltIn, SymbolKind::Equate).unwrap();
+        t.set_global("start", Source::BuiltIn, true);
+        t.set_global("undef", Source::BuiltIn, true);
+        t.set_value("start", Value::rel(0x1000, 0));
+        t.set_value("count", Value::abs(-1));
+
+        assert_eq!(t.to_string(), "\
+ffffffff abs  l count
+00001000 0    g start
+???????? ???  g undef
+");
+    }
+}
@@ -22,7 +22,7 @@ use aex::ast::{Node, Expr};
 use aex::ast::{BinaryOp, HasPrec, Prec};
 
 /// A binary operator expression.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct Binary<'a, A = ()> {
     /// Operator.
     pub op: BinaryOp,
@@ -22,7 +22,7 @@ use aex::ast::{Node, Expr};
 use aex::ast::{HasPrec, Prec};
 
 /// A dereference expression.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct Deref<'a, A = ()> {
     /// Terms that compute the effective address.
     pub terms: Vec<Expr<'a, A>>,
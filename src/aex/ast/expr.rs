@@ -21,7 +21,7 @@ use aex::fmt::{Code, Style};
 use super::*;
 
 /// An expression.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum Expr<'a, A = ()> {
     /// Identifier
     Id(Id<'a, A>),
@@ -29,6 +29,9 @@ pub enum Expr<'a, A = ()> {
     /// Integer literal
     Int(Int<A>),
 
+    /// Floating-point literal
+    Float(Float<A>),
+
     /// Register
     Reg(Reg<'a, A>),
 
@@ -64,6 +67,7 @@ impl<'a, A> Node for Expr<'a, A> {
         match *self {
             Expr::Id     (ref i) => i.ann(),
             Expr::Int    (ref i) => i.ann(),
+            Expr::Float  (ref x) => x.ann(),
             Expr::Reg    (ref r) => r.ann(),
             Expr::Unary  (ref u) => u.ann(),
             Expr::Binary (ref b) => b.ann(),
@@ -78,6 +82,7 @@ impl<'a, A> HasPrec for Expr<'a, A> {
         match *self {
             Expr::Id     (ref i) => i.prec(),
             Expr::Int    (ref i) => i.prec(),
+            Expr::Float  (ref x) => x.prec(),
             Expr::Reg    (ref r) => r.prec(),
             Expr::Unary  (ref u) => u.prec(),
             Expr::Binary (ref b) => b.prec(),
@@ -92,6 +97,7 @@ impl<'a, A> Display for Expr<'a, A> {
         match *self {
             Expr::Id     (ref i) => Display::fmt(i, f),
             Expr::Int    (ref i) => Display::fmt(i, f),
+            Expr::Float  (ref x) => Display::fmt(x, f),
             Expr::Reg    (ref r) => Display::fmt(r, f),
             Expr::Unary  (ref u) => Display::fmt(u, f),
             Expr::Binary (ref b) => Display::fmt(b, f),
@@ -107,6 +113,7 @@ impl<'a, A> Code for Expr<'a, A> {
         match *self {
             Expr::Id     (ref i) => Code::fmt(i, f, s),
             Expr::Int    (ref i) => Code::fmt(i, f, s),
+            Expr::Float  (ref x) => Code::fmt(x, f, s),
             Expr::Reg    (ref r) => Code::fmt(r, f, s),
             Expr::Unary  (ref u) => Code::fmt(u, f, s),
             Expr::Binary (ref b) => Code::fmt(b, f, s),
@@ -143,6 +150,13 @@ mod tests {
         assert_eq!(s, "0x2A");
     }
 
+    #[test]
+    fn fmt_float() {
+        let e = Expr::Float(Float::from(0.5));
+        let s = format!("{}", e);
+        assert_eq!(s, "0.5");
+    }
+
     #[test]
     fn fmt_reg() {
         let e = Expr::Reg(Reg::new("a"));
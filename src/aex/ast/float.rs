@@ -0,0 +1,126 @@
+// Floating-Point Literals
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt::{self, Display, Formatter};
+use aex::fmt::{Code, Style};
+use aex::ast::{Node, Prec, HasPrec};
+
+/// A floating-point literal.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Float<A = ()> {
+    /// The value of the floating-point literal.
+    pub value: f64,
+
+    /// Annotation.
+    pub ann: A,
+}
+
+impl Float {
+    /// Creates a new `Float` with the given value and with `()` annotation.
+    #[inline]
+    pub fn new<V>(val: V) -> Self
+    where V: Into<f64> {
+        Self::new_with_ann(val, ())
+    }
+}
+
+impl<A> Float<A> {
+    /// Creates a new `Float` with the given value and annotation.
+    #[inline]
+    pub fn new_with_ann<V>(val: V, ann: A) -> Self
+    where V: Into<f64> {
+        Float { value: val.into(), ann }
+    }
+}
+
+impl<T> From<T> for Float where T: Into<f64> {
+    /// Converts the given value to a `Float` with `()` annotation.
+    #[inline]
+    fn from(val: T) -> Self { Self::new(val) }
+}
+
+impl<A> Node for Float<A> {
+    /// Annotation type.
+    type Ann = A;
+
+    /// Gets the annotation for this node.
+    fn ann(&self) -> &A { &self.ann }
+}
+
+impl<A> HasPrec for Float<A> {
+    /// Gets the operator precedence level.
+    #[inline(always)]
+    fn prec(&self) -> Prec { Prec::Atomic }
+}
+
+impl<A> Display for Float<A> {
+    /// Formats the value using the given formatter.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.value)
+    }
+}
+
+impl<A> Code for Float<A> {
+    /// Formats the value as code, using the given formatter and style.
+    #[inline]
+    fn fmt<S: Style<A> + ?Sized>
+          (&self, f: &mut Formatter, s: &S) -> fmt::Result {
+        s.write_float(f, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aex::fmt::{ToStyled, IntelStyle};
+    use aex::ast::Prec;
+
+    #[test]
+    fn new() {
+        let x = Float::new(1.5);
+        assert_eq!(x.value, 1.5);
+        assert_eq!(x.ann, ());
+    }
+
+    #[test]
+    fn new_with_ann() {
+        let x = Float::new_with_ann(1.5f32, "a");
+        assert_eq!(x.value, 1.5);
+        assert_eq!(x.ann, "a");
+    }
+
+    #[test]
+    fn prec() {
+        let x = Float::new(1.5);
+        assert_eq!(x.prec(), Prec::Atomic);
+    }
+
+    #[test]
+    fn fmt() {
+        let x = Float { value: 2.0, ann: "a" };
+        let s = format!("{}", &x);
+        assert_eq!(s, "2.0");
+    }
+
+    #[test]
+    fn fmt_asm() {
+        let x = Float { value: -0.25, ann: "a" };
+        let s = format!("{}", x.styled(&IntelStyle));
+        assert_eq!(s, "-0.25");
+    }
+}
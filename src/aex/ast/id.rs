@@ -21,7 +21,7 @@ use aex::fmt::{Code, Style};
 use aex::ast::{Node, Prec, HasPrec};
 
 /// An identifier.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Id<'a, A = ()> {
     /// The name of the identifier.
     pub name: &'a str,
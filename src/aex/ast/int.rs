@@ -22,7 +22,7 @@ use aex::ast::{Node, Prec, HasPrec};
 use num::BigInt;
 
 /// An integer literal.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct Int<A = ()> {
     /// The value of the integer literal.
     pub value: BigInt,
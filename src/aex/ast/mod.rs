@@ -29,6 +29,7 @@
 mod binary;
 mod deref;
 mod expr;
+mod float;
 mod id;
 mod int;
 //mod operand;
@@ -39,6 +40,7 @@ mod unary;
 pub use self::binary::*;
 pub use self::deref::*;
 pub use self::expr::*;
+pub use self::float::*;
 pub use self::id::*;
 pub use self::int::*;
 //pub use self::operand::*;
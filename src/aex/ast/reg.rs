@@ -21,7 +21,7 @@ use aex::fmt::{Code, Style};
 use aex::ast::{Node, Prec, HasPrec};
 
 /// A register.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Reg<'a, A = ()> {
     /// The name of the register.
     pub name: &'a str,
@@ -22,7 +22,7 @@ use aex::ast::{Node, Expr};
 use aex::ast::{UnaryOp, HasAssoc, HasPrec, Assoc, Prec};
 
 /// A unary operator expression.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct Unary<'a, A = ()> {
     /// Operator.
     pub op: UnaryOp,
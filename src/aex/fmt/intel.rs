@@ -25,81 +25,23 @@ use super::{Style, StyleExt};
 pub struct IntelStyle;
 
 impl<C> Style<C> for IntelStyle {
-/*
-    fn write_reg(
-        &self,
-        f:    &mut Formatter,
-        name: &str
-    ) -> fmt::Result {
-        f.write_str(name)
-    }
-
-    fn write_ind(
-        &self,
-        f:  &mut Formatter,
-        ea: &AsmDisplay
-    ) -> fmt::Result {
-        write!(f, "[{}]", Asm(ea, self))
-    }
-
-    fn write_ind_predec(
-        &self,
-        f:   &mut Formatter,
-        reg: &AsmDisplay
-    ) -> fmt::Result {
-        write!(f, "[--{}]", Asm(reg, self))
-    }
-
-    fn write_ind_postinc(
-        &self,
-        f:   &mut Formatter,
-        reg: &AsmDisplay
-    ) -> fmt::Result {
-        write!(f, "[{}++]", Asm(reg, self))
-    }
-
-    fn write_base_disp(
-        &self,
-        f:    &mut Formatter,
-        base: &AsmDisplay,
-        disp: &AsmDisplay
-    ) -> fmt::Result {
-        write!(
-            f, "[{base}+{disp}]",
-            base = Asm(base, self),
-            disp = Asm(disp, self)
-        )
-    }
-
-    fn write_base_disp_idx(
-        &self,
-        f:     &mut Formatter,
-        base:  &AsmDisplay,
-        disp:  &AsmDisplay,
-        index: &AsmDisplay,
-        scale: &AsmDisplay
-    ) -> fmt::Result {
-        write!(
-            f, "[{base}+{index}*{scale}+{disp}]",
-            base  = Asm(base,  self),
-            disp  = Asm(disp,  self),
-            index = Asm(index, self),
-            scale = Asm(scale, self),
-        )
-    }
-
-    fn write_scale(
-        &self,
-        f:     &mut Formatter,
-        scale: u8
-    ) -> fmt::Result {
-        write!(f, "{}", scale)
-    }
-*/
     fn write_deref(&self, f: &mut Formatter, expr: &Deref<C>) -> fmt::Result {
         f.write_char('[')?;
         self.write_list(f, " + ", &*expr.terms)?;
         f.write_char(']')
     }
+
+    fn write_reg_list(&self, f: &mut Formatter, items: &[(Reg<C>, Option<Reg<C>>)])
+                     -> fmt::Result {
+        for (i, item) in items.iter().enumerate() {
+            if i != 0 { f.write_char(',')?; }
+            self.write_reg(f, &item.0)?;
+            if let Some(ref last) = item.1 {
+                f.write_char('-')?;
+                self.write_reg(f, last)?;
+            }
+        }
+        Ok(())
+    }
 }
 
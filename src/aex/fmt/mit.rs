@@ -16,8 +16,9 @@
 // You should have received a copy of the GNU General Public License
 // along with AEx.  If not, see <http://www.gnu.org/licenses/>.
 
-//use std::fmt::{self, Formatter};
-use super::{/*Asm , AsmDisplay,*/ Style};
+use std::fmt::{self, Formatter, Write};
+use aex::ast::*;
+use super::{Style, ToStyled};
 
 /// MIT assembly style.
 #[derive(Clone, Debug)]
@@ -25,6 +26,7 @@ pub struct MitStyle {
     pub arg_spaces: bool,
     pub reg_prefix: &'static str,
     pub imm_prefix: &'static str,
+    pub prefer_aliases: bool,
 }
 
 impl Default for MitStyle {
@@ -33,82 +35,73 @@ impl Default for MitStyle {
             arg_spaces: false,
             reg_prefix: "%",
             imm_prefix: "#",
+            prefer_aliases: false,
         }
     }
 }
 
 impl<C> Style<C> for MitStyle {
-/*
-    fn write_reg(
-        &self,
-        f:    &mut Formatter,
-        name: &str
-    ) -> fmt::Result {
-        write!(f, "{}{}", self.reg_prefix, name)
+    fn write_reg(&self, f: &mut Formatter, reg: &Reg<C>) -> fmt::Result {
+        write!(f, "{}{}", self.reg_prefix, reg.name)
     }
 
-    fn write_ind(
-        &self,
-        f:  &mut Formatter,
-        ea: &AsmDisplay
-    ) -> fmt::Result {
-        write!(f, "{}@", Asm(ea, self))
+    fn prefer_aliases(&self) -> bool {
+        self.prefer_aliases
     }
 
-    fn write_ind_predec(
-        &self,
-        f:   &mut Formatter,
-        reg: &AsmDisplay
-    ) -> fmt::Result {
-        write!(f, "{}@-", Asm(reg, self))
+    fn write_op(&self, f: &mut Formatter, name: &str, size: &str) -> fmt::Result {
+        write!(f, "{}{}", name, size)
     }
 
-    fn write_ind_postinc(
-        &self,
-        f:   &mut Formatter,
-        reg: &AsmDisplay
-    ) -> fmt::Result {
-        write!(f, "{}@+", Asm(reg, self))
+    fn write_arg_sep(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(if self.arg_spaces { ", " } else { "," })
     }
 
-    fn write_base_disp(
-        &self,
-        f:    &mut Formatter,
-        base: &AsmDisplay,
-        disp: &AsmDisplay
-    ) -> fmt::Result {
+    fn write_imm(&self, f: &mut Formatter, expr: &Expr<C>) -> fmt::Result {
+        write!(f, "{}{}", self.imm_prefix, expr.styled(self))
+    }
+
+    fn write_abs(&self, f: &mut Formatter, addr: &Expr<C>, long: bool) -> fmt::Result {
+        write!(f, "{}:{}", addr.styled(self), if long { 'l' } else { 'w' })
+    }
+
+    fn write_ind(&self, f: &mut Formatter, base: &Reg<C>) -> fmt::Result {
+        write!(f, "{}@", base.styled(self))
+    }
+
+    fn write_ind_predec(&self, f: &mut Formatter, base: &Reg<C>) -> fmt::Result {
+        write!(f, "{}@-", base.styled(self))
+    }
+
+    fn write_ind_postinc(&self, f: &mut Formatter, base: &Reg<C>) -> fmt::Result {
+        write!(f, "{}@+", base.styled(self))
+    }
+
+    fn write_base_disp(&self, f: &mut Formatter, base: &Reg<C>, disp: &Expr<C>)
+                      -> fmt::Result {
         write!(
             f, "{base}@({disp})",
-            base = Asm(base, self),
-            disp = Asm(disp, self)
+            base = base.styled(self),
+            disp = disp.styled(self),
         )
     }
 
-    fn write_base_disp_idx(
-        &self,
-        f:     &mut Formatter,
-        base:  &AsmDisplay,
-        disp:  &AsmDisplay,
-        index: &AsmDisplay,
-        scale: &AsmDisplay
-    ) -> fmt::Result {
+    fn write_base_disp_idx(&self,
+                           f:     &mut Formatter,
+                           base:  &Reg<C>,
+                           disp:  &Expr<C>,
+                           index: &Reg<C>,
+                           scale: u8)
+                          -> fmt::Result {
         write!(
-            f, "{base}@({disp},{sp}{index}:{scale})",
+            f, "{base}@({disp},{sp}{index}:",
             sp    = if self.arg_spaces {" "} else {""},
-            base  = Asm(base,  self),
-            disp  = Asm(disp,  self),
-            index = Asm(index, self),
-            scale = Asm(scale, self),
-        )
-    }
-
-    fn write_scale(
-        &self,
-        f:     &mut Formatter,
-        scale: u8
-    ) -> fmt::Result {
-        write!(f, "{}", scale)
+            base  = base .styled(self),
+            disp  = disp .styled(self),
+            index = index.styled(self),
+        )?;
+        Style::<C>::write_scale(self, f, scale)?;
+        f.write_char(')')
     }
-*/
 }
 
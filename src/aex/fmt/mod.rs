@@ -22,10 +22,12 @@ use aex::ast::*;
 pub mod att;
 pub mod intel;
 pub mod mit;
+pub mod motorola;
 
 pub use self::att::*;
 pub use self::intel::*;
 pub use self::mit::*;
+pub use self::motorola::*;
 
 // -----------------------------------------------------------------------------
 
@@ -116,6 +118,17 @@ pub trait Style<A> : Debug {
         write!(f, "{}", num.value)
     }
 
+    /// Writes an address to the given formatter in this code style.
+    fn write_addr(&self, f: &mut Formatter, addr: u64) -> fmt::Result {
+        write!(f, "{:#x}", addr)
+    }
+
+    /// Writes a floating-point literal to the given formatter in this code
+    /// style.
+    fn write_float(&self, f: &mut Formatter, num: &Float<A>) -> fmt::Result {
+        write!(f, "{:?}", num.value)
+    }
+
     /// Writes a register to the given formatter in this code style.
     fn write_reg(&self, f: &mut Formatter, reg: &Reg<A>) -> fmt::Result {
         f.write_str(reg.name)
@@ -157,6 +170,108 @@ pub trait Style<A> : Debug {
         self.write_list(f, ", ", &*expr.terms)?;
         f.write_char(']')
     }
+
+    /// Returns whether this code style prefers alternate names, such as
+    /// condition aliases (`hs` for `cc`), where a target defines them.
+    fn prefer_aliases(&self) -> bool {
+        false
+    }
+
+    /// Writes an instruction name with an optional size suffix to the given
+    /// formatter in this code style.
+    fn write_op(&self, f: &mut Formatter, name: &str, size: &str) -> fmt::Result {
+        f.write_str(name)?;
+        if !size.is_empty() { write!(f, ".{}", size)?; }
+        Ok(())
+    }
+
+    /// Writes the separator between instruction operands to the given
+    /// formatter in this code style.
+    fn write_arg_sep(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(", ")
+    }
+
+    /// Writes a register list to the given formatter in this code style.
+    /// Each item is a register, or an inclusive range of registers from the
+    /// first to the second.
+    fn write_reg_list(&self, f: &mut Formatter, items: &[(Reg<A>, Option<Reg<A>>)])
+                     -> fmt::Result {
+        for (i, item) in items.iter().enumerate() {
+            if i != 0 { f.write_char('/')?; }
+            self.write_reg(f, &item.0)?;
+            if let Some(ref last) = item.1 {
+                f.write_char('-')?;
+                self.write_reg(f, last)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes an immediate operand to the given formatter in this code style.
+    fn write_imm(&self, f: &mut Formatter, expr: &Expr<A>) -> fmt::Result {
+        write!(f, "{}", expr.styled(self))
+    }
+
+    /// Writes an absolute memory operand to the given formatter in this code
+    /// style.  The address is a longword if `long` is true, or a sign-extended
+    /// word otherwise.
+    #[allow(unused_variables)]
+    fn write_abs(&self, f: &mut Formatter, addr: &Expr<A>, long: bool) -> fmt::Result {
+        write!(f, "[{}]", addr.styled(self))
+    }
+
+    /// Writes a register-indirect operand to the given formatter in this code
+    /// style.
+    fn write_ind(&self, f: &mut Formatter, base: &Reg<A>) -> fmt::Result {
+        write!(f, "[{}]", base.styled(self))
+    }
+
+    /// Writes a register-indirect operand with pre-decrement to the given
+    /// formatter in this code style.
+    fn write_ind_predec(&self, f: &mut Formatter, base: &Reg<A>) -> fmt::Result {
+        write!(f, "[--{}]", base.styled(self))
+    }
+
+    /// Writes a register-indirect operand with post-increment to the given
+    /// formatter in this code style.
+    fn write_ind_postinc(&self, f: &mut Formatter, base: &Reg<A>) -> fmt::Result {
+        write!(f, "[{}++]", base.styled(self))
+    }
+
+    /// Writes a base-plus-displacement operand to the given formatter in this
+    /// code style.
+    fn write_base_disp(&self, f: &mut Formatter, base: &Reg<A>, disp: &Expr<A>)
+                      -> fmt::Result {
+        write!(
+            f, "[{base}+{disp}]",
+            base = base.styled(self),
+            disp = disp.styled(self),
+        )
+    }
+
+    /// Writes a base-plus-scaled-index-plus-displacement operand to the given
+    /// formatter in this code style.
+    fn write_base_disp_idx(&self,
+                           f:     &mut Formatter,
+                           base:  &Reg<A>,
+                           disp:  &Expr<A>,
+                           index: &Reg<A>,
+                           scale: u8)
+                          -> fmt::Result {
+        write!(
+            f, "[{base}+{index}*",
+            base  = base .styled(self),
+            index = index.styled(self),
+        )?;
+        self.write_scale(f, scale)?;
+        write!(f, "+{}]", disp.styled(self))
+    }
+
+    /// Writes an index scaling factor to the given formatter in this code
+    /// style.
+    fn write_scale(&self, f: &mut Formatter, scale: u8) -> fmt::Result {
+        write!(f, "{}", scale)
+    }
 }
 
 pub trait StyleExt<A>: Style<A> {
@@ -204,6 +319,13 @@ mod tests {
         assert_eq!(s, "42");
     }
 
+    #[test]
+    fn write_float() {
+        let x = Float::from(1.5);
+        let s = format!("{}", x.styled(&DefaultStyle));
+        assert_eq!(s, "1.5");
+    }
+
     #[test]
     fn write_reg() {
         let i = Reg::new("a");
@@ -245,5 +367,55 @@ mod tests {
         let s = format!("{}", e.styled(&DefaultStyle));
         assert_eq!(s, "[a, b, c]");
     }
+
+    #[test]
+    fn write_addressing() {
+        struct Ops;
+        impl Display for Ops {
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                write_ops(f, &DefaultStyle)
+            }
+        }
+
+        fn write_ops<S: Style<()>>(f: &mut Formatter, s: &S) -> fmt::Result {
+            let r = Reg::new("a0");
+            let x = Reg::new("d1");
+            let d = Expr::Int(Int::from(4));
+
+            s.write_op(f, "move", "l")?;
+            f.write_char(' ')?;
+            s.write_ind(f, &r)?;
+            s.write_arg_sep(f)?;
+            s.write_ind_predec(f, &r)?;
+            s.write_arg_sep(f)?;
+            s.write_ind_postinc(f, &r)?;
+            s.write_arg_sep(f)?;
+            s.write_base_disp(f, &r, &d)?;
+            s.write_arg_sep(f)?;
+            s.write_base_disp_idx(f, &r, &d, &x, 4)?;
+            s.write_arg_sep(f)?;
+            s.write_abs(f, &d, true)?;
+            s.write_arg_sep(f)?;
+            s.write_imm(f, &d)
+        }
+
+        let s = Ops.to_string();
+        assert_eq!(s, "move.l [a0], [--a0], [a0++], [a0+4], [a0+d1*4+4], [4], 4");
+    }
+
+    #[test]
+    fn write_reg_list() {
+        struct List;
+        impl Display for List {
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                Style::<()>::write_reg_list(&DefaultStyle, f, &[
+                    (Reg::new("d0"), Some(Reg::new("d3"))),
+                    (Reg::new("a2"), None),
+                ])
+            }
+        }
+
+        assert_eq!(List.to_string(), "d0-d3/a2");
+    }
 }
 
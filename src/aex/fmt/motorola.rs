@@ -0,0 +1,103 @@
+// Motorola Assembly Style
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt::{self, Formatter, Write};
+use aex::ast::*;
+use super::{Style, ToStyled};
+
+/// Motorola assembly style.
+#[derive(Clone, Debug)]
+pub struct MotorolaStyle {
+    pub arg_spaces: bool,
+    pub reg_prefix: &'static str,
+    pub imm_prefix: &'static str,
+    pub prefer_aliases: bool,
+}
+
+impl Default for MotorolaStyle {
+    fn default() -> Self {
+        MotorolaStyle {
+            arg_spaces: false,
+            reg_prefix: "",
+            imm_prefix: "#",
+            prefer_aliases: false,
+        }
+    }
+}
+
+impl<C> Style<C> for MotorolaStyle {
+    fn write_reg(&self, f: &mut Formatter, reg: &Reg<C>) -> fmt::Result {
+        write!(f, "{}{}", self.reg_prefix, reg.name)
+    }
+
+    fn prefer_aliases(&self) -> bool {
+        self.prefer_aliases
+    }
+
+    fn write_arg_sep(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(if self.arg_spaces { ", " } else { "," })
+    }
+
+    fn write_imm(&self, f: &mut Formatter, expr: &Expr<C>) -> fmt::Result {
+        write!(f, "{}{}", self.imm_prefix, expr.styled(self))
+    }
+
+    fn write_abs(&self, f: &mut Formatter, addr: &Expr<C>, long: bool) -> fmt::Result {
+        write!(f, "({}).{}", addr.styled(self), if long { 'l' } else { 'w' })
+    }
+
+    fn write_ind(&self, f: &mut Formatter, base: &Reg<C>) -> fmt::Result {
+        write!(f, "({})", base.styled(self))
+    }
+
+    fn write_ind_predec(&self, f: &mut Formatter, base: &Reg<C>) -> fmt::Result {
+        write!(f, "-({})", base.styled(self))
+    }
+
+    fn write_ind_postinc(&self, f: &mut Formatter, base: &Reg<C>) -> fmt::Result {
+        write!(f, "({})+", base.styled(self))
+    }
+
+    fn write_base_disp(&self, f: &mut Formatter, base: &Reg<C>, disp: &Expr<C>)
+                      -> fmt::Result {
+        write!(
+            f, "({disp},{sp}{base})",
+            sp   = if self.arg_spaces {" "} else {""},
+            base = base.styled(self),
+            disp = disp.styled(self),
+        )
+    }
+
+    fn write_base_disp_idx(&self,
+                           f:     &mut Formatter,
+                           base:  &Reg<C>,
+                           disp:  &Expr<C>,
+                           index: &Reg<C>,
+                           scale: u8)
+                          -> fmt::Result {
+        write!(
+            f, "({disp},{sp}{base},{sp}{index}.l*",
+            sp    = if self.arg_spaces {" "} else {""},
+            base  = base .styled(self),
+            disp  = disp .styled(self),
+            index = index.styled(self),
+        )?;
+        Style::<C>::write_scale(self, f, scale)?;
+        f.write_char(')')
+    }
+}
@@ -18,6 +18,7 @@
 
 use std::io::Result;
 use std::mem::{size_of, transmute};
+use std::ptr::read_unaligned;
 
 use aex::util::{ByteOrder, Endian};
 use super::RewindRead;
@@ -26,7 +27,7 @@ macro_rules! read_as {
     ($r:expr, $t:ty) => {{
         let order = $r.byte_order();
         let bytes = $r.read_bytes(size_of::<$t>())?;
-        let value = unsafe { *( bytes.as_ptr() as *const $t ) };
+        let value = unsafe { read_unaligned(bytes.as_ptr() as *const $t) };
         Ok(value.to_order(order))
     }};
 }
@@ -156,6 +157,18 @@ impl<R: RewindRead> DecodeReader<R> {
     pub fn new(inner: R, order: ByteOrder, reloc: u64) -> Self {
         Self { inner, order, reloc }
     }
+
+    /// Returns a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
 }
 
 impl<R: RewindRead> DecodeRead for DecodeReader<R> {
@@ -224,7 +237,7 @@ impl<R: RewindRead> DecodeRead for DecodeReader<R> {
 mod tests {
     use std::io::Cursor;
     use aex::util::{BE, LE};
-    use super::super::RewindReader;
+    use super::super::{RewindReader, SliceReader};
     use super::*;
 
     #[test]
@@ -402,6 +415,17 @@ mod tests {
         assert_eq!(format!("{:e}", r.read_f64().unwrap()), "-4.886459655043775e235");
     }
 
+    #[test]
+    fn seek_inner() {
+        let src   = [0x12, 0x34, 0x56, 0x78];
+        let mut r = DecodeReader::new(SliceReader::new(&src), BE, 0x100);
+
+        r.get_mut().seek(2);
+        assert_eq!(r.vma(), 0x102);
+        assert_eq!(r.read_u16().unwrap(), 0x5678);
+        assert_eq!(r.get_ref().pending_len(), 2);
+    }
+
     fn reader() -> Box<DecodeRead> {
         let src = vec![0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0];
         let src = Cursor::new(src);
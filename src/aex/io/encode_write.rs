@@ -0,0 +1,205 @@
+// Encoding Writer
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::{Result, Write};
+
+use aex::util::{ByteOrder, Endian};
+
+macro_rules! write_as {
+    ($w:expr, $v:expr) => {{
+        let order = $w.byte_order();
+        $w.write_bytes(&$v.to_order(order).to_ne_bytes())
+    }};
+}
+
+/// A writer suitable for encoding machine code.
+///
+/// The counterpart of `DecodeRead`.  An `EncodeWrite` maintains a current
+/// byte order, which is used when writing multi-byte primitive values
+/// (`u32`, etc.).
+///
+pub trait EncodeWrite {
+    /// Returns the byte order used to encode multi-byte values.
+    fn byte_order(&self) -> ByteOrder;
+
+    /// Sets the byte order used to encode multi-byte values.
+    fn set_byte_order(&mut self, order: ByteOrder);
+
+    /// Writes all of the given bytes.
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()>;
+
+    /// Writes a `u8`.
+    #[inline]
+    fn write_u8(&mut self, v: u8) -> Result<()> {
+        self.write_bytes(&[v])
+    }
+
+    /// Writes an `i8`.
+    #[inline]
+    fn write_i8(&mut self, v: i8) -> Result<()> {
+        self.write_u8(v as u8)
+    }
+
+    /// Writes a `u16`.
+    #[inline]
+    fn write_u16(&mut self, v: u16) -> Result<()> {
+        write_as!(self, v)
+    }
+
+    /// Writes an `i16`.
+    #[inline]
+    fn write_i16(&mut self, v: i16) -> Result<()> {
+        write_as!(self, v)
+    }
+
+    /// Writes a `u32`.
+    #[inline]
+    fn write_u32(&mut self, v: u32) -> Result<()> {
+        write_as!(self, v)
+    }
+
+    /// Writes an `i32`.
+    #[inline]
+    fn write_i32(&mut self, v: i32) -> Result<()> {
+        write_as!(self, v)
+    }
+
+    /// Writes a `u64`.
+    #[inline]
+    fn write_u64(&mut self, v: u64) -> Result<()> {
+        write_as!(self, v)
+    }
+
+    /// Writes an `i64`.
+    #[inline]
+    fn write_i64(&mut self, v: i64) -> Result<()> {
+        write_as!(self, v)
+    }
+
+    /// Writes an `f32`.
+    #[inline]
+    fn write_f32(&mut self, v: f32) -> Result<()> {
+        self.write_u32(v.to_bits())
+    }
+
+    /// Writes an `f64`.
+    #[inline]
+    fn write_f64(&mut self, v: f64) -> Result<()> {
+        self.write_u64(v.to_bits())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EncodeWriter<W: Write> {
+    inner: W,
+    order: ByteOrder,
+}
+
+impl<W: Write> EncodeWriter<W> {
+    pub fn new(inner: W, order: ByteOrder) -> Self {
+        Self { inner, order }
+    }
+
+    /// Returns a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwraps the writer, returning the underlying writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> EncodeWrite for EncodeWriter<W> {
+    /// Returns the byte order used to encode multi-byte values.
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        self.order
+    }
+
+    /// Sets the byte order used to encode multi-byte values.
+    #[inline]
+    fn set_byte_order(&mut self, order: ByteOrder) {
+        self.order = order;
+    }
+
+    /// Writes all of the given bytes.
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.inner.write_all(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aex::util::{BE, LE};
+    use super::*;
+
+    #[test]
+    fn write_u8() {
+        let mut w = EncodeWriter::new(vec![], BE);
+        w.write_u8(0x12).unwrap();
+        w.write_i8(-1).unwrap();
+        assert_eq!(w.into_inner(), vec![0x12, 0xFF]);
+    }
+
+    #[test]
+    fn write_int_be() {
+        let mut w = EncodeWriter::new(vec![], BE);
+        w.write_u16(0x1234).unwrap();
+        w.write_i32(-2).unwrap();
+        assert_eq!(w.into_inner(), vec![0x12, 0x34, 0xFF, 0xFF, 0xFF, 0xFE]);
+    }
+
+    #[test]
+    fn write_int_le() {
+        let mut w = EncodeWriter::new(vec![], LE);
+        w.write_u16(0x1234).unwrap();
+        w.write_u32(0x12345678).unwrap();
+        assert_eq!(w.into_inner(), vec![0x34, 0x12, 0x78, 0x56, 0x34, 0x12]);
+    }
+
+    #[test]
+    fn write_float() {
+        let mut w = EncodeWriter::new(vec![], BE);
+        w.write_f32(1.5).unwrap();
+        w.write_f64(-2.5).unwrap();
+        assert_eq!(w.into_inner(), vec![
+            0x3F, 0xC0, 0x00, 0x00,
+            0xC0, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ]);
+    }
+
+    #[test]
+    fn set_byte_order() {
+        let mut w = EncodeWriter::new(vec![], BE);
+        w.set_byte_order(LE);
+        assert_eq!(w.byte_order(), LE);
+        w.write_i16(1).unwrap();
+        assert_eq!(w.get_ref(), &vec![0x01, 0x00]);
+    }
+}
@@ -20,9 +20,13 @@ use std::io::{Read, Result};
 use std::io::ErrorKind::*;
 
 mod rewind_read;
+mod slice_read;
 mod decode_read;
+mod encode_write;
 pub use self::rewind_read::*;
+pub use self::slice_read::*;
 pub use self::decode_read::*;
+pub use self::encode_write::*;
 
 /// Extends `std::io::Read` with the `read_to_buf` method.
 pub trait ReadToBuf: Read {
@@ -0,0 +1,162 @@
+// Random-Access Slice Reader
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::{Error as E, Result};
+use std::io::ErrorKind::*;
+
+use super::RewindRead;
+
+/// A rewindable reader over an in-memory byte slice.
+///
+/// Unlike `RewindReader`, a `SliceReader` can seek to any position, which
+/// makes it suitable for random access into an image.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct SliceReader<'a> {
+    bytes: &'a [u8],    // entire slice
+    pos:   usize,       // position of first pending byte; consumed byte count
+    len:   usize,       // pending byte count
+}
+
+impl<'a> SliceReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0, len: 0 }
+    }
+
+    /// Returns the entire underlying slice.
+    #[inline]
+    pub fn get_ref(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Moves the reader to the given position, forgetting pending bytes.
+    /// Positions past the end of the slice are allowed; reads there fail.
+    #[inline]
+    pub fn seek(&mut self, pos: usize) {
+        self.pos = pos;
+        self.len = 0;
+    }
+}
+
+impl<'a> RewindRead for SliceReader<'a> {
+    #[inline]
+    fn consumed_pos(&self) -> usize {
+        self.pos
+    }
+
+    #[inline]
+    fn pending_len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn pending_pos(&self) -> usize {
+        self.pos + self.len
+    }
+
+    #[inline]
+    fn pending_bytes(&self) -> &[u8] {
+        &self.bytes[self.pos..self.pos + self.len]
+    }
+
+    #[inline]
+    fn consume(&mut self) {
+        self.pos += self.len;
+        self.len  = 0;
+    }
+
+    #[inline]
+    fn rewind(&mut self) {
+        self.len = 0;
+    }
+
+    fn read_bytes(&mut self, n: usize) -> Result<&[u8]> {
+        let beg = self.pos + self.len;
+        let end = match beg.checked_add(n) {
+            Some(x) => x,
+            None    => panic!("read_bytes: would overflow buffer"),
+        };
+
+        if end > self.bytes.len() {
+            return Err(E::new(UnexpectedEof, "failed to read requested bytes"));
+        }
+
+        self.len += n;
+        Ok(&self.bytes[beg..end])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static BYTES: [u8; 8] = [0, 1, 2, 3, 4, 5, 6, 7];
+
+    #[test]
+    fn read_and_consume() {
+        let mut r = SliceReader::new(&BYTES);
+
+        assert_eq!(r.read_bytes(2).unwrap(), &[0, 1]);
+        assert_eq!(r.pending_bytes(), &[0, 1]);
+        r.consume();
+
+        assert_eq!(r.consumed_pos(), 2);
+        assert_eq!(r.pending_len(),  0);
+        assert_eq!(r.read_bytes(3).unwrap(), &[2, 3, 4]);
+        assert_eq!(r.pending_pos(),  5);
+    }
+
+    #[test]
+    fn read_and_rewind() {
+        let mut r = SliceReader::new(&BYTES);
+
+        r.read_bytes(3).unwrap();
+        r.rewind();
+
+        assert_eq!(r.consumed_pos(), 0);
+        assert_eq!(r.pending_len(),  0);
+        assert_eq!(r.read_bytes(1).unwrap(), &[0]);
+    }
+
+    #[test]
+    fn seek() {
+        let mut r = SliceReader::new(&BYTES);
+
+        r.read_bytes(2).unwrap();
+        r.seek(6);
+
+        assert_eq!(r.consumed_pos(), 6);
+        assert_eq!(r.pending_len(),  0);
+        assert_eq!(r.read_bytes(2).unwrap(), &[6, 7]);
+
+        r.seek(1);
+        assert_eq!(r.read_bytes(1).unwrap(), &[1]);
+    }
+
+    #[test]
+    fn read_past_end() {
+        let mut r = SliceReader::new(&BYTES);
+
+        r.seek(7);
+        assert_eq!(r.read_bytes(2).unwrap_err().kind(), UnexpectedEof);
+        assert_eq!(r.pending_len(), 0);
+
+        r.seek(9);
+        assert_eq!(r.read_bytes(1).unwrap_err().kind(), UnexpectedEof);
+    }
+}
@@ -0,0 +1,370 @@
+// Diagnostic Messages
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+
+use aex::source::Source;
+
+use self::MessageId::*;
+use self::MessageLevel::*;
+
+/// A collection of diagnostic messages.
+#[derive(Clone, Default, Debug)]
+pub struct Messages<'a> {
+    messages:    Vec<Message<'a>>,
+    error_count: usize,
+}
+
+/// A diagnostic message.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Message<'a> {
+    /// Source to which the message pertains.
+    pub source: Source<'a>,
+
+    /// Severity.
+    pub level: MessageLevel,
+
+    /// Message identifier.
+    pub id: MessageId,
+
+    /// Message text.
+    pub text: Cow<'static, str>,
+}
+
+/// Message severity levels.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[repr(u8)]
+pub enum MessageLevel {
+    Note,
+    Warning,
+    Error,
+}
+
+/// Message identifiers.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum MessageId {
+    // Parser Messages
+    Unrec,
+    Expected,
+
+    // Symbol Messages
+    SymRedefined,
+    SymUndefined,
+
+    // Evaluation Messages
+    NotAbsolute,
+    DivByZero,
+
+    // Assembler Messages
+    UnknownOp,
+    UnknownReg,
+    Encode,
+    NoConvergence,
+
+    // Directive Messages
+    UnknownDirective,
+    ValueRange,
+    OrgBackward,
+    BadAlign,
+    DataInBss,
+    ReadFailed,
+
+    // Macro Messages
+    MacroRedefined,
+    Unmatched,
+    MacroArgs,
+    MacroDepth,
+    MacroSite,
+
+    // User Messages
+    UserError,
+    UserWarning,
+
+    // Local Label Messages
+    LocalUndefined,
+
+    // Address Messages
+    AddrOverflow,
+
+    // Repetition Messages
+    RepeatCount,
+}
+
+impl<'a> Messages<'a> {
+    /// Creates an empty collection of messages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns whether the collection contains any errors.
+    #[inline]
+    pub fn has_errors(&self) -> bool {
+        self.error_count > 0
+    }
+
+    /// Returns the number of errors in the collection.
+    #[inline]
+    pub fn error_count(&self) -> usize {
+        self.error_count
+    }
+
+    /// Returns the messages in the collection, in the order added.
+    #[inline]
+    pub fn as_slice(&self) -> &[Message<'a>] {
+        &self.messages
+    }
+
+    /// Adds the given message to the collection.
+    pub fn push(&mut self, m: Message<'a>) {
+        if m.level >= Error {
+            self.error_count += 1;
+        }
+        self.messages.push(m);
+    }
+
+    fn add<T>(&mut self, s: Source<'a>, l: MessageLevel, i: MessageId, t: T)
+             where T: Into<Cow<'static, str>> {
+        self.push(Message { source: s, level: l, id: i, text: t.into() });
+    }
+
+    pub fn err_unrec(&mut self, s: Source<'a>, c: char) {
+        self.add(s, Error, Unrec, format!(
+            "Unrecognized character: '{}'", c
+        ));
+    }
+
+    pub fn err_expected(&mut self, s: Source<'a>, description: &str) {
+        self.add(s, Error, Expected, format!(
+            "Expected: {}", description
+        ));
+    }
+
+    pub fn err_sym_redefined(&mut self, s: Source<'a>, name: &str) {
+        self.add(s, Error, SymRedefined, format!(
+            "Symbol already defined: {}", name
+        ));
+    }
+
+    pub fn err_sym_undefined(&mut self, s: Source<'a>, name: &str) {
+        self.add(s, Error, SymUndefined, format!(
+            "Symbol not defined: {}", name
+        ));
+    }
+
+    pub fn err_not_absolute(&mut self, s: Source<'a>) {
+        self.add(s, Error, NotAbsolute,
+            "Operation requires absolute (non-relocatable) operands."
+        );
+    }
+
+    pub fn err_div_by_zero(&mut self, s: Source<'a>) {
+        self.add(s, Error, DivByZero,
+            "Division by zero."
+        );
+    }
+
+    pub fn err_unknown_op(&mut self, s: Source<'a>, name: &str) {
+        self.add(s, Error, UnknownOp, format!(
+            "Unknown instruction: {}", name
+        ));
+    }
+
+    pub fn err_unknown_reg(&mut self, s: Source<'a>, name: &str) {
+        self.add(s, Error, UnknownReg, format!(
+            "Unknown register: {}", name
+        ));
+    }
+
+    pub fn err_encode<E: Display>(&mut self, s: Source<'a>, e: E) {
+        self.add(s, Error, Encode, format!(
+            "Cannot encode instruction: {}", e
+        ));
+    }
+
+    pub fn err_no_convergence(&mut self, s: Source<'a>, passes: usize) {
+        self.add(s, Error, NoConvergence, format!(
+            "Layout did not converge after {} passes.", passes
+        ));
+    }
+
+    pub fn err_unknown_directive(&mut self, s: Source<'a>, name: &str) {
+        self.add(s, Error, UnknownDirective, format!(
+            "Unknown directive: {}", name
+        ));
+    }
+
+    pub fn err_value_range<V: Display>(&mut self, s: Source<'a>, value: V, bytes: u8) {
+        self.add(s, Error, ValueRange, format!(
+            "Value {} does not fit in {} byte(s).", value, bytes
+        ));
+    }
+
+    pub fn err_org_backward(&mut self, s: Source<'a>) {
+        self.add(s, Error, OrgBackward,
+            "Cannot move the location counter backward."
+        );
+    }
+
+    pub fn err_bad_align(&mut self, s: Source<'a>) {
+        self.add(s, Error, BadAlign,
+            "Alignment must be a power of two."
+        );
+    }
+
+    pub fn err_data_in_bss(&mut self, s: Source<'a>, section: &str) {
+        self.add(s, Error, DataInBss, format!(
+            "Section {} cannot contain non-zero data.", section
+        ));
+    }
+
+    pub fn err_read_failed<E: Display>(&mut self, s: Source<'a>, path: &str, e: E) {
+        self.add(s, Error, ReadFailed, format!(
+            "Cannot read file {}: {}", path, e
+        ));
+    }
+
+    pub fn err_macro_redefined(&mut self, s: Source<'a>, name: &str) {
+        self.add(s, Error, MacroRedefined, format!(
+            "Macro already defined: {}", name
+        ));
+    }
+
+    pub fn err_unmatched(&mut self, s: Source<'a>, name: &str) {
+        self.add(s, Error, Unmatched, format!(
+            "Unmatched directive: {}", name
+        ));
+    }
+
+    pub fn err_macro_args(&mut self, s: Source<'a>, name: &str) {
+        self.add(s, Error, MacroArgs, format!(
+            "Too many arguments for macro: {}", name
+        ));
+    }
+
+    pub fn err_macro_depth(&mut self, s: Source<'a>, depth: usize) {
+        self.add(s, Error, MacroDepth, format!(
+            "Macro expansion exceeds {} levels.", depth
+        ));
+    }
+
+    pub fn note_macro_site(&mut self, s: Source<'a>, name: &str) {
+        self.add(s, Note, MacroSite, format!(
+            "In expansion of macro: {}", name
+        ));
+    }
+
+    pub fn err_user(&mut self, s: Source<'a>, text: String) {
+        self.add(s, Error, UserError, text);
+    }
+
+    pub fn warn_user(&mut self, s: Source<'a>, text: String) {
+        self.add(s, Warning, UserWarning, text);
+    }
+
+    pub fn err_local_undefined(&mut self, s: Source<'a>, name: &str, place: &str) {
+        self.add(s, Error, LocalUndefined, format!(
+            "Local label not defined {}: {}", place, name
+        ));
+    }
+
+    pub fn err_addr_overflow(&mut self, s: Source<'a>) {
+        self.add(s, Error, AddrOverflow,
+            "Location counter would exceed the 32-bit address space."
+        );
+    }
+
+    pub fn err_repeat_count(&mut self, s: Source<'a>, max: usize) {
+        self.add(s, Error, RepeatCount, format!(
+            "Repetition count exceeds {}.", max
+        ));
+    }
+}
+
+impl<'a> Display for Messages<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for m in &self.messages {
+            writeln!(f, "{}", m)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Display for Message<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}: {}{:03}: {}",
+            self.source,
+            match self.level { Note => 'N', Warning => 'W', Error => 'E' },
+            self.id as u16,
+            self.text
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aex::source::{File, Pos, Source};
+
+    #[test]
+    fn messages_empty() {
+        let m = Messages::new();
+
+        assert_eq!(m.error_count(), 0);
+        assert!(!m.has_errors());
+        assert_eq!(m.to_string(), "");
+    }
+
+    #[test]
+    fn messages_single() {
+        let     f = File::new("file", "");
+        let     s = Source::File { file: &f, pos: Pos::bof(), len: 1 };
+        let mut m = Messages::new();
+
+        m.err_unrec(s, 'c');
+
+        assert_eq!(m.error_count(), 1);
+        assert!(m.has_errors());
+        assert_eq!(m.to_string(), "file:1:1: E000: Unrecognized character: 'c'\n");
+    }
+
+    #[test]
+    fn messages_multiple() {
+        let     f  = File::new("file", "");
+        let     p0 = Pos::bof();
+        let mut p1 = Pos::bof();
+        let mut m  = Messages::new();
+
+        p1.advance('c');
+
+        let s0 = Source::File { file: &f, pos: p0, len: 1 };
+        let s1 = Source::File { file: &f, pos: p1, len: 1 };
+
+        m.err_unrec(s0, 'c');
+        m.err_sym_undefined(s1, "x");
+
+        assert_eq!(m.error_count(), 2);
+        assert_eq!(m.as_slice()[1].id, SymUndefined);
+        assert_eq!(
+            m.to_string(),
+            "file:1:1: E000: Unrecognized character: 'c'\n\
+             file:1:2: E003: Symbol not defined: x\n"
+        );
+    }
+}
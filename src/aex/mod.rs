@@ -19,10 +19,12 @@
 //#[macro_use]
 pub mod util;
 
+pub mod asm;
 pub mod ast;
 pub mod fmt;
 pub mod io;
-//pub mod source;
+pub mod message;
+pub mod source;
 pub mod target;
-//pub mod types;
+pub mod types;
 
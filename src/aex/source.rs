@@ -124,7 +124,7 @@ pub struct File<'a> {
 impl<'a> File<'a> {
     #[inline]
     pub fn new<D: Into<String>>(name: &'a str, data: D) -> Self {
-        File { name: name, data: data.into() }
+        File { name, data: data.into() }
     }
 
     pub fn from_reader<R: Read>(name: &'a str, mut reader: R) -> Self {
@@ -227,13 +227,13 @@ impl Ord for Pos {
     }
 }
 
-impl<'a> Display for Pos {
+impl Display for Pos {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "{}:{}", self.line, self.column)
     }
 }
 
-impl<'a> Debug for Pos {
+impl Debug for Pos {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "{}:{}[{}]", self.line, self.column, self.byte)
     }
@@ -296,7 +296,7 @@ pub mod tests {
         let sa = Source::File { file: &fa, pos: p, len: 2 };
         let sb = Source::File { file: &fb, pos: p, len: 2 };
 
-        sa | sb;
+        let _ = sa | sb;
     }
 
     // -------------------------------------------------------------------------
@@ -0,0 +1,224 @@
+// ColdFire Opcode Table Checks
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::Cursor;
+use std::slice;
+use aex::io::{DecodeReader, RewindReader};
+use aex::util::BE;
+use super::{decode, Flags, Opcode, EXT_WORD};
+
+/// A problem found in an opcodes table.  Opcodes are identified by their
+/// indexes in the table.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TableIssue {
+    /// The opcode has bits set outside of its mask.
+    BitsOutsideMask(usize),
+
+    /// Two opcodes, supported by a common ISA revision or extension, accept
+    /// the same encoding.  The earlier opcode shadows the later one for that
+    /// encoding.  Includes an example opcode word.
+    Ambiguous(usize, usize, u16),
+
+    /// Every encoding the opcode accepts is accepted by some earlier opcode
+    /// that exists wherever the opcode does.
+    Unreachable(usize),
+}
+
+/// Don't-care bit patterns used to fill opcode extension words and operand
+/// extension words when probing the table.
+const PATTERNS: [u16; 4] = [0x0000, 0xFFFF, 0x1234, 0x0800];
+
+/// Checks the given opcodes table for inconsistencies.
+///
+/// Probes each of the 65536 opcode words, combined with several extension
+/// word patterns, against every opcode whose discriminant bits match.
+///
+pub fn check_opcodes(opcodes: &'static [Opcode]) -> Vec<TableIssue> {
+    let mut issues = vec![];
+
+    for (i, o) in opcodes.iter().enumerate() {
+        if o.bits.0 & !o.mask.0 != 0 || o.bits.1 & !o.mask.1 != 0 {
+            issues.push(TableIssue::BitsOutsideMask(i));
+        }
+    }
+
+    let mut reachable = vec![false; opcodes.len()];
+    let mut ambiguous = vec![];
+
+    for word in 0..0x10000u32 {
+        let word = word as u16;
+
+        let candidates: Vec<usize> = (0..opcodes.len())
+            .filter(|&i| word & opcodes[i].mask.0 == opcodes[i].bits.0)
+            .collect();
+
+        for &p in &PATTERNS {
+            // Reachability: accepted, and not by an earlier opcode that
+            // exists wherever this one does
+            for (n, &b) in candidates.iter().enumerate() {
+                if reachable[b] { continue; }
+
+                let ext = opcodes[b].bits.1 | p & !opcodes[b].mask.1;
+                if !accepts(&opcodes[b], word, ext, p) { continue; }
+
+                let flags    = isa(&opcodes[b]);
+                let shadowed = candidates[..n].iter().any(|&a| {
+                    isa(&opcodes[a]) & flags == flags && accepts(&opcodes[a], word, ext, p)
+                });
+
+                if !shadowed { reachable[b] = true; }
+            }
+
+            // Ambiguity: a common encoding accepted by two opcodes that
+            // share an ISA
+            for (n, &b) in candidates.iter().enumerate() {
+                for &a in &candidates[..n] {
+                    if ambiguous.iter().any(|&(x, y, _)| (x, y) == (a, b)) { continue; }
+
+                    let (oa, ob) = (&opcodes[a], &opcodes[b]);
+                    if isa(oa) & isa(ob) == 0 { continue; }
+
+                    let ext = match (has_ext(oa), has_ext(ob)) {
+                        (true, true) => {
+                            if (oa.bits.1 ^ ob.bits.1) & oa.mask.1 & ob.mask.1 != 0 { continue; }
+                            oa.bits.1 | ob.bits.1 | p & !oa.mask.1 & !ob.mask.1
+                        },
+                        (true,  false) => oa.bits.1 | p & !oa.mask.1,
+                        (false, true ) => ob.bits.1 | p & !ob.mask.1,
+                        (false, false) => p,
+                    };
+
+                    if accepts(oa, word, ext, p) && accepts(ob, word, ext, p) {
+                        ambiguous.push((a, b, word));
+                    }
+                }
+            }
+        }
+    }
+
+    issues.extend(
+        ambiguous.into_iter().map(|(a, b, w)| TableIssue::Ambiguous(a, b, w))
+    );
+    issues.extend(
+        reachable.iter().enumerate()
+            .filter(|&(_, &r)| !r)
+            .map(|(i, _)| TableIssue::Unreachable(i))
+    );
+    issues
+}
+
+#[inline]
+fn isa(o: &Opcode) -> Flags {
+    o.flags & !EXT_WORD
+}
+
+#[inline]
+fn has_ext(o: &Opcode) -> bool {
+    o.flags & EXT_WORD != 0
+}
+
+/// Returns whether the opcode decodes the given opcode word, followed by
+/// the given extension word and then by operand extension words of the
+/// given pattern.
+fn accepts(o: &'static Opcode, word: u16, ext: u16, fill: u16) -> bool {
+    let mut bytes = Vec::with_capacity(16);
+    for w in [word, ext].iter().chain([fill; 6].iter()) {
+        bytes.push((w >> 8) as u8);
+        bytes.push(*w       as u8);
+    }
+
+    let src   = RewindReader::new(Cursor::new(bytes));
+    let mut r = DecodeReader::new(src, BE, 0);
+
+    matches!(decode(slice::from_ref(o), isa(o), &mut r), Ok(Some(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::*;
+
+    static NOP: Opcode = Opcode {
+        name:  Mnemonic::Nop,
+        size:  Size::Zero,
+        bits:  (0x4E71, 0),
+        mask:  (0xFFFF, 0),
+        args:  OperandForms::Nullary,
+        flags: CF_A,
+    };
+
+    static BAD_BITS: [Opcode; 1] = [
+        Opcode { mask: (0xFFF0, 0), ..NOP },
+    ];
+
+    static SHADOWED: [Opcode; 2] = [
+        NOP,
+        Opcode { name: Mnemonic::Rts, ..NOP },
+    ];
+
+    static DISJOINT_ISA: [Opcode; 2] = [
+        NOP,
+        Opcode { name: Mnemonic::Rts, flags: CF_FPU, ..NOP },
+    ];
+
+    static OVERLAP: [Opcode; 2] = [
+        Opcode { mask: (0xFFF0, 0), bits: (0x4E70, 0), ..NOP },
+        NOP,
+    ];
+
+    #[test]
+    fn check_bits_outside_mask() {
+        let issues = check_opcodes(&BAD_BITS);
+        assert!(issues.contains(&TableIssue::BitsOutsideMask(0)));
+    }
+
+    #[test]
+    fn check_shadowed() {
+        assert_eq!(check_opcodes(&SHADOWED), vec![
+            TableIssue::Ambiguous(0, 1, 0x4E71),
+            TableIssue::Unreachable(1),
+        ]);
+    }
+
+    #[test]
+    fn check_disjoint_isa() {
+        assert_eq!(check_opcodes(&DISJOINT_ISA), vec![]);
+    }
+
+    #[test]
+    fn check_overlap() {
+        assert_eq!(check_opcodes(&OVERLAP), vec![
+            TableIssue::Ambiguous(0, 1, 0x4E71),
+            TableIssue::Unreachable(1),
+        ]);
+    }
+
+    #[test]
+    fn check_table() {
+        let issues = check_opcodes(OPCODES);
+
+        let details: String = issues.iter().map(|i| match *i {
+            TableIssue::BitsOutsideMask(a) | TableIssue::Unreachable(a)
+                => format!("{:?}: {:?}\n", i, OPCODES[a]),
+            TableIssue::Ambiguous(a, b, _)
+                => format!("{:?}:\n  {:?}\n  {:?}\n", i, OPCODES[a], OPCODES[b]),
+        }).collect();
+
+        assert!(issues.is_empty(), "{}", details);
+    }
+}
@@ -0,0 +1,285 @@
+// ColdFire Condition Codes
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+/// ColdFire integer condition codes, as tested by `bcc` and `scc`.
+///
+/// Variants are in encoding order.  They are not re-exported, as `Eq` would
+/// shadow the trait of the same name.
+///
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Condition {
+    /// True
+    T,
+    /// False
+    F,
+    /// High (unsigned >)
+    Hi,
+    /// Low or same (unsigned <=)
+    Ls,
+    /// Carry clear; high or same (unsigned >=)
+    Cc,
+    /// Carry set; low (unsigned <)
+    Cs,
+    /// Not equal
+    Ne,
+    /// Equal
+    Eq,
+    /// Overflow clear
+    Vc,
+    /// Overflow set
+    Vs,
+    /// Plus
+    Pl,
+    /// Minus
+    Mi,
+    /// Greater or equal (signed >=)
+    Ge,
+    /// Less than (signed <)
+    Lt,
+    /// Greater than (signed >)
+    Gt,
+    /// Less or equal (signed <=)
+    Le,
+}
+
+static CONDITIONS: [Condition; 16] = [
+    Condition::T,  Condition::F,  Condition::Hi, Condition::Ls,
+    Condition::Cc, Condition::Cs, Condition::Ne, Condition::Eq,
+    Condition::Vc, Condition::Vs, Condition::Pl, Condition::Mi,
+    Condition::Ge, Condition::Lt, Condition::Gt, Condition::Le,
+];
+
+static CONDITION_NAMES: [(&str, &str); 16] = [
+    ("t",  "t" ), ("f",  "f" ), ("hi", "hi"), ("ls", "ls"),
+    ("cc", "hs"), ("cs", "lo"), ("ne", "ne"), ("eq", "eq"),
+    ("vc", "vc"), ("vs", "vs"), ("pl", "pl"), ("mi", "mi"),
+    ("ge", "ge"), ("lt", "lt"), ("gt", "gt"), ("le", "le"),
+];
+
+impl Condition {
+    /// Returns the condition with the given 4-bit encoding.
+    #[inline]
+    pub fn decode(bits: u8) -> Self {
+        CONDITIONS[bits as usize & 0xF]
+    }
+
+    /// Returns the 4-bit encoding of the condition.
+    #[inline]
+    pub fn encode(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the condition with the given name or alias, if any.
+    pub fn with_name(name: &str) -> Option<Self> {
+        CONDITION_NAMES.iter()
+            .position(|&(n, a)| n.eq_ignore_ascii_case(name)
+                             || a.eq_ignore_ascii_case(name))
+            .map(|i| CONDITIONS[i])
+    }
+
+    /// Returns the name of the condition.
+    #[inline]
+    pub fn name(self) -> &'static str {
+        CONDITION_NAMES[self as usize].0
+    }
+
+    /// Returns the alternate name of the condition: `hs` for `cc` and `lo`
+    /// for `cs`.  Other conditions have no alternate name and return their
+    /// usual name.
+    #[inline]
+    pub fn alias(self) -> &'static str {
+        CONDITION_NAMES[self as usize].1
+    }
+
+    /// Returns the name or alias of the condition, as preferred.
+    #[inline]
+    pub fn name_pref(self, alias: bool) -> &'static str {
+        if alias { self.alias() } else { self.name() }
+    }
+}
+
+/// ColdFire floating-point condition predicates, as tested by `fbcc`.
+///
+/// Variants are in encoding order.  The predicates from `Sf` on are the
+/// signaling versions of those before them: they also set the BSUN status
+/// bit when an operand is a NaN.
+///
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum FpCondition {
+    /// False
+    F,
+    /// Equal
+    Eq,
+    /// Ordered greater than
+    Ogt,
+    /// Ordered greater or equal
+    Oge,
+    /// Ordered less than
+    Olt,
+    /// Ordered less or equal
+    Ole,
+    /// Ordered greater or less than
+    Ogl,
+    /// Ordered
+    Or,
+    /// Unordered
+    Un,
+    /// Unordered or equal
+    Ueq,
+    /// Unordered or greater than
+    Ugt,
+    /// Unordered or greater or equal
+    Uge,
+    /// Unordered or less than
+    Ult,
+    /// Unordered or less or equal
+    Ule,
+    /// Not equal
+    Ne,
+    /// True
+    T,
+    /// Signaling false
+    Sf,
+    /// Signaling equal
+    Seq,
+    /// Greater than
+    Gt,
+    /// Greater or equal
+    Ge,
+    /// Less than
+    Lt,
+    /// Less or equal
+    Le,
+    /// Greater or less than
+    Gl,
+    /// Greater, less, or equal
+    Gle,
+    /// Not greater, less, or equal
+    Ngle,
+    /// Not greater or less than
+    Ngl,
+    /// Not less or equal
+    Nle,
+    /// Not less than
+    Nlt,
+    /// Not greater or equal
+    Nge,
+    /// Not greater than
+    Ngt,
+    /// Signaling not equal
+    Sne,
+    /// Signaling true
+    St,
+}
+
+static FP_CONDITIONS: [FpCondition; 32] = [
+    FpCondition::F,    FpCondition::Eq,   FpCondition::Ogt,  FpCondition::Oge,
+    FpCondition::Olt,  FpCondition::Ole,  FpCondition::Ogl,  FpCondition::Or,
+    FpCondition::Un,   FpCondition::Ueq,  FpCondition::Ugt,  FpCondition::Uge,
+    FpCondition::Ult,  FpCondition::Ule,  FpCondition::Ne,   FpCondition::T,
+    FpCondition::Sf,   FpCondition::Seq,  FpCondition::Gt,   FpCondition::Ge,
+    FpCondition::Lt,   FpCondition::Le,   FpCondition::Gl,   FpCondition::Gle,
+    FpCondition::Ngle, FpCondition::Ngl,  FpCondition::Nle,  FpCondition::Nlt,
+    FpCondition::Nge,  FpCondition::Ngt,  FpCondition::Sne,  FpCondition::St,
+];
+
+static FP_CONDITION_NAMES: [&str; 32] = [
+    "f",    "eq",   "ogt",  "oge",  "olt",  "ole",  "ogl",  "or",
+    "un",   "ueq",  "ugt",  "uge",  "ult",  "ule",  "ne",   "t",
+    "sf",   "seq",  "gt",   "ge",   "lt",   "le",   "gl",   "gle",
+    "ngle", "ngl",  "nle",  "nlt",  "nge",  "ngt",  "sne",  "st",
+];
+
+impl FpCondition {
+    /// Returns the condition with the given 5-bit encoding.
+    #[inline]
+    pub fn decode(bits: u8) -> Self {
+        FP_CONDITIONS[bits as usize & 0x1F]
+    }
+
+    /// Returns the 5-bit encoding of the condition.
+    #[inline]
+    pub fn encode(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the condition with the given name, if any.
+    pub fn with_name(name: &str) -> Option<Self> {
+        FP_CONDITION_NAMES.iter()
+            .position(|n| n.eq_ignore_ascii_case(name))
+            .map(|i| FP_CONDITIONS[i])
+    }
+
+    /// Returns the name of the condition.
+    #[inline]
+    pub fn name(self) -> &'static str {
+        FP_CONDITION_NAMES[self as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        assert_eq!( Condition::decode(0x0), Condition::T  );
+        assert_eq!( Condition::decode(0x7), Condition::Eq );
+        assert_eq!( Condition::decode(0xF), Condition::Le );
+    }
+
+    #[test]
+    fn encode() {
+        for bits in 0..16 {
+            assert_eq!( Condition::decode(bits).encode(), bits );
+        }
+    }
+
+    #[test]
+    fn with_name() {
+        assert_eq!( Condition::with_name("ne"), Some(Condition::Ne) );
+        assert_eq!( Condition::with_name("HS"), Some(Condition::Cc) );
+        assert_eq!( Condition::with_name("lo"), Some(Condition::Cs) );
+        assert_eq!( Condition::with_name("ra"), None                );
+    }
+
+    #[test]
+    fn name() {
+        assert_eq!( Condition::Cc.name(),           "cc" );
+        assert_eq!( Condition::Cc.alias(),          "hs" );
+        assert_eq!( Condition::Cs.name_pref(true),  "lo" );
+        assert_eq!( Condition::Cs.name_pref(false), "cs" );
+        assert_eq!( Condition::Gt.name_pref(true),  "gt" );
+    }
+
+    #[test]
+    fn fp_encode() {
+        for bits in 0..32 {
+            assert_eq!( FpCondition::decode(bits).encode(), bits );
+        }
+        assert_eq!( FpCondition::decode(0x0E), FpCondition::Ne );
+    }
+
+    #[test]
+    fn fp_names() {
+        assert_eq!( FpCondition::with_name("OGT"), Some(FpCondition::Ogt) );
+        assert_eq!( FpCondition::with_name("ngle"), Some(FpCondition::Ngle) );
+        assert_eq!( FpCondition::with_name("hi"),  None                   );
+        assert_eq!( FpCondition::St.name(),        "st"                   );
+    }
+}
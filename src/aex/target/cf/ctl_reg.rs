@@ -0,0 +1,190 @@
+// ColdFire Control Registers
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use aex::ast::Reg;
+use aex::fmt::ToCode;
+
+pub use self::CtlReg::*;
+
+/// ColdFire control registers, accessible via `movec`.
+///
+/// Not all registers exist on all models.
+///
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum CtlReg {
+    /// Cache control register
+    Cacr,
+    /// Address space identifier
+    Asid,
+    /// Access control registers
+    Acr0, Acr1, Acr2, Acr3,
+    /// MMU base address register
+    Mmubar,
+    /// Other stack pointer
+    OtherA7,
+    /// Vector base register
+    Vbr,
+    /// MAC status register, as `MacReg::Macsr`
+    MacStatus,
+    /// MAC address mask register, as `MacReg::Mask`
+    MacMask,
+    /// MAC accumulator 0, as `MacReg::Acc0`
+    MacAcc0,
+    /// EMAC accumulator extension bytes, as `MacReg::AccExt01` and
+    /// `MacReg::AccExt23`
+    MacAccExt01, MacAccExt23,
+    /// EMAC accumulators 1-3, as `MacReg::Acc1` to `MacReg::Acc3`
+    MacAcc1, MacAcc2, MacAcc3,
+    /// Status register
+    StatusReg,
+    /// Program counter
+    Pc,
+    /// ROM base address registers
+    Rombar0, Rombar1,
+    /// RAM base address registers
+    Rambar0, Rambar1,
+    /// Multiprocessor control register
+    Mpcr,
+    /// Embedded DRAM base address register
+    Edrambar,
+    /// Secondary module base address register
+    Secmbar,
+    /// Module base address register
+    Mbar,
+}
+
+static CTL_REGS: [(CtlReg, u16, &str); 29] = [
+    (Cacr,        0x002, "cacr"    ),
+    (Asid,        0x003, "asid"    ),
+    (Acr0,        0x004, "acr0"    ),
+    (Acr1,        0x005, "acr1"    ),
+    (Acr2,        0x006, "acr2"    ),
+    (Acr3,        0x007, "acr3"    ),
+    (Mmubar,      0x008, "mmubar"  ),
+    (OtherA7,     0x800, "other_a7"),
+    (Vbr,         0x801, "vbr"     ),
+    (MacStatus,   0x804, "macsr"   ),
+    (MacMask,     0x805, "mask"    ),
+    (MacAcc0,     0x806, "acc0"    ),
+    (MacAccExt01, 0x807, "accext01"),
+    (MacAccExt23, 0x808, "accext23"),
+    (MacAcc1,     0x809, "acc1"    ),
+    (MacAcc2,     0x80A, "acc2"    ),
+    (MacAcc3,     0x80B, "acc3"    ),
+    (StatusReg,   0x80E, "sr"      ),
+    (Pc,          0x80F, "pc"      ),
+    (Rombar0,     0xC00, "rombar0" ),
+    (Rombar1,     0xC01, "rombar1" ),
+    (Rambar0,     0xC04, "rambar0" ),
+    (Rambar1,     0xC05, "rambar1" ),
+    (Mpcr,        0xC0C, "mpcr"    ),
+    (Edrambar,    0xC0D, "edrambar"),
+    (Secmbar,     0xC0E, "secmbar" ),
+    (Mbar,        0xC0F, "mbar"    ),
+    // Aliases, accepted when parsing only
+    (Rambar1,     0xC05, "rambar"  ),
+    (Rombar0,     0xC00, "rombar"  ),
+];
+
+impl CtlReg {
+    /// Returns the control register with the given 12-bit Rc encoding, if
+    /// any.
+    pub fn decode(rc: u16) -> Option<Self> {
+        CTL_REGS.iter()
+            .find(|&&(_, bits, _)| bits == rc)
+            .map(|&(reg, _, _)| reg)
+    }
+
+    /// Returns the 12-bit Rc encoding of the control register.
+    #[inline]
+    pub fn encode(self) -> u16 {
+        CTL_REGS[self as usize].1
+    }
+
+    /// Returns the control register with the given name, if any.
+    pub fn with_name(name: &str) -> Option<Self> {
+        CTL_REGS.iter()
+            .find(|&&(_, _, n)| n.eq_ignore_ascii_case(name))
+            .map(|&(reg, _, _)| reg)
+    }
+
+    /// Returns the name of the control register.
+    #[inline]
+    pub fn name(self) -> &'static str {
+        CTL_REGS[self as usize].2
+    }
+}
+
+impl<A> ToCode<A> for CtlReg {
+    type Output = Reg<'static, A>;
+
+    /// Converts to a code-formattable value with the given annotation.
+    #[inline]
+    fn to_code(&self, ann: A) -> Self::Output {
+        Reg::new_with_ann(self.name(), ann)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_order() {
+        for (i, &(reg, _, _)) in CTL_REGS[..27].iter().enumerate() {
+            assert_eq!(reg as usize, i);
+        }
+    }
+
+    #[test]
+    fn decode() {
+        assert_eq!( CtlReg::decode(0x801), Some(Vbr)       );
+        assert_eq!( CtlReg::decode(0xC0F), Some(Mbar)      );
+        assert_eq!( CtlReg::decode(0x80E), Some(StatusReg) );
+        assert_eq!( CtlReg::decode(0x80B), Some(MacAcc3)   );
+        assert_eq!( CtlReg::decode(0x001), None            );
+    }
+
+    #[test]
+    fn encode() {
+        assert_eq!( Cacr.encode(),    0x002 );
+        assert_eq!( Rambar1.encode(), 0xC05 );
+    }
+
+    #[test]
+    fn with_name() {
+        assert_eq!( CtlReg::with_name("VBR"),    Some(Vbr)       );
+        assert_eq!( CtlReg::with_name("rambar"), Some(Rambar1)   );
+        assert_eq!( CtlReg::with_name("MACSR"),  Some(MacStatus) );
+        assert_eq!( CtlReg::with_name("d0"),     None            );
+    }
+
+    #[test]
+    fn name() {
+        assert_eq!( Acr2.name(), "acr2" );
+    }
+
+    #[test]
+    fn to_code() {
+        let c = Vbr.to_code(42);
+
+        assert_eq!(c.name, "vbr");
+        assert_eq!(c.ann,   42 );
+    }
+}
@@ -0,0 +1,145 @@
+// ColdFire Debug Module Registers
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use aex::ast::Reg;
+use aex::fmt::ToCode;
+
+pub use self::DbgReg::*;
+
+/// ColdFire debug module registers, as written by debug commands via
+/// `wdebug`.
+///
+/// Not all registers exist on all models.
+///
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum DbgReg {
+    /// Configuration/status register
+    Csr,
+    /// BDM address attribute register
+    Baar,
+    /// Address attribute trigger register
+    Aatr,
+    /// Trigger definition register
+    Tdr,
+    /// PC breakpoint register
+    Pbr,
+    /// PC breakpoint mask register
+    Pbmr,
+    /// Address breakpoint high register
+    Abhr,
+    /// Address breakpoint low register
+    Ablr,
+    /// Data breakpoint register
+    Dbr,
+    /// Data breakpoint mask register
+    Dbmr,
+    /// Additional PC breakpoint registers
+    Pbr1, Pbr2, Pbr3,
+}
+
+static DBG_REGS: [(DbgReg, u8, &str); 13] = [
+    (Csr,  0x00, "csr" ),
+    (Baar, 0x05, "baar"),
+    (Aatr, 0x06, "aatr"),
+    (Tdr,  0x07, "tdr" ),
+    (Pbr,  0x08, "pbr" ),
+    (Pbmr, 0x09, "pbmr"),
+    (Abhr, 0x0C, "abhr"),
+    (Ablr, 0x0D, "ablr"),
+    (Dbr,  0x0E, "dbr" ),
+    (Dbmr, 0x0F, "dbmr"),
+    (Pbr1, 0x18, "pbr1"),
+    (Pbr2, 0x1A, "pbr2"),
+    (Pbr3, 0x1B, "pbr3"),
+];
+
+impl DbgReg {
+    /// Returns the debug register with the given 5-bit DRc encoding, if any.
+    pub fn decode(drc: u8) -> Option<Self> {
+        DBG_REGS.iter()
+            .find(|&&(_, bits, _)| bits == drc)
+            .map(|&(reg, _, _)| reg)
+    }
+
+    /// Returns the 5-bit DRc encoding of the debug register.
+    #[inline]
+    pub fn encode(self) -> u8 {
+        DBG_REGS[self as usize].1
+    }
+
+    /// Returns the debug register with the given name, if any.
+    pub fn with_name(name: &str) -> Option<Self> {
+        DBG_REGS.iter()
+            .find(|&&(_, _, n)| n.eq_ignore_ascii_case(name))
+            .map(|&(reg, _, _)| reg)
+    }
+
+    /// Returns the name of the debug register.
+    #[inline]
+    pub fn name(self) -> &'static str {
+        DBG_REGS[self as usize].2
+    }
+}
+
+impl<A> ToCode<A> for DbgReg {
+    type Output = Reg<'static, A>;
+
+    /// Converts to a code-formattable value with the given annotation.
+    #[inline]
+    fn to_code(&self, ann: A) -> Self::Output {
+        Reg::new_with_ann(self.name(), ann)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_order() {
+        for (i, &(reg, _, _)) in DBG_REGS.iter().enumerate() {
+            assert_eq!(reg as usize, i);
+        }
+    }
+
+    #[test]
+    fn decode() {
+        assert_eq!( DbgReg::decode(0x0E), Some(Dbr) );
+        assert_eq!( DbgReg::decode(0x01), None      );
+    }
+
+    #[test]
+    fn encode() {
+        assert_eq!( Pbr3.encode(), 0x1B );
+    }
+
+    #[test]
+    fn with_name() {
+        assert_eq!( DbgReg::with_name("TDR"), Some(Tdr) );
+        assert_eq!( DbgReg::with_name("vbr"), None      );
+    }
+
+    #[test]
+    fn to_code() {
+        let c = Csr.to_code(42);
+
+        assert_eq!(c.name, "csr");
+        assert_eq!(c.ann,   42 );
+    }
+}
@@ -0,0 +1,361 @@
+// ColdFire Decoding
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::Result;
+use aex::io::DecodeRead;
+use aex::util::invalid;
+use super::{Instruction, Opcode, Flags, EXT_WORD};
+
+/// Decodes an instruction using the given opcodes table.
+///
+/// Only opcodes supported by the given ISA profile are considered.  The
+/// profile is a combination of `CF_*` flags, such as `CF_B | CF_MAC`.
+/// Each opcode whose discriminant bits match is tried in table order.  The
+/// first opcode whose operands decode successfully is used, and the bytes of
+/// the instruction are consumed.  If no opcode matches, returns `None`, and
+/// the reader is rewound to the start of the instruction.
+///
+pub fn decode<'a, R: DecodeRead>(opcodes: &'static [Opcode],
+                                 isa:     Flags,
+                                 r:       &mut R)
+                                -> Result<Option<Instruction<'a>>> {
+    let word = r.read_u16()?;
+    r.rewind();
+
+    let candidates = opcodes.iter().filter(|o| o.is_supported(isa));
+    decode_from(candidates, word, r)
+}
+
+/// Number of upper opcode word bits by which `Dispatch` groups opcodes.
+const DISPATCH_BITS: u16 = 10;
+
+/// Opcode dispatch table.
+///
+/// Groups the opcodes of a table by the upper bits of the opcode word, so
+/// that decoding tries only the opcodes that could match.  Opcodes not
+/// supported by the ISA profile are left out.  Table order is preserved
+/// within each group.  Building a dispatch table is costly; build one per
+/// profile and reuse it.
+///
+#[derive(Clone, Debug)]
+pub struct Dispatch {
+    groups: Vec<Vec<&'static Opcode>>,
+}
+
+impl Dispatch {
+    /// Builds a dispatch table for the opcodes of the given table that are
+    /// supported by the given ISA profile.
+    pub fn new(opcodes: &'static [Opcode], isa: Flags) -> Self {
+        let shift = 16 - DISPATCH_BITS;
+        let upper = !0u16 << shift;
+
+        let groups = (0..1 << DISPATCH_BITS)
+            .map(|i: u16| {
+                let word = i << shift;
+                opcodes.iter()
+                    .filter(|o| o.is_supported(isa))
+                    .filter(|o| word & o.mask.0 & upper == o.bits.0 & upper)
+                    .collect()
+            })
+            .collect();
+
+        Dispatch { groups }
+    }
+
+    /// Returns the opcodes that could match the given opcode word, in table
+    /// order.
+    #[inline]
+    pub fn candidates(&self, word: u16) -> &[&'static Opcode] {
+        &self.groups[(word >> (16 - DISPATCH_BITS)) as usize]
+    }
+
+    /// Decodes an instruction.  Behaves like `decode`, but tries only the
+    /// candidate opcodes for the instruction's opcode word.
+    pub fn decode<'a, R: DecodeRead>(&self, r: &mut R)
+                                    -> Result<Option<Instruction<'a>>> {
+        let word = r.read_u16()?;
+        r.rewind();
+
+        let candidates = self.candidates(word).iter().cloned();
+        decode_from(candidates, word, r)
+    }
+}
+
+fn decode_from<'a, I, R>(candidates: I, word: u16, r: &mut R)
+                        -> Result<Option<Instruction<'a>>>
+where I: Iterator<Item=&'static Opcode>, R: DecodeRead {
+    for o in candidates {
+        // Word must match discriminant bits of opcode
+        if word & o.mask.0 != o.bits.0 { continue; }
+
+        // Remaining words must match valid operand set
+        match decode_as(o, r) {
+            Ok(i) => {
+                r.consume();
+                return Ok(Some(i));
+            },
+            Err(_) => {
+                r.rewind();
+            },
+        }
+    }
+
+    Ok(None)
+}
+
+fn decode_as<'a, R: DecodeRead>(o: &'static Opcode, r: &mut R)
+                               -> Result<Instruction<'a>> {
+    let addr = r.vma();
+    let word = r.read_u16()?;
+
+    let ext = if o.flags & EXT_WORD != 0 {
+        let ext = r.read_u16()?;
+        if ext & o.mask.1 != o.bits.1 {
+            return invalid("extension word does not match opcode");
+        }
+        ext
+    } else {
+        0
+    };
+
+    let operands = o.args.decode((word, ext), o.size, r)?;
+
+    Ok(Instruction {
+        name:     o.name,
+        size:     o.size,
+        opcode:   o,
+        operands,
+        addr,
+        bytes:    r.pending_bytes().to_vec(),
+    })
+}
+
+// For    assembly : name -> [opcode] -- use  first opcode that matches
+// For disassembly : [(opcode, name)] -- find first opcode that matches, then get name
+//
+//   "movea.l" -> [a]       -- a    is  the opcode  for movea.l
+//   "move.l"  -> [b, c]    -- b, c are the opcodes for move.l
+//
+// for 0x2...:
+//   [ (a, "movea.l")
+//   , (b, "move.l" )
+//   , (c, "move.l" )
+//   ]
+
+// Trie in structure, or ad-hoc trie in code (bunch o'matches)?
+//   - code: does not require mask in opcodes table any more
+//   - code: uses I cache instead of D cache
+//   - data: automatic generation
+//   - data: easier evolution
+//
+// First level disasm trie:
+//
+// 0 -> ori|btst|bchg|bset|andi|subi|addi|eori|cmpi
+// 1 -> move.b
+// 2 -> move.l|movea.l
+// 3 -> move.w|movea.w
+// 4 -> (everything else!)
+// 5 -> addq|scc|subq|tpf
+// 6 -> bra|bsr|bcc
+// 7 -> moveq
+// 8 -> or|divu.w|divs.w
+// 9 -> sub|subx|suba
+// A -> (mac stuff)
+// B -> cmp|cmpa|eor
+// C -> and|mulu.w|muls.wA
+// D -> add|addx|adda
+// E -> asl|asr|lksl|lsr
+// F -> cpushl|wddata|wdebug|(fp stuff)
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use aex::ast::{Expr, Int};
+    use aex::io::{DecodeRead, DecodeReader, RewindReader};
+    use aex::util::BE;
+    use super::*;
+    use super::super::*;
+
+    #[test]
+    fn decode_nullary() {
+        let mut r = reader(&[0x4E, 0x71]);
+        let i = decode(OPCODES, CF_A, &mut r).unwrap().unwrap();
+        assert_eq!(i.name,     Mnemonic::Nop);
+        assert_eq!(i.size,     Size::Zero);
+        assert_eq!(i.operands, vec![]);
+        assert_eq!(i.addr,     0x1000);
+        assert_eq!(i.bytes,    vec![0x4E, 0x71]);
+        assert_eq!(r.lma(),    2);
+    }
+
+    #[test]
+    fn decode_binary() {
+        // move.l (a0)+, -(a1)
+        let mut r = reader(&[0x23, 0x18]);
+        let i = decode(OPCODES, CF_A, &mut r).unwrap().unwrap();
+        assert_eq!(i.name, Mnemonic::Move);
+        assert_eq!(i.size, Size::Long);
+        assert_eq!(i.operands, vec![
+            Operand::Mode(Mode::AddrPostInc(A0)),
+            Operand::Mode(Mode::AddrPreDec(A1)),
+        ]);
+    }
+
+    #[test]
+    fn decode_ext_words() {
+        // addi.l #0x12345678, d3
+        let mut r = reader(&[0x06, 0x83, 0x12, 0x34, 0x56, 0x78, 0x4E, 0x71]);
+        let i = decode(OPCODES, CF_A, &mut r).unwrap().unwrap();
+        assert_eq!(i.name, Mnemonic::Addi);
+        assert_eq!(i.operands, vec![
+            Operand::Mode(Mode::Imm(Expr::Int(Int::new(0x12345678)))),
+            Operand::Mode(Mode::Data(D3)),
+        ]);
+        assert_eq!(i.bytes.len(), 6);
+        assert_eq!(r.lma(), 6);
+    }
+
+    #[test]
+    fn decode_opcode_ext_word() {
+        // mulu.l d0, d1
+        let mut r = reader(&[0x4C, 0x00, 0x10, 0x00]);
+        let i = decode(OPCODES, CF_A, &mut r).unwrap().unwrap();
+        assert_eq!(i.name, Mnemonic::Mulu);
+        assert_eq!(i.operands, vec![
+            Operand::Mode(Mode::Data(D0)),
+            Operand::Mode(Mode::Data(D1)),
+        ]);
+    }
+
+    #[test]
+    fn decode_after_failed_candidate() {
+        // addx.l d0, d1 -- also matches add.l d1, <ea> with invalid <ea>
+        let mut r = reader(&[0xD3, 0x80]);
+        let i = decode(OPCODES, CF_A, &mut r).unwrap().unwrap();
+        assert_eq!(i.name, Mnemonic::Addx);
+        assert_eq!(i.operands, vec![
+            Operand::Mode(Mode::Data(D0)),
+            Operand::Mode(Mode::Data(D1)),
+        ]);
+        assert_eq!(i.bytes, vec![0xD3, 0x80]);
+    }
+
+    #[test]
+    fn decode_quick() {
+        // addq.l #8, (a2)
+        let mut r = reader(&[0x50, 0x92]);
+        let i = decode(OPCODES, CF_A, &mut r).unwrap().unwrap();
+        assert_eq!(i.name, Mnemonic::Addq);
+        assert_eq!(i.operands, vec![
+            Operand::Mode(Mode::Imm(Expr::Int(Int::new(8)))),
+            Operand::Mode(Mode::AddrInd(A2)),
+        ]);
+    }
+
+    #[test]
+    fn decode_none() {
+        let mut r = reader(&[0xFF, 0xFF, 0x4E, 0x71]);
+        assert_eq!(decode(OPCODES, CF_A, &mut r).unwrap(), None);
+        assert_eq!(r.lma(), 0);
+        assert_eq!(r.pending_len(), 0);
+    }
+
+    #[test]
+    fn decode_truncated() {
+        // addi.l with missing immediate
+        let mut r = reader(&[0x06, 0x83, 0x12, 0x34]);
+        assert_eq!(decode(OPCODES, CF_A, &mut r).unwrap(), None);
+        assert_eq!(r.pending_len(), 0);
+    }
+
+    #[test]
+    fn decode_eof() {
+        let mut r = reader(&[0x4E]);
+        assert!(decode(OPCODES, CF_A, &mut r).is_err());
+    }
+
+    #[test]
+    fn dispatch_candidates() {
+        let d = Dispatch::new(OPCODES, CF_A);
+        let c = d.candidates(0x4E71);
+        assert!(c.iter().any(|o| o.name == Mnemonic::Nop));
+        assert!(c.iter().all(|o| 0x4E40 & o.mask.0 & 0xFFC0 == o.bits.0 & 0xFFC0));
+        assert!(c.len() < 10);
+    }
+
+    #[test]
+    fn dispatch_excludes_unsupported() {
+        let d = Dispatch::new(OPCODES, CF_A);
+        assert!(d.candidates(0xF200).is_empty());
+    }
+
+    #[test]
+    fn dispatch_matches_linear() {
+        for &isa in &[CF_A | CF_MAC, CF_C | CF_FPU | CF_EMAC] {
+            let d = Dispatch::new(OPCODES, isa);
+            for word in 0..0x10000u32 {
+                let bytes = [(word >> 8) as u8, word as u8, 0x00, 0x10, 0x00, 0x20];
+                let a = decode(OPCODES, isa, &mut reader(&bytes)).unwrap();
+                let b = d.decode(&mut reader(&bytes)).unwrap();
+                assert_eq!(a, b, "{:04X}", word);
+            }
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn dispatch_benchmark() {
+        use std::time::Instant;
+
+        // Every opcode word, each followed by some extension words
+        let mut bytes = Vec::with_capacity(0x10000 * 6);
+        for word in 0..0x10000u32 {
+            bytes.extend_from_slice(&[(word >> 8) as u8, word as u8, 0x00, 0x10, 0x00, 0x20]);
+        }
+
+        let isa = CF_C | CF_FPU | CF_EMAC;
+        let d   = Dispatch::new(OPCODES, isa);
+
+        let linear = time(|r| decode(OPCODES, isa, r), &bytes);
+        let dispatch = time(|r| d.decode(r), &bytes);
+
+        println!("linear:   {:?}", linear);
+        println!("dispatch: {:?}", dispatch);
+        println!("speedup:  {:.1}x", linear.as_secs_f64() / dispatch.as_secs_f64());
+
+        fn time<F>(mut f: F, bytes: &[u8]) -> ::std::time::Duration
+        where F: FnMut(&mut DecodeReader<RewindReader<Cursor<Vec<u8>>>>)
+                 -> Result<Option<Instruction<'static>>> {
+            let mut r     = reader(bytes);
+            let     start = Instant::now();
+            while r.lma() < bytes.len() as u64 {
+                if f(&mut r).unwrap().is_none() {
+                    r.read_u16().unwrap();
+                    r.consume();
+                }
+            }
+            start.elapsed()
+        }
+    }
+
+    fn reader(bytes: &[u8]) -> DecodeReader<RewindReader<Cursor<Vec<u8>>>> {
+        let src = Cursor::new(bytes.to_vec());
+        let src = RewindReader::new(src);
+        DecodeReader::new(src, BE, 0x1000)
+    }
+}
@@ -0,0 +1,302 @@
+// ColdFire Disassembler
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt::{self, Formatter};
+use std::io::{self, ErrorKind, Write};
+
+use aex::ast::{Id, Node};
+use aex::fmt::{Code, Style, ToStyled};
+use aex::io::DecodeRead;
+
+use super::{Dispatch, Flags, Instruction, Labels, Opcode};
+
+/// Width of the raw bytes column of a listing, in characters.
+const BYTES_WIDTH: usize = 14;
+
+/// Linear-sweep disassembler.
+///
+/// Decodes instructions one after another from the current position of a
+/// reader.  Words that do not begin a valid instruction are emitted as data,
+/// and decoding resumes at the next word.
+///
+#[derive(Clone, Debug)]
+pub struct Disassembler {
+    dispatch: Dispatch,
+}
+
+/// A line of disassembly.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Line<'a> {
+    /// Address (VMA) of the line.
+    pub addr: u64,
+
+    /// Bytes disassembled for the line.
+    pub bytes: Vec<u8>,
+
+    /// What the bytes disassembled as.
+    pub item: Item<'a>,
+}
+
+/// A disassembled item.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Item<'a> {
+    /// A decoded instruction.
+    Instruction(Instruction<'a>),
+
+    /// A word that does not begin a valid instruction.
+    Short(u16),
+
+    /// A trailing byte too short to be an instruction.
+    Byte(u8),
+}
+
+impl Disassembler {
+    /// Creates a disassembler for the opcodes of the given table that are
+    /// supported by the given ISA profile.
+    pub fn new(opcodes: &'static [Opcode], isa: Flags) -> Self {
+        Disassembler { dispatch: Dispatch::new(opcodes, isa) }
+    }
+
+    /// Disassembles one line from the given reader, consuming its bytes.
+    /// Returns `None` at the end of input.
+    pub fn decode_line<'a, R: DecodeRead>(&self, r: &mut R)
+                                         -> io::Result<Option<Line<'a>>> {
+        let addr = r.vma();
+
+        let item = match self.dispatch.decode(r) {
+            Ok(Some(i)) => {
+                let bytes = i.bytes.clone();
+                return Ok(Some(Line { addr, bytes, item: Item::Instruction(i) }));
+            },
+            Ok(None) => {
+                Item::Short(r.read_u16()?)
+            },
+            Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => {
+                r.rewind();
+                match r.read_u8() {
+                    Ok(b) => Item::Byte(b),
+                    Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => {
+                        r.rewind();
+                        return Ok(None);
+                    },
+                    Err(e) => return Err(e),
+                }
+            },
+            Err(e) => return Err(e),
+        };
+
+        let bytes = r.pending_bytes().to_vec();
+        r.consume();
+        Ok(Some(Line { addr, bytes, item }))
+    }
+
+    /// Disassembles the given reader to its end, returning the lines.
+    pub fn decode_lines<'a, R: DecodeRead>(&self, r: &mut R)
+                                          -> io::Result<Vec<Line<'a>>> {
+        let mut lines = Vec::new();
+        while let Some(line) = self.decode_line(r)? {
+            lines.push(line);
+        }
+        Ok(lines)
+    }
+
+    /// Disassembles the given reader to its end, writing a listing in the
+    /// given code style to the given output.  See `write_lines`.
+    pub fn write_listing<R, S, W>(&self, r: &mut R, style: &S, out: &mut W)
+                                 -> io::Result<()>
+    where R: DecodeRead, S: Style<()> + ?Sized, W: Write {
+        write_lines(&self.decode_lines(r)?, style, out)
+    }
+}
+
+/// Writes a listing of the given lines in the given code style to the given
+/// output.
+///
+/// Addresses referenced by branches and PC-relative operands get synthesized
+/// labels, which are written on lines of their own and used in operands in
+/// place of numbers.
+///
+pub fn write_lines<S, W>(lines: &[Line], style: &S, out: &mut W) -> io::Result<()>
+where S: Style<()> + ?Sized, W: Write {
+    let labels = Labels::collect(lines);
+
+    for line in lines {
+        if let Some(name) = labels.get(line.addr) {
+            writeln!(out, "{}:", Id::new(name).styled(style))?;
+        }
+
+        let mut line = line.clone();
+        if let Item::Instruction(ref mut i) = line.item {
+            labels.apply(i);
+        }
+
+        writeln!(out, "{}", line.styled(style))?;
+    }
+
+    Ok(())
+}
+
+impl<'a> Node for Item<'a> {
+    /// Annotation type.
+    type Ann = ();
+
+    /// Gets the annotation for this node.
+    fn ann(&self) -> &() { &() }
+}
+
+impl<'a> Code for Item<'a> {
+    /// Formats the value as code, using the given formatter and style.
+    fn fmt<S: Style<()> + ?Sized>
+          (&self, f: &mut Formatter, s: &S) -> fmt::Result {
+        match *self {
+            Item::Instruction (ref i) => i.fmt(f, s),
+            Item::Short       (w)     => write!(f, ".short 0x{:04x}", w),
+            Item::Byte        (b)     => write!(f, ".byte 0x{:02x}", b),
+        }
+    }
+}
+
+impl<'a> Node for Line<'a> {
+    /// Annotation type.
+    type Ann = ();
+
+    /// Gets the annotation for this node.
+    fn ann(&self) -> &() { &() }
+}
+
+impl<'a> Code for Line<'a> {
+    /// Formats the value as a listing line: address, raw bytes, and code.
+    fn fmt<S: Style<()> + ?Sized>
+          (&self, f: &mut Formatter, s: &S) -> fmt::Result {
+        let mut hex = String::with_capacity(BYTES_WIDTH);
+        for (i, b) in self.bytes.iter().enumerate() {
+            if i != 0 && i % 2 == 0 { hex.push(' '); }
+            hex.push_str(&format!("{:02x}", b));
+        }
+
+        write!(
+            f, "{addr:08x}:  {hex:<width$}  {item}",
+            addr  = self.addr,
+            hex   = hex,
+            width = BYTES_WIDTH,
+            item  = self.item.styled(s),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use aex::fmt::{AttStyle, MitStyle};
+    use aex::io::{DecodeReader, RewindReader};
+    use aex::util::BE;
+    use super::*;
+    use super::super::*;
+
+    static CODE: [u8; 22] = [
+        0x4E, 0x56, 0xFF, 0xF8,         // link.w   %fp,#-8
+        0x20, 0x2E, 0x00, 0x08,         // move.l   8(%fp),%d0
+        0x67, 0x04,                     // beq.s    +4
+        0xFF, 0xFF,                     // (invalid)
+        0x22, 0xD8,                     // move.l   (%a0)+,(%a1)+
+        0x41, 0xFB, 0x08, 0xFC,         // lea.l    -4(%pc,%d0*1),%a0
+        0x4E, 0x5E,                     // unlk     %fp
+        0x4E, 0x75,                     // rts
+    ];
+
+    #[test]
+    fn decode_line() {
+        let d     = Disassembler::new(OPCODES, CF_A);
+        let mut r = reader(&CODE[..4], 0x1000);
+
+        let line = d.decode_line(&mut r).unwrap().unwrap();
+        assert_eq!(line.addr,  0x1000);
+        assert_eq!(line.bytes, vec![0x4E, 0x56, 0xFF, 0xF8]);
+        match line.item {
+            Item::Instruction(ref i) => assert_eq!(i.name, Mnemonic::Link),
+            ref item                 => panic!("unexpected item: {:?}", item),
+        }
+
+        assert_eq!(d.decode_line(&mut r).unwrap(), None);
+    }
+
+    #[test]
+    fn decode_line_invalid() {
+        let d     = Disassembler::new(OPCODES, CF_A);
+        let mut r = reader(&[0xFF, 0xFF, 0x4E, 0x71, 0x12], 0);
+
+        let line = d.decode_line(&mut r).unwrap().unwrap();
+        assert_eq!(line.item,  Item::Short(0xFFFF));
+        assert_eq!(line.bytes, vec![0xFF, 0xFF]);
+
+        let line = d.decode_line(&mut r).unwrap().unwrap();
+        assert_eq!(line.addr, 2);
+
+        let line = d.decode_line(&mut r).unwrap().unwrap();
+        assert_eq!(line.addr, 4);
+        assert_eq!(line.item, Item::Byte(0x12));
+
+        assert_eq!(d.decode_line(&mut r).unwrap(), None);
+    }
+
+    #[test]
+    fn write_listing_att() {
+        assert_eq!(listing(&AttStyle::default()), "\
+00001000:  4e56 fff8       link.w %fp,#-8
+00001004:  202e 0008       move.l 8(%fp),%d0
+00001008:  6704            beq.s L_0000100e
+0000100a:  ffff            .short 0xffff
+data_0000100c:
+0000100c:  22d8            move.l (%a0)+,(%a1)+
+L_0000100e:
+0000100e:  41fb 08fc       lea.l data_0000100c(%pc,%d0*1),%a0
+00001012:  4e5e            unlk %fp
+00001014:  4e75            rts
+");
+    }
+
+    #[test]
+    fn write_listing_mit() {
+        assert_eq!(listing(&MitStyle::default()), "\
+00001000:  4e56 fff8       linkw %fp,#-8
+00001004:  202e 0008       movel %fp@(8),%d0
+00001008:  6704            beqs L_0000100e
+0000100a:  ffff            .short 0xffff
+data_0000100c:
+0000100c:  22d8            movel %a0@+,%a1@+
+L_0000100e:
+0000100e:  41fb 08fc       leal %pc@(data_0000100c,%d0:1),%a0
+00001012:  4e5e            unlk %fp
+00001014:  4e75            rts
+");
+    }
+
+    fn listing<S: Style<()>>(style: &S) -> String {
+        let d     = Disassembler::new(OPCODES, CF_A);
+        let mut r = reader(&CODE, 0x1000);
+        let mut o = Vec::new();
+        d.write_listing(&mut r, style, &mut o).unwrap();
+        String::from_utf8(o).unwrap()
+    }
+
+    fn reader(bytes: &[u8], reloc: u64) -> DecodeReader<RewindReader<Cursor<Vec<u8>>>> {
+        let src = Cursor::new(bytes.to_vec());
+        let src = RewindReader::new(src);
+        DecodeReader::new(src, BE, reloc)
+    }
+}
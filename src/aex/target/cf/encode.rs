@@ -0,0 +1,419 @@
+// ColdFire Instruction Encoder
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use num::{BigInt, ToPrimitive};
+
+use aex::ast::Expr;
+use aex::io::{EncodeWrite, EncodeWriter};
+use aex::types::int::IntSpec;
+use aex::util::BE;
+
+use super::{Flags, Instruction, Mnemonic, Opcode, Operand, Size, OPCODES, CF_ALL, EXT_WORD};
+
+/// An error that prevents encoding an instruction.
+#[derive(Debug)]
+pub enum EncodeError {
+    /// No opcode has the given name and size.
+    NoOpcode(Mnemonic, Size),
+
+    /// No opcode of the name and size accepts the operands.
+    Mismatch,
+
+    /// A value does not fit in its field.
+    OutOfRange {
+        field: &'static str,
+        value: BigInt,
+        min:   BigInt,
+        max:   BigInt,
+    },
+
+    /// A value fits in its field, but its encoding is reserved.
+    Reserved {
+        field: &'static str,
+        value: BigInt,
+    },
+
+    /// An operand expression is not a constant.
+    NotConstant(&'static str),
+
+    /// The operand is not supported by the encoder.
+    Unsupported(&'static str),
+
+    /// An I/O error occurred while writing the encoding.
+    Io(io::Error),
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            EncodeError::NoOpcode(name, size) => {
+                write!(f, "no opcode for {}", name.as_str())?;
+                match size.suffix() {
+                    ""     => Ok(()),
+                    suffix => write!(f, ".{}", suffix),
+                }
+            },
+            EncodeError::Mismatch => {
+                f.write_str("operands do not match any form of the instruction")
+            },
+            EncodeError::OutOfRange { field, ref value, ref min, ref max } => {
+                write!(f, "{} {} out of range ({} to {})", field, value, min, max)
+            },
+            EncodeError::Reserved { field, ref value } => {
+                write!(f, "{} {} is reserved", field, value)
+            },
+            EncodeError::NotConstant(field) => {
+                write!(f, "{} is not a constant", field)
+            },
+            EncodeError::Unsupported(what) => {
+                write!(f, "{} not supported", what)
+            },
+            EncodeError::Io(ref e) => {
+                e.fmt(f)
+            },
+        }
+    }
+}
+
+impl Error for EncodeError { }
+
+impl From<io::Error> for EncodeError {
+    fn from(e: io::Error) -> Self {
+        EncodeError::Io(e)
+    }
+}
+
+/// Instruction encoder.
+///
+/// Encodes an instruction using the first opcode of the given name and size
+/// whose operand forms accept the given operands.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Encoder {
+    opcodes: &'static [Opcode],
+    isa:     Flags,
+}
+
+impl Encoder {
+    /// Creates an encoder for the opcodes of the given table that are
+    /// supported by the given ISA profile.
+    pub fn new(opcodes: &'static [Opcode], isa: Flags) -> Self {
+        Encoder { opcodes, isa }
+    }
+
+    /// Encodes an instruction with the given name, size, and operands.
+    ///
+    /// Operands are as decoded: branch operands are displacements, and all
+    /// values are constants.  If no opcode accepts the operands, the error
+    /// is the most specific one encountered.
+    ///
+    pub fn encode(&self, name: Mnemonic, size: Size, operands: &[Operand])
+                 -> Result<Vec<u8>, EncodeError> {
+        self.encode_by(name, size, |o| encode_as(o, operands)).map(|(bytes, _)| bytes)
+    }
+
+    /// Encodes an instruction with the given name and size, using operands
+    /// produced for each candidate opcode by the given function.  This lets
+    /// an assembler interpret source operands according to the operand forms
+    /// of each opcode.  Errors are as for `encode`.
+    ///
+    pub fn encode_with<'a, F>(&self, name: Mnemonic, size: Size, operands: F)
+                             -> Result<Vec<u8>, EncodeError>
+    where F: FnMut(&Opcode) -> Result<Vec<Operand<'a>>, EncodeError> {
+        self.encode_opcode_with(name, size, operands).map(|(bytes, _)| bytes)
+    }
+
+    /// Encodes an instruction like `encode_with`, also returning the opcode
+    /// used.
+    pub fn encode_opcode_with<'a, F>(&self, name: Mnemonic, size: Size, mut operands: F)
+                                    -> Result<(Vec<u8>, &'static Opcode), EncodeError>
+    where F: FnMut(&Opcode) -> Result<Vec<Operand<'a>>, EncodeError> {
+        self.encode_by(name, size, |o| encode_as(o, &operands(o)?))
+    }
+
+    /// Returns the opcodes with the given name and size that are supported
+    /// by the encoder's ISA profile.
+    pub fn opcodes<'e>(&'e self, name: Mnemonic, size: Size)
+                      -> impl Iterator<Item = &'static Opcode> + 'e {
+        self.opcodes.iter()
+            .filter(move |o| o.name == name && o.size == size && o.is_supported(self.isa))
+    }
+
+    fn encode_by<F>(&self, name: Mnemonic, size: Size, mut encode: F)
+                   -> Result<(Vec<u8>, &'static Opcode), EncodeError>
+    where F: FnMut(&Opcode) -> Result<Vec<u8>, EncodeError> {
+        let mut error = None;
+
+        for o in self.opcodes(name, size) {
+            match encode(o) {
+                Ok(bytes) => {
+                    return Ok((bytes, o));
+                },
+                Err(EncodeError::Mismatch) => {
+                    if error.is_none() { error = Some(EncodeError::Mismatch); }
+                },
+                Err(e) => {
+                    if let Some(EncodeError::Mismatch) | None = error { error = Some(e); }
+                },
+            }
+        }
+
+        Err(error.unwrap_or(EncodeError::NoOpcode(name, size)))
+    }
+
+    /// Encodes the given instruction.  Its opcode and bytes are ignored.
+    #[inline]
+    pub fn encode_instruction(&self, i: &Instruction) -> Result<Vec<u8>, EncodeError> {
+        self.encode(i.name, i.size, &i.operands)
+    }
+}
+
+/// Encodes an instruction with the given name, size, and operands, using
+/// any opcode in `OPCODES`.  See `Encoder::encode`.
+pub fn encode(name: Mnemonic, size: Size, operands: &[Operand])
+             -> Result<Vec<u8>, EncodeError> {
+    Encoder::new(OPCODES, CF_ALL).encode(name, size, operands)
+}
+
+fn encode_as(o: &Opcode, operands: &[Operand]) -> Result<Vec<u8>, EncodeError> {
+    let mut words = o.bits;
+    let mut more  = EncodeWriter::new(vec![], BE);
+
+    o.args.encode(operands, o.size, &mut words, &mut more)?;
+
+    // Operand fields must not disturb discriminant bits
+    if words.0 & o.mask.0 != o.bits.0 || words.1 & o.mask.1 != o.bits.1 {
+        return Err(EncodeError::Mismatch);
+    }
+
+    let mut w = EncodeWriter::new(vec![], BE);
+    w.write_u16(words.0)?;
+    if o.flags & EXT_WORD != 0 {
+        w.write_u16(words.1)?;
+    }
+    w.write_bytes(more.get_ref())?;
+    Ok(w.into_inner())
+}
+
+/// Returns an integer specifier for a field of the given width.
+#[inline]
+pub fn int_spec(width: u8, signed: bool) -> IntSpec {
+    IntSpec { store_width: width, value_width: width, value_scale: 0, signed }
+}
+
+/// Returns the value of the given constant integer expression.
+pub fn int_value<'e>(field: &'static str, e: &'e Expr) -> Result<&'e BigInt, EncodeError> {
+    match *e {
+        Expr::Int(ref i) => Ok(&i.value),
+        _                => Err(EncodeError::NotConstant(field)),
+    }
+}
+
+/// Returns the value of the given constant floating-point or integer
+/// expression.
+pub fn float_value(field: &'static str, e: &Expr) -> Result<f64, EncodeError> {
+    match *e {
+        Expr::Float(ref f) => Ok(f.value),
+        Expr::Int  (ref i) => i.value.to_f64().ok_or(EncodeError::NotConstant(field)),
+        _                  => Err(EncodeError::NotConstant(field)),
+    }
+}
+
+/// Encodes the given value into a field of the given specification.
+pub fn check_int(field: &'static str, spec: IntSpec, value: &BigInt)
+                -> Result<u64, EncodeError> {
+    spec.encode(value).map_err(|_| EncodeError::OutOfRange {
+        field,
+        value: value.clone(),
+        min:   spec.min_value(),
+        max:   spec.max_value(),
+    })
+}
+
+/// Encodes the given constant integer expression into a field of the given
+/// width, interpreting the field as signed or unsigned as the value
+/// requires.
+pub fn check_bits(field: &'static str, width: u8, e: &Expr) -> Result<u64, EncodeError> {
+    let value = int_value(field, e)?;
+    let s     = int_spec(width, true);
+    let u     = int_spec(width, false);
+
+    s.encode(value).or_else(|_| u.encode(value)).map_err(|_| EncodeError::OutOfRange {
+        field,
+        value: value.clone(),
+        min:   s.min_value(),
+        max:   u.max_value(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use aex::ast::{Expr, Float, Id, Int};
+    use super::*;
+    use super::super::*;
+
+    #[test]
+    fn encode_nullary() {
+        assert_eq!( encode(Mnemonic::Nop, Size::Zero, &[]).unwrap(), vec![0x4E, 0x71] );
+    }
+
+    #[test]
+    fn encode_modes() {
+        // move.l 8(%fp),%d0
+        let ops = [Operand::Mode(Mode::AddrDisp(FP, int(8))), Operand::Mode(Mode::Data(D0))];
+        assert_eq!( encode(Mnemonic::Move, Size::Long, &ops).unwrap(), vec![0x20, 0x2E, 0x00, 0x08] );
+
+        // lea.l -4(%pc,%d0*1),%a0
+        let x   = Index { reg: IndexReg::Data(D0), scale: Scale::Byte };
+        let ops = [Operand::Mode(Mode::PcIdxDisp(x, int(-4))), Operand::Mode(Mode::Addr(A0))];
+        assert_eq!( encode(Mnemonic::Lea, Size::Long, &ops).unwrap(), vec![0x41, 0xFB, 0x08, 0xFC] );
+
+        // move.l #0x12345678,(%a1)+
+        let ops = [Operand::Mode(Mode::Imm(int(0x12345678))), Operand::Mode(Mode::AddrPostInc(A1))];
+        assert_eq!( encode(Mnemonic::Move, Size::Long, &ops).unwrap(),
+                    vec![0x22, 0xFC, 0x12, 0x34, 0x56, 0x78] );
+    }
+
+    #[test]
+    fn encode_ext_word() {
+        // movem.l %d0-%d7,(%sp)
+        let ops = [Operand::RegList(RegList(0x00FF)), Operand::Mode(Mode::AddrInd(SP))];
+        assert_eq!( encode(Mnemonic::Movem, Size::Long, &ops).unwrap(), vec![0x48, 0xD7, 0x00, 0xFF] );
+
+        // movec.l %a1,%rambar1
+        let ops = [Operand::Mode(Mode::Addr(A1)), Operand::CtlReg(Rambar1)];
+        assert_eq!( encode(Mnemonic::Movec, Size::Long, &ops).unwrap(), vec![0x4E, 0x7B, 0x9C, 0x05] );
+    }
+
+    #[test]
+    fn encode_float_imm() {
+        // fmove.s #1.5,%fp0
+        let ops = [
+            Operand::Mode(Mode::Imm(Expr::Float(Float::new(1.5)))),
+            Operand::FpReg(FP0),
+        ];
+        let bytes = Encoder::new(OPCODES, CF_FPU).encode(Mnemonic::Fmove, Size::Single, &ops).unwrap();
+        assert_eq!( &bytes[4..], &[0x3F, 0xC0, 0x00, 0x00] );
+    }
+
+    #[test]
+    fn encode_quick() {
+        // addq.l #8,%d1
+        let ops = [Operand::Mode(Mode::Imm(int(8))), Operand::Mode(Mode::Data(D1))];
+        assert_eq!( encode(Mnemonic::Addq, Size::Long, &ops).unwrap(), vec![0x50, 0x81] );
+
+        // addq.l #9,%d1
+        let ops = [Operand::Mode(Mode::Imm(int(9))), Operand::Mode(Mode::Data(D1))];
+        match encode(Mnemonic::Addq, Size::Long, &ops).unwrap_err() {
+            EncodeError::OutOfRange { field, min, max, .. } => {
+                assert_eq!(field, "quick immediate");
+                assert_eq!(min,   BigInt::from(1));
+                assert_eq!(max,   BigInt::from(8));
+            },
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // moveq.l #-129,%d0
+        let ops = [Operand::Mode(Mode::Imm(int(-129))), Operand::Mode(Mode::Data(D0))];
+        let e   = encode(Mnemonic::Moveq, Size::Long, &ops).unwrap_err();
+        assert_eq!( e.to_string(), "quick immediate -129 out of range (-128 to 127)" );
+    }
+
+    #[test]
+    fn encode_branch() {
+        // bne.s *-2
+        let ops = [Operand::Cond(Condition::Ne), Operand::Branch(int(-4))];
+        assert_eq!( encode(Mnemonic::Bcc, Size::Byte, &ops).unwrap(), vec![0x66, 0xFC] );
+
+        // bne.s *+2 (reserved displacement)
+        let ops = [Operand::Cond(Condition::Ne), Operand::Branch(int(0))];
+        assert!(matches!(
+            encode(Mnemonic::Bcc, Size::Byte, &ops).unwrap_err(),
+            EncodeError::Reserved { field: "branch displacement", .. }
+        ));
+
+        // bne.w *+0x8002 (out of range)
+        let ops = [Operand::Cond(Condition::Ne), Operand::Branch(int(0x8000))];
+        assert!(matches!(
+            encode(Mnemonic::Bcc, Size::Word, &ops).unwrap_err(),
+            EncodeError::OutOfRange { field: "branch displacement", .. }
+        ));
+    }
+
+    #[test]
+    fn encode_disp_range() {
+        // move.l 0x8000(%a0),%d0
+        let ops = [Operand::Mode(Mode::AddrDisp(A0, int(0x8000))), Operand::Mode(Mode::Data(D0))];
+        let e   = encode(Mnemonic::Move, Size::Long, &ops).unwrap_err();
+        assert_eq!( e.to_string(), "displacement 32768 out of range (-32768 to 32767)" );
+    }
+
+    #[test]
+    fn encode_not_constant() {
+        let ops = [Operand::Mode(Mode::Abs32(Expr::Id(Id::new("x")))), Operand::Mode(Mode::Data(D0))];
+        assert!(matches!(
+            encode(Mnemonic::Move, Size::Long, &ops).unwrap_err(),
+            EncodeError::NotConstant("absolute address")
+        ));
+    }
+
+    #[test]
+    fn encode_mismatch() {
+        // lea.l %d0,%a0
+        let ops = [Operand::Mode(Mode::Data(D0)), Operand::Mode(Mode::Addr(A0))];
+        assert!(matches!(
+            encode(Mnemonic::Lea, Size::Long, &ops).unwrap_err(),
+            EncodeError::Mismatch
+        ));
+
+        // nop %d0
+        let ops = [Operand::Mode(Mode::Data(D0))];
+        assert!(matches!(
+            encode(Mnemonic::Nop, Size::Zero, &ops).unwrap_err(),
+            EncodeError::Mismatch
+        ));
+    }
+
+    #[test]
+    fn encode_no_opcode() {
+        let e = encode(Mnemonic::Nop, Size::Long, &[]).unwrap_err();
+        assert_eq!( e.to_string(), "no opcode for nop.l" );
+    }
+
+    #[test]
+    fn encode_isa() {
+        // mov3q.l #1,%d0 (ISA_B)
+        let ops = [Operand::Mode(Mode::Imm(int(1))), Operand::Mode(Mode::Data(D0))];
+        assert!( Encoder::new(OPCODES, CF_A).encode(Mnemonic::Mov3q, Size::Long, &ops).is_err() );
+        assert!( Encoder::new(OPCODES, CF_B).encode(Mnemonic::Mov3q, Size::Long, &ops).is_ok()  );
+    }
+
+    #[test]
+    fn check_bits() {
+        assert_eq!( super::check_bits("x", 8, &int(-1)).unwrap(),  0xFF );
+        assert_eq!( super::check_bits("x", 8, &int(255)).unwrap(), 0xFF );
+        assert!(    super::check_bits("x", 8, &int(256)).is_err() );
+    }
+
+    fn int<'a>(n: i64) -> Expr<'a> {
+        Expr::Int(Int::new(n))
+    }
+}
@@ -0,0 +1,109 @@
+// ColdFire Floating-Point Data Registers
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use aex::ast::Reg;
+use aex::fmt::ToCode;
+
+pub use self::FpReg::*;
+
+/// ColdFire floating-point data registers.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum FpReg {
+    FP0, FP1, FP2, FP3, FP4, FP5, FP6, FP7
+}
+
+static FP_REGS: [FpReg; 8] = [
+    FP0, FP1, FP2, FP3, FP4, FP5, FP6, FP7
+];
+
+static FP_REG_NAMES: [&str; 8] = [
+    "fp0", "fp1", "fp2", "fp3", "fp4", "fp5", "fp6", "fp7"
+];
+
+impl FpReg {
+    /// Returns the floating-point data register with the given number.
+    #[inline]
+    pub fn with_num(n: u8) -> Self {
+        FP_REGS[n as usize]
+    }
+
+    /// Returns the floating-point data register with the given name, if any.
+    pub fn with_name(name: &str) -> Option<Self> {
+        FP_REG_NAMES.iter()
+            .position(|n| n.eq_ignore_ascii_case(name))
+            .map(|i| FP_REGS[i])
+    }
+
+    /// Returns the number of the floating-point data register.
+    #[inline]
+    pub fn num(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the name of the floating-point data register.
+    #[inline]
+    pub fn name(self) -> &'static str {
+        FP_REG_NAMES[self as usize]
+    }
+}
+
+impl<A> ToCode<A> for FpReg {
+    type Output = Reg<'static, A>;
+
+    /// Converts to a code-formattable value with the given annotation.
+    #[inline]
+    fn to_code(&self, ann: A) -> Self::Output {
+        Reg::new_with_ann(self.name(), ann)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_num() {
+        assert_eq!( FpReg::with_num(3), FP3 );
+    }
+
+    #[test]
+    fn with_name() {
+        assert_eq!( FpReg::with_name("fp4"), Some(FP4) );
+        assert_eq!( FpReg::with_name("FP0"), Some(FP0) );
+        assert_eq!( FpReg::with_name("fp"),  None      );
+    }
+
+    #[test]
+    fn num() {
+        assert_eq!( FP6.num(), 6 );
+    }
+
+    #[test]
+    fn name() {
+        assert_eq!( FP5.name(), "fp5" );
+    }
+
+    #[test]
+    fn to_code() {
+        let c = FP0.to_code(42);
+
+        assert_eq!(c.name, "fp0");
+        assert_eq!(c.ann,   42 );
+    }
+}
+
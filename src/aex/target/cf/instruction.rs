@@ -0,0 +1,294 @@
+// ColdFire Instructions
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use std::fmt::{self, Formatter, Write};
+use num::ToPrimitive;
+
+use aex::ast::{Expr, Node};
+use aex::fmt::{Code, Style};
+
+use super::{Condition, FpCondition, MacShift, Mnemonic, Mode, Opcode, Operand, Size, EXT_WORD};
+
+/// A decoded ColdFire instruction.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Instruction<'a> {
+    /// Instruction name.
+    pub name: Mnemonic,
+
+    /// Operation size.
+    pub size: Size,
+
+    /// Entry in the opcodes table.
+    pub opcode: &'static Opcode,
+
+    /// Operands, in assembly order.
+    pub operands: Vec<Operand<'a>>,
+
+    /// Address (VMA) of the instruction.
+    pub addr: u64,
+
+    /// Encoded bytes of the instruction, including extension words.
+    pub bytes: Vec<u8>,
+}
+
+impl<'a> Instruction<'a> {
+    /// Returns the condition tested by the instruction, if it is a `bcc` or
+    /// `scc`.
+    pub fn cond(&self) -> Option<Condition> {
+        match (self.name, self.operands.first()) {
+            (Mnemonic::Bcc, Some(&Operand::Cond(c))) |
+            (Mnemonic::Scc, Some(&Operand::Cond(c))) => Some(c),
+            _                                        => None,
+        }
+    }
+
+    /// Returns the floating-point condition tested by the instruction, if it
+    /// is an `fbcc`.
+    pub fn fp_cond(&self) -> Option<FpCondition> {
+        match (self.name, self.operands.first()) {
+            (Mnemonic::Fbcc, Some(&Operand::FpCond(c))) => Some(c),
+            _                                           => None,
+        }
+    }
+
+    /// Returns the target address of the instruction's branch operand, if
+    /// any.
+    pub fn target(&self) -> Option<u64> {
+        self.operands.iter()
+            .filter_map(|o| match *o {
+                Operand::Branch(Expr::Int(ref d)) => d.value.to_i64(),
+                _                                 => None,
+            })
+            .map(|d| self.addr.wrapping_add(2).wrapping_add(d as u64))
+            .next()
+    }
+
+    /// Returns the address referenced by the instruction's PC-relative
+    /// operand, if any.  For an indexed operand, this is the address before
+    /// indexing.
+    pub fn pc_ref(&self) -> Option<u64> {
+        // PC is the address of the first extension word after the opcode
+        let pc = match self.opcode.flags & EXT_WORD {
+            0 => self.addr.wrapping_add(2),
+            _ => self.addr.wrapping_add(4),
+        };
+
+        self.operands.iter()
+            .filter_map(|o| match *o {
+                Operand::Mode(Mode::PcDisp   (   Expr::Int(ref d))) |
+                Operand::Mode(Mode::PcIdxDisp(_, Expr::Int(ref d))) => d.value.to_i64(),
+                _                                                   => None,
+            })
+            .map(|d| pc.wrapping_add(d as u64))
+            .next()
+    }
+}
+
+impl<'a> Node for Instruction<'a> {
+    /// Annotation type.
+    type Ann = ();
+
+    /// Gets the annotation for this node.
+    fn ann(&self) -> &() { &() }
+}
+
+impl<'a> Code for Instruction<'a> {
+    /// Formats the value as code, using the given formatter and style.
+    ///
+    /// Branch operands are written as target addresses, in hexadecimal.  An
+    /// omitted MAC shift factor is not written.  The condition of a `bcc`,
+    /// `scc`, or `fbcc` is folded into the name, like `beq.s`, `sne`, or
+    /// `fbogt.w`.
+    ///
+    fn fmt<S: Style<()> + ?Sized>
+          (&self, f: &mut Formatter, s: &S) -> fmt::Result {
+        let size = match (self.name, self.size) {
+            (Mnemonic::Bra, Size::Byte) |
+            (Mnemonic::Bsr, Size::Byte) |
+            (Mnemonic::Bcc, Size::Byte) => "s",
+            (Mnemonic::Scc, _)          => "",
+            (_,             size)       => size.suffix(),
+        };
+
+        let cond    = self.cond();
+        let fp_cond = self.fp_cond();
+        match (cond, fp_cond) {
+            (Some(c), _) => {
+                let name = self.name.as_str();
+                let name = format!("{}{}", &name[..1], c.name_pref(s.prefer_aliases()));
+                s.write_op(f, &name, size)?;
+            },
+            (_, Some(c)) => {
+                s.write_op(f, &format!("fb{}", c.name()), size)?;
+            },
+            _ => {
+                s.write_op(f, self.name.as_str(), size)?;
+            },
+        }
+
+        let operands = self.operands.iter()
+            .filter(|o| **o != Operand::MacShift(MacShift::None))
+            .filter(|o| cond.is_none()    || !matches!(**o, Operand::Cond(_)))
+            .filter(|o| fp_cond.is_none() || !matches!(**o, Operand::FpCond(_)));
+
+        for (i, o) in operands.enumerate() {
+            if i == 0 { f.write_char(' ')?; } else { s.write_arg_sep(f)?; }
+
+            match (o, self.target()) {
+                (&Operand::Branch(_), Some(t)) => s.write_addr(f, t)?,
+                _                              => o.fmt(f, s)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use aex::fmt::{AttStyle, MitStyle, ToStyled};
+    use aex::io::{DecodeReader, RewindReader};
+    use aex::util::BE;
+    use super::super::*;
+
+    #[test]
+    fn target() {
+        // bra.w *-4
+        let i = decode_at(&[0x60, 0x00, 0xFF, 0xFA], 0x2000);
+        assert_eq!(i.target(), Some(0x1FFC));
+
+        // nop
+        let i = decode_at(&[0x4E, 0x71], 0x2000);
+        assert_eq!(i.target(), None);
+    }
+
+    #[test]
+    fn cond() {
+        // beq.s *+6
+        let i = decode_at(&[0x67, 0x04], 0);
+        assert_eq!(i.cond(), Some(Condition::Eq));
+
+        // bra.s *+6
+        let i = decode_at(&[0x60, 0x04], 0);
+        assert_eq!(i.cond(), None);
+
+        // fbogt.w *+18
+        let i = decode_at(&[0xF2, 0x82, 0x00, 0x10], 0);
+        assert_eq!(i.cond(),    None);
+        assert_eq!(i.fp_cond(), Some(FpCondition::Ogt));
+    }
+
+    #[test]
+    fn pc_ref() {
+        // lea.l 16(%pc),%a0
+        let i = decode_at(&[0x41, 0xFA, 0x00, 0x10], 0x2000);
+        assert_eq!(i.pc_ref(), Some(0x2012));
+
+        // move.l -4(%pc,%d0*1),%d1
+        let i = decode_at(&[0x22, 0x3B, 0x08, 0xFC], 0x2000);
+        assert_eq!(i.pc_ref(), Some(0x1FFE));
+
+        // move.l %d0,%d1
+        let i = decode_at(&[0x22, 0x00], 0x2000);
+        assert_eq!(i.pc_ref(), None);
+    }
+
+    #[test]
+    fn fmt() {
+        let s = AttStyle::default();
+
+        // bra.w *-4
+        let i = decode_at(&[0x60, 0x00, 0xFF, 0xFA], 0x2000);
+        assert_eq!(i.styled(&s).to_string(), "bra.w 0x1ffc");
+
+        // remu.l %d0,%d2:%d1
+        let i = decode_at(&[0x4C, 0x40, 0x10, 0x02], 0);
+        assert_eq!(i.styled(&s).to_string(), "remu.l %d0,%d2:%d1");
+
+        // movec %a0,%vbr
+        let i = decode_at(&[0x4E, 0x7B, 0x88, 0x01], 0);
+        assert_eq!(i.styled(&s).to_string(), "movec.l %a0,%vbr");
+
+        // mac.w %d1.l,%d2.u
+        let i = decode_at(&[0xA4, 0x01, 0x00, 0x80], 0);
+        assert_eq!(i.styled(&s).to_string(), "mac.w %d1.l,%d2.u");
+
+        // movem.l %d0-%d7,(%sp)
+        let i = decode_at(&[0x48, 0xD7, 0x00, 0xFF], 0);
+        assert_eq!(i.styled(&s).to_string(), "movem.l %d0-%d7,(%sp)");
+
+        // movem.l (%sp),%d2-%d7/%a2-%fp
+        let i = decode_at(&[0x4C, 0xD7, 0x7C, 0xFC], 0);
+        assert_eq!(i.styled(&s).to_string(), "movem.l (%sp),%d2-%d7/%a2-%fp");
+    }
+
+    #[test]
+    fn fmt_cond() {
+        let att = AttStyle::default();
+        let mit = MitStyle::default();
+
+        // beq.s *+6
+        let i = decode_at(&[0x67, 0x04], 0x1000);
+        assert_eq!(i.styled(&att).to_string(), "beq.s 0x1006");
+        assert_eq!(i.styled(&mit).to_string(), "beqs 0x1006");
+
+        // bra.s *+6
+        let i = decode_at(&[0x60, 0x04], 0x1000);
+        assert_eq!(i.styled(&att).to_string(), "bra.s 0x1006");
+
+        // bhi.w *+0x102
+        let i = decode_at(&[0x62, 0x00, 0x01, 0x00], 0x1000);
+        assert_eq!(i.styled(&att).to_string(), "bhi.w 0x1102");
+
+        // sne %d0
+        let i = decode_at(&[0x56, 0xC0], 0);
+        assert_eq!(i.styled(&att).to_string(), "sne %d0");
+
+        // fbeq.w *+18
+        let i = decode_at(&[0xF2, 0x81, 0x00, 0x10], 0x1000);
+        assert_eq!(i.styled(&att).to_string(), "fbeq.w 0x1012");
+        assert_eq!(i.styled(&mit).to_string(), "fbeqw 0x1012");
+
+        // fmovem.d %fp0-%fp3/%fp5,(%sp)
+        let i = decode_at(&[0xF2, 0x17, 0xF0, 0xF4], 0);
+        assert_eq!(i.styled(&att).to_string(), "fmovem.d %fp0-%fp3/%fp5,(%sp)");
+    }
+
+    #[test]
+    fn fmt_cond_alias() {
+        let mut s = AttStyle::default();
+
+        // bcc.s *+6; scs %d1
+        let b = decode_at(&[0x64, 0x04], 0x1000);
+        let c = decode_at(&[0x55, 0xC1], 0);
+        assert_eq!(b.styled(&s).to_string(), "bcc.s 0x1006");
+        assert_eq!(c.styled(&s).to_string(), "scs %d1");
+
+        s.prefer_aliases = true;
+        assert_eq!(b.styled(&s).to_string(), "bhs.s 0x1006");
+        assert_eq!(c.styled(&s).to_string(), "slo %d1");
+    }
+
+    fn decode_at<'a>(bytes: &[u8], reloc: u64) -> Instruction<'a> {
+        let src   = Cursor::new(bytes.to_vec());
+        let mut r = DecodeReader::new(RewindReader::new(src), BE, reloc);
+        decode(OPCODES, CF_A | CF_MAC | CF_FPU, &mut r).unwrap().unwrap()
+    }
+}
@@ -0,0 +1,214 @@
+// ColdFire Disassembly Labels
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::collections::btree_map::Iter;
+
+use aex::ast::{Expr, Id};
+
+use super::{Instruction, Item, Line, Mode, Operand};
+use super::Mnemonic::*;
+
+/// Kinds of synthesized labels, in increasing order of precedence.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum LabelKind {
+    /// Data referenced by a PC-relative operand.
+    Data,
+
+    /// Code reached by a branch or jump.
+    Local,
+
+    /// Subroutine called by `bsr` or `jsr`.
+    Sub,
+}
+
+impl LabelKind {
+    /// Returns the prefix of label names of this kind.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            LabelKind::Data  => "data_",
+            LabelKind::Local => "L_",
+            LabelKind::Sub   => "sub_",
+        }
+    }
+}
+
+/// Labels synthesized for the addresses referenced by disassembled code.
+///
+/// Each label is named for its kind and address, like `L_0001f2a4`.  An
+/// address referenced in several ways gets the label of the highest-precedence
+/// kind.
+///
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Labels {
+    map: BTreeMap<u64, (LabelKind, String)>,
+}
+
+impl Labels {
+    /// Creates an empty set of labels.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates labels for the addresses referenced by instructions in the
+    /// given lines.  Only addresses at which a line begins get labels.
+    pub fn collect(lines: &[Line]) -> Self {
+        let starts = lines.iter()
+            .map(|l| l.addr)
+            .collect::<BTreeSet<_>>();
+
+        let mut labels = Self::new();
+
+        for line in lines {
+            let i = match line.item {
+                Item::Instruction(ref i) => i,
+                _                        => continue,
+            };
+
+            let call = matches!(i.name, Bsr | Jsr);
+            let jump = matches!(i.name, Jmp);
+
+            if let Some(t) = i.target() {
+                if starts.contains(&t) {
+                    labels.insert(t, if call { LabelKind::Sub } else { LabelKind::Local });
+                }
+            }
+
+            if let Some(t) = i.pc_ref() {
+                if starts.contains(&t) {
+                    let kind = if call {
+                        LabelKind::Sub
+                    } else if jump {
+                        LabelKind::Local
+                    } else {
+                        LabelKind::Data
+                    };
+                    labels.insert(t, kind);
+                }
+            }
+        }
+
+        labels
+    }
+
+    /// Adds a label of the given kind at the given address, unless the address
+    /// already has a label of the same or higher precedence.
+    pub fn insert(&mut self, addr: u64, kind: LabelKind) {
+        if let Some(&(k, _)) = self.map.get(&addr) {
+            if k >= kind { return; }
+        }
+        let name = format!("{}{:08x}", kind.prefix(), addr);
+        self.map.insert(addr, (kind, name));
+    }
+
+    /// Returns the name of the label at the given address, if any.
+    pub fn get(&self, addr: u64) -> Option<&str> {
+        self.map.get(&addr).map(|entry| &entry.1[..])
+    }
+
+    /// Returns the labels, in address order.
+    pub fn iter<'l>(&'l self) -> Iter<'l, u64, (LabelKind, String)> {
+        self.map.iter()
+    }
+
+    /// Replaces the branch targets and PC-relative displacements of the given
+    /// instruction with labels, where labels exist for the addresses.
+    pub fn apply<'a>(&'a self, i: &mut Instruction<'a>) {
+        let target = i.target() .and_then(|a| self.get(a));
+        let pc_ref = i.pc_ref() .and_then(|a| self.get(a));
+
+        for o in &mut i.operands {
+            match *o {
+                Operand::Branch(ref mut e) => {
+                    if let Some(name) = target { *e = Expr::Id(Id::new(name)); }
+                },
+                Operand::Mode(Mode::PcDisp(ref mut e)) |
+                Operand::Mode(Mode::PcIdxDisp(_, ref mut e)) => {
+                    if let Some(name) = pc_ref { *e = Expr::Id(Id::new(name)); }
+                },
+                _ => {},
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aex::util::BE;
+    use super::*;
+    use super::super::*;
+
+    static IMAGE: [u8; 14] = [
+        0x61, 0x06,                     // 1000: bsr.s    1008
+        0x41, 0xFA, 0x00, 0x06,         // 1002: lea.l    6(%pc),%a0    ; 100a
+        0x60, 0xFA,                     // 1006: bra.s    1002
+        0x4E, 0x75,                     // 1008: rts
+        0x12, 0x34, 0x56, 0x78,         // 100a: (data)
+    ];
+
+    #[test]
+    fn insert() {
+        let mut l = Labels::new();
+        l.insert(0x10, LabelKind::Local);
+        l.insert(0x10, LabelKind::Data);
+        assert_eq!(l.get(0x10), Some("L_00000010"));
+        l.insert(0x10, LabelKind::Sub);
+        assert_eq!(l.get(0x10), Some("sub_00000010"));
+        assert_eq!(l.get(0x12), None);
+    }
+
+    #[test]
+    fn collect() {
+        let l     = Labels::collect(&lines());
+        let names = l.iter().map(|(_, entry)| &entry.1[..]).collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["L_00001002", "sub_00001008", "data_0000100a"]);
+    }
+
+    #[test]
+    fn collect_outside_lines() {
+        let lines = lines();
+        let l     = Labels::collect(&lines[..2]);
+
+        assert_eq!(l.iter().count(), 0);
+    }
+
+    #[test]
+    fn apply() {
+        let mut lines = lines();
+        let     l     = Labels::collect(&lines);
+
+        let ops = lines.iter_mut()
+            .filter_map(|line| match line.item {
+                Item::Instruction(ref mut i) => {
+                    l.apply(i);
+                    i.operands.first().cloned()
+                },
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(ops[0], Operand::Branch(Expr::Id(Id::new("sub_00001008"))));
+        assert_eq!(ops[1], Operand::Mode(Mode::PcDisp(Expr::Id(Id::new("data_0000100a")))));
+        assert_eq!(ops[2], Operand::Branch(Expr::Id(Id::new("L_00001002"))));
+    }
+
+    fn lines() -> Vec<Line<'static>> {
+        Tracer::new(OPCODES, CF_A).trace(&IMAGE, BE, 0x1000, &[0x1000]).lines()
+    }
+}
@@ -0,0 +1,235 @@
+// ColdFire MAC/EMAC Registers and Fields
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use aex::ast::Reg;
+use aex::fmt::ToCode;
+
+pub use self::MacReg::*;
+
+/// ColdFire MAC and EMAC registers.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum MacReg {
+    /// Accumulator 0 (the only accumulator of the MAC unit)
+    Acc0,
+
+    /// Accumulator 1 (EMAC)
+    Acc1,
+
+    /// Accumulator 2 (EMAC)
+    Acc2,
+
+    /// Accumulator 3 (EMAC)
+    Acc3,
+
+    /// Status register
+    Macsr,
+
+    /// Address mask register
+    Mask,
+
+    /// Accumulator extension bytes, accumulators 0 and 1 (EMAC)
+    AccExt01,
+
+    /// Accumulator extension bytes, accumulators 2 and 3 (EMAC)
+    AccExt23,
+}
+
+static ACCS: [MacReg; 4] = [
+    Acc0, Acc1, Acc2, Acc3
+];
+
+static MAC_REGS: [MacReg; 8] = [
+    Acc0, Acc1, Acc2, Acc3, Macsr, Mask, AccExt01, AccExt23
+];
+
+static MAC_REG_NAMES: [&str; 8] = [
+    "acc0", "acc1", "acc2", "acc3", "macsr", "mask", "accext01", "accext23"
+];
+
+impl MacReg {
+    /// Returns the accumulator with the given number.
+    #[inline]
+    pub fn acc(n: u8) -> Self {
+        ACCS[n as usize]
+    }
+
+    /// Returns the MAC register with the given name, if any.
+    pub fn with_name(name: &str) -> Option<Self> {
+        MAC_REG_NAMES.iter()
+            .position(|n| n.eq_ignore_ascii_case(name))
+            .map(|i| MAC_REGS[i])
+    }
+
+    /// Returns the number of the accumulator, or `None` if the register is
+    /// not an accumulator.
+    #[inline]
+    pub fn acc_num(self) -> Option<u8> {
+        ACCS.iter().position(|&r| r == self).map(|n| n as u8)
+    }
+
+    /// Returns the name of the register.
+    #[inline]
+    pub fn name(self) -> &'static str {
+        MAC_REG_NAMES[self as usize]
+    }
+}
+
+impl<A> ToCode<A> for MacReg {
+    type Output = Reg<'static, A>;
+
+    /// Converts to a code-formattable value with the given annotation.
+    #[inline]
+    fn to_code(&self, ann: A) -> Self::Output {
+        Reg::new_with_ann(self.name(), ann)
+    }
+}
+
+/// Register halves selected by word-sized MAC operations.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum MacHalf {
+    /// Lower word (bits 15-0)
+    Lower,
+
+    /// Upper word (bits 31-16)
+    Upper,
+}
+
+impl MacHalf {
+    /// Decodes a register half from a U/L bit.
+    #[inline]
+    pub fn decode(bit: u16) -> Self {
+        if bit == 0 { MacHalf::Lower } else { MacHalf::Upper }
+    }
+
+    /// Encodes the register half as a U/L bit.
+    #[inline]
+    pub fn encode(self) -> u16 {
+        self as u16
+    }
+
+    /// Returns the suffix that selects the register half.
+    #[inline]
+    pub fn suffix(self) -> &'static str {
+        match self {
+            MacHalf::Lower => ".l",
+            MacHalf::Upper => ".u",
+        }
+    }
+}
+
+/// Product shift factors of MAC operations.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum MacShift {
+    /// No shift
+    None,
+
+    /// Shift left by 1 bit (<<)
+    Left,
+
+    /// Shift right by 1 bit (>>)
+    Right,
+}
+
+impl MacShift {
+    /// Decodes a shift factor from a 2-bit field.  Returns `None` for the
+    /// reserved encoding.
+    pub fn decode(bits: u16) -> Option<Self> {
+        match bits & 0b11 {
+            0b00 => Some(MacShift::None),
+            0b01 => Some(MacShift::Left),
+            0b11 => Some(MacShift::Right),
+            _    => None,
+        }
+    }
+
+    /// Encodes the shift factor as a 2-bit field.
+    pub fn encode(self) -> u16 {
+        match self {
+            MacShift::None  => 0b00,
+            MacShift::Left  => 0b01,
+            MacShift::Right => 0b11,
+        }
+    }
+
+    /// Returns the operator that denotes the shift factor, or an empty
+    /// string if there is no shift.
+    #[inline]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MacShift::None  => "",
+            MacShift::Left  => "<<",
+            MacShift::Right => ">>",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acc() {
+        assert_eq!( MacReg::acc(2), Acc2 );
+    }
+
+    #[test]
+    fn with_name() {
+        assert_eq!( MacReg::with_name("acc3"),  Some(Acc3)  );
+        assert_eq!( MacReg::with_name("MACSR"), Some(Macsr) );
+        assert_eq!( MacReg::with_name("acc"),   None        );
+    }
+
+    #[test]
+    fn acc_num() {
+        assert_eq!( Acc3 .acc_num(), Some(3) );
+        assert_eq!( Macsr.acc_num(), None    );
+    }
+
+    #[test]
+    fn name() {
+        assert_eq!( Acc1    .name(), "acc1"     );
+        assert_eq!( Macsr   .name(), "macsr"    );
+        assert_eq!( AccExt23.name(), "accext23" );
+    }
+
+    #[test]
+    fn to_code() {
+        let c = Mask.to_code(42);
+
+        assert_eq!(c.name, "mask");
+        assert_eq!(c.ann,   42 );
+    }
+
+    #[test]
+    fn half() {
+        assert_eq!( MacHalf::decode(0), MacHalf::Lower );
+        assert_eq!( MacHalf::decode(1), MacHalf::Upper );
+        assert_eq!( MacHalf::Upper.suffix(), ".u" );
+        assert_eq!( MacHalf::Upper.encode(), 1    );
+    }
+
+    #[test]
+    fn shift() {
+        assert_eq!( MacShift::decode(0b00), Some(MacShift::None)  );
+        assert_eq!( MacShift::decode(0b01), Some(MacShift::Left)  );
+        assert_eq!( MacShift::decode(0b10), None                  );
+        assert_eq!( MacShift::decode(0b11), Some(MacShift::Right) );
+        assert_eq!( MacShift::Right.as_str(), ">>" );
+        assert_eq!( MacShift::Right.encode(), 0b11 );
+    }
+}
@@ -0,0 +1,197 @@
+// ColdFire Instruction Names
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use self::Mnemonic::*;
+
+/// Instruction names.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Mnemonic {
+    Add, Adda, Addi, Addq, Addx, And, Andi, Asl, Asr,
+    Bcc, Bchg, Bclr, Bitrev, Bra, Bset, Bsr, Btst, Byterev,
+    Clr, Cmp, Cmpa, Cmpi, Cpushl,
+    Divs, Divu,
+    Eor, Eori, Ext, Extb,
+    Fabs, Fadd, Fbcc, Fcmp, Fdiv, Ff1, Fint, Fintrz, Fmove, Fmovem, Fmul, Fneg, Frestore, Fsave, Fsqrt, Fsub, Ftst,
+    Halt,
+    Illegal, Intouch,
+    Jmp, Jsr,
+    Lea, Link, Lsl, Lsr,
+    Mac, Mov3q, Movclr, Move, Movea, Movec, Movem, Moveq, Msac, Muls, Mulu, Mvs, Mvz,
+    Neg, Negx, Nop, Not,
+    Or, Ori,
+    Pea, Pulse,
+    Rems, Remu, Rte, Rts,
+    Sats, Scc, Stop, Strldsr, Sub, Suba, Subi, Subq, Subx, Swap,
+    Tas, Tpf, Trap, Tst,
+    Unlk,
+    Wddata, Wdebug,
+}
+
+static MNEMONICS: [Mnemonic; 97] = [
+    Add, Adda, Addi, Addq, Addx, And, Andi, Asl, Asr,
+    Bcc, Bchg, Bclr, Bitrev, Bra, Bset, Bsr, Btst, Byterev,
+    Clr, Cmp, Cmpa, Cmpi, Cpushl,
+    Divs, Divu,
+    Eor, Eori, Ext, Extb,
+    Fabs, Fadd, Fbcc, Fcmp, Fdiv, Ff1, Fint, Fintrz, Fmove, Fmovem, Fmul, Fneg, Frestore, Fsave, Fsqrt, Fsub, Ftst,
+    Halt,
+    Illegal, Intouch,
+    Jmp, Jsr,
+    Lea, Link, Lsl, Lsr,
+    Mac, Mov3q, Movclr, Move, Movea, Movec, Movem, Moveq, Msac, Muls, Mulu, Mvs, Mvz,
+    Neg, Negx, Nop, Not,
+    Or, Ori,
+    Pea, Pulse,
+    Rems, Remu, Rte, Rts,
+    Sats, Scc, Stop, Strldsr, Sub, Suba, Subi, Subq, Subx, Swap,
+    Tas, Tpf, Trap, Tst,
+    Unlk,
+    Wddata, Wdebug,
+];
+
+impl Mnemonic {
+    /// Returns the instruction with the given name, if any.
+    pub fn with_name(name: &str) -> Option<Self> {
+        MNEMONICS.iter()
+            .find(|m| m.as_str().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    /// Returns the string representation of the instruction name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Add      => "add",
+            Adda     => "adda",
+            Addi     => "addi",
+            Addq     => "addq",
+            Addx     => "addx",
+            And      => "and",
+            Andi     => "andi",
+            Asl      => "asl",
+            Asr      => "asr",
+            Bcc      => "bcc",
+            Bchg     => "bchg",
+            Bclr     => "bclr",
+            Bitrev   => "bitrev",
+            Bra      => "bra",
+            Bset     => "bset",
+            Bsr      => "bsr",
+            Btst     => "btst",
+            Byterev  => "byterev",
+            Clr      => "clr",
+            Cmp      => "cmp",
+            Cmpa     => "cmpa",
+            Cmpi     => "cmpi",
+            Cpushl   => "cpushl",
+            Divs     => "divs",
+            Divu     => "divu",
+            Eor      => "eor",
+            Eori     => "eori",
+            Ext      => "ext",
+            Extb     => "extb",
+            Fabs     => "fabs",
+            Fadd     => "fadd",
+            Fbcc     => "fbcc",
+            Fcmp     => "fcmp",
+            Fdiv     => "fdiv",
+            Ff1      => "ff1",
+            Fint     => "fint",
+            Fintrz   => "fintrz",
+            Fmove    => "fmove",
+            Fmovem   => "fmovem",
+            Fmul     => "fmul",
+            Fneg     => "fneg",
+            Frestore => "frestore",
+            Fsave    => "fsave",
+            Fsqrt    => "fsqrt",
+            Fsub     => "fsub",
+            Ftst     => "ftst",
+            Halt     => "halt",
+            Illegal  => "illegal",
+            Intouch  => "intouch",
+            Jmp      => "jmp",
+            Jsr      => "jsr",
+            Lea      => "lea",
+            Link     => "link",
+            Lsl      => "lsl",
+            Lsr      => "lsr",
+            Mac      => "mac",
+            Mov3q    => "mov3q",
+            Movclr   => "movclr",
+            Move     => "move",
+            Movea    => "movea",
+            Movec    => "movec",
+            Movem    => "movem",
+            Moveq    => "moveq",
+            Msac     => "msac",
+            Muls     => "muls",
+            Mulu     => "mulu",
+            Mvs      => "mvs",
+            Mvz      => "mvz",
+            Neg      => "neg",
+            Negx     => "negx",
+            Nop      => "nop",
+            Not      => "not",
+            Or       => "or",
+            Ori      => "ori",
+            Pea      => "pea",
+            Pulse    => "pulse",
+            Rems     => "rems",
+            Remu     => "remu",
+            Rte      => "rte",
+            Rts      => "rts",
+            Sats     => "sats",
+            Scc      => "scc",
+            Stop     => "stop",
+            Strldsr  => "strldsr",
+            Sub      => "sub",
+            Suba     => "suba",
+            Subi     => "subi",
+            Subq     => "subq",
+            Subx     => "subx",
+            Swap     => "swap",
+            Tas      => "tas",
+            Tpf      => "tpf",
+            Trap     => "trap",
+            Tst      => "tst",
+            Unlk     => "unlk",
+            Wddata   => "wddata",
+            Wdebug   => "wdebug",
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_name() {
+        assert_eq!( Mnemonic::with_name("movem"), Some(Movem) );
+        assert_eq!( Mnemonic::with_name("RTS"),   Some(Rts)   );
+        assert_eq!( Mnemonic::with_name("mov"),   None        );
+    }
+
+    #[test]
+    fn with_name_all() {
+        for &m in MNEMONICS.iter() {
+            assert_eq!( Mnemonic::with_name(m.as_str()), Some(m) );
+        }
+    }
+}
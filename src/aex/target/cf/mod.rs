@@ -0,0 +1,131 @@
+// ColdFire Target
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+// Operand Representations
+
+//mod index;
+mod scale;
+mod cond;
+mod data_reg;
+mod addr_reg;
+mod ctl_reg;
+mod dbg_reg;
+mod fp_reg;
+mod reg_list;
+mod mac;
+//mod addr_disp;
+//mod addr_disp_idx;
+//mod misc_regs;
+//mod pc_disp;
+//mod pc_disp_idx;
+mod modes;
+
+//pub use self::index::*;
+pub use self::scale::*;
+pub use self::cond::*;
+pub use self::data_reg::*; // mode 0
+pub use self::addr_reg::*; // mode 1
+pub use self::ctl_reg::*;
+pub use self::dbg_reg::*;
+pub use self::fp_reg::*;
+pub use self::reg_list::*;
+pub use self::mac::*;
+//pub use self::addr_disp::*;
+//pub use self::addr_disp_idx::*;
+//pub use self::data_reg::*;
+//pub use self::misc_regs::*;
+//pub use self::pc_disp::*;
+//pub use self::pc_disp_idx::*;
+pub use self::modes::*;
+
+// Encoding / Decoding
+
+mod check;
+mod decode;
+mod disasm;
+mod encode;
+mod instruction;
+mod labels;
+mod mnemonics;
+mod opcodes;
+mod operand;
+mod relax;
+mod round_trip;
+mod trace;
+
+pub use self::check::*;
+pub use self::decode::*;
+pub use self::disasm::*;
+pub use self::encode::*;
+pub use self::instruction::*;
+pub use self::labels::*;
+pub use self::mnemonics::*;
+pub use self::opcodes::*;
+pub use self::operand::*;
+pub use self::relax::*;
+pub use self::round_trip::*;
+pub use self::trace::*;
+
+/// Operation sizes.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Size {
+    /// No associated size.
+    Zero,
+
+    /// Byte
+    Byte,
+
+    /// Word (2 bytes)
+    Word,
+
+    /// Longword (4 bytes)
+    Long,
+
+    /// Single-precision floating-point (4 bytes)
+    Single,
+
+    /// Double-precision floating-point (8 bytes)
+    Double,
+}
+
+
+impl Size {
+    /// Returns the assembly suffix for the size, without the leading dot.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Size::Zero   => "",
+            Size::Byte   => "b",
+            Size::Word   => "w",
+            Size::Long   => "l",
+            Size::Single => "s",
+            Size::Double => "d",
+        }
+    }
+
+    /// Returns the size in bytes.
+    pub fn bytes(self) -> u8 {
+        match self {
+            Size::Zero   => 0,
+            Size::Byte   => 1,
+            Size::Word   => 2,
+            Size::Long   => 4,
+            Size::Single => 4,
+            Size::Double => 8,
+        }
+    }
+}
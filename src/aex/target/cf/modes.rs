@@ -0,0 +1,629 @@
+// ColdFire Addressing Modes
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt::{self, Formatter};
+use std::io;
+use num::BigInt;
+
+use aex::ast::{Expr, Float, Int, Node, Reg};
+use aex::fmt::{Code, Style, ToCode};
+use aex::io::{DecodeRead, EncodeWrite};
+use aex::util::{BitPos, invalid};
+
+use super::{AddrReg, DataReg, EncodeError, Scale, Size};
+use super::{check_bits, check_int, float_value, int_spec, int_value};
+
+/// ColdFire addressing modes bitfield.
+pub type Modes = u16;
+
+pub const DR: Modes = 1 <<  0; // 0.*: data reg direct
+pub const AR: Modes = 1 <<  1; // 1.*: addr reg direct
+pub const AI: Modes = 1 <<  2; // 2.*: addr reg indirect
+pub const AP: Modes = 1 <<  3; // 3.*: addr reg indirect, auto-increment (plus)
+pub const AM: Modes = 1 <<  4; // 4.*: addr reg indirect, auto-decrement (minus)
+pub const AD: Modes = 1 <<  5; // 5.*: addr reg indirect, displaced
+pub const AX: Modes = 1 <<  6; // 6.*: addr reg indirect, indexed, displaced
+pub const MS: Modes = 1 <<  7; // 7.0: absolute short
+pub const ML: Modes = 1 <<  8; // 7.1: absolute long
+pub const PD: Modes = 1 <<  9; // 7.2: pc-relative, displaced
+pub const PX: Modes = 1 << 10; // 7.3: pc-relative, indexed, displaced
+pub const IM: Modes = 1 << 11; // 7.4: immediate
+
+/// A ColdFire operand location specified by addressing mode.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Mode<'a> {
+    /// Data register direct mode.
+    Data(DataReg),
+
+    /// Address register direct mode.
+    Addr(AddrReg),
+
+    /// Address register indirect mode.
+    AddrInd(AddrReg),
+
+    /// Address register indirect with post-increment mode.
+    AddrPostInc(AddrReg),
+
+    /// Address register indirect with pre-decrement mode.
+    AddrPreDec(AddrReg),
+
+    /// Address register indirect with displacement mode.
+    AddrDisp(AddrReg, Expr<'a>),
+
+    /// Address register indirect with scaled index and displacement mode.
+    AddrIdxDisp(AddrReg, Index, Expr<'a>),
+
+    /// Program counter indirect with displacement mode.
+    PcDisp(Expr<'a>),
+
+    /// Program counter indirect with scaled index and displacement mode.
+    PcIdxDisp(Index, Expr<'a>),
+
+    /// Absolute short mode (signed 16-bit address).
+    Abs16(Expr<'a>),
+
+    /// Absolute long mode (unsigned 32-bit address).
+    Abs32(Expr<'a>),
+
+    /// Immediate mode.
+    Imm(Expr<'a>),
+}
+
+/// A Coldfire scaled index.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Index {
+    /// The index register.
+    pub reg: IndexReg,
+
+    /// The scaling factor.
+    pub scale: Scale,
+}
+
+/// ColdFire index registers.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum IndexReg {
+    /// Data register used as an index register.
+    Data(DataReg),
+
+    /// Address register used as an index register.
+    Addr(AddrReg),
+}
+
+impl<'a> Mode<'a> {
+    /// Decodes a `Mode` from the 6-bit mode/register field at position `pos`
+    /// of the given instruction word.  Extension words, if any, are read from
+    /// the given reader and become pending.
+    ///
+    /// Only the addressing modes in `modes` are accepted.  Other modes, and
+    /// reserved encodings, yield an `InvalidData` error.  The operation size
+    /// determines the length of immediate data.
+    ///
+    pub fn decode<R: DecodeRead>(word:  u16,
+                                 pos:   BitPos,
+                                 modes: Modes,
+                                 size:  Size,
+                                 r:     &mut R)
+                                -> io::Result<Self> {
+        let reg  = ((word >>  pos     ) & 7) as u8;
+        let mode = ((word >> (pos + 3)) & 7) as u8;
+
+        Self::decode_fields(mode, reg, modes, size, r)
+    }
+
+    /// Decodes a `Mode` from separate 3-bit mode and register fields.
+    ///
+    /// This is useful for instructions like `move`, whose destination field
+    /// has the mode and register subfields swapped.
+    ///
+    pub fn decode_fields<R: DecodeRead>(mode:  u8,
+                                        reg:   u8,
+                                        modes: Modes,
+                                        size:  Size,
+                                        r:     &mut R)
+                                       -> io::Result<Self> {
+        let kind = match (mode, reg) {
+            (0..=6, _) => 1 << mode,
+            (7, 0..=4) => 1 << (7 + reg),
+            _          => return invalid("reserved addressing mode"),
+        };
+
+        if kind & modes == 0 {
+            return invalid("addressing mode not allowed");
+        }
+
+        Ok(match kind {
+            DR => Mode::Data        (DataReg::with_num(reg)),
+            AR => Mode::Addr        (AddrReg::with_num(reg)),
+            AI => Mode::AddrInd     (AddrReg::with_num(reg)),
+            AP => Mode::AddrPostInc (AddrReg::with_num(reg)),
+            AM => Mode::AddrPreDec  (AddrReg::with_num(reg)),
+            AD => {
+                let disp = r.read_i16()?;
+                Mode::AddrDisp(AddrReg::with_num(reg), int(disp))
+            },
+            AX => {
+                let (index, disp) = decode_index(r)?;
+                Mode::AddrIdxDisp(AddrReg::with_num(reg), index, int(disp))
+            },
+            MS => Mode::Abs16(int(r.read_i16()?)),
+            ML => Mode::Abs32(int(r.read_u32()?)),
+            PD => Mode::PcDisp(int(r.read_i16()?)),
+            PX => {
+                let (index, disp) = decode_index(r)?;
+                Mode::PcIdxDisp(index, int(disp))
+            },
+            _ => Mode::Imm(decode_imm(size, r)?),
+        })
+    }
+
+    /// Encodes the mode as separate 3-bit mode and register fields, returned
+    /// in that order.  Extension words, if any, are written to the given
+    /// writer.  The operation size determines the length of immediate data.
+    pub fn encode_fields<W: EncodeWrite>(&self, size: Size, w: &mut W)
+                                        -> Result<(u8, u8), EncodeError> {
+        Ok(match *self {
+            Mode::Data        (r) => (0, r.num()),
+            Mode::Addr        (r) => (1, r.num()),
+            Mode::AddrInd     (r) => (2, r.num()),
+            Mode::AddrPostInc (r) => (3, r.num()),
+            Mode::AddrPreDec  (r) => (4, r.num()),
+            Mode::AddrDisp    (r, ref d) => {
+                w.write_u16(encode_disp16(d)?)?;
+                (5, r.num())
+            },
+            Mode::AddrIdxDisp (r, x, ref d) => {
+                w.write_u16(x.encode(encode_disp8(d)?))?;
+                (6, r.num())
+            },
+            Mode::Abs16 (ref e) => {
+                let v = int_value("absolute address", e)?;
+                w.write_u16(check_int("absolute short address", int_spec(16, true), v)? as u16)?;
+                (7, 0)
+            },
+            Mode::Abs32 (ref e) => {
+                w.write_u32(check_bits("absolute address", 32, e)? as u32)?;
+                (7, 1)
+            },
+            Mode::PcDisp (ref d) => {
+                w.write_u16(encode_disp16(d)?)?;
+                (7, 2)
+            },
+            Mode::PcIdxDisp (x, ref d) => {
+                w.write_u16(x.encode(encode_disp8(d)?))?;
+                (7, 3)
+            },
+            Mode::Imm (ref e) => {
+                encode_imm(size, e, w)?;
+                (7, 4)
+            },
+        })
+    }
+
+    /// Returns the addressing mode bit for this mode.
+    pub fn kind(&self) -> Modes {
+        match *self {
+            Mode::Data        (..) => DR,
+            Mode::Addr        (..) => AR,
+            Mode::AddrInd     (..) => AI,
+            Mode::AddrPostInc (..) => AP,
+            Mode::AddrPreDec  (..) => AM,
+            Mode::AddrDisp    (..) => AD,
+            Mode::AddrIdxDisp (..) => AX,
+            Mode::Abs16       (..) => MS,
+            Mode::Abs32       (..) => ML,
+            Mode::PcDisp      (..) => PD,
+            Mode::PcIdxDisp   (..) => PX,
+            Mode::Imm         (..) => IM,
+        }
+    }
+}
+
+impl Index {
+    /// Decodes an `Index` from the given brief extension word.
+    ///
+    /// ColdFire supports only the brief extension word format, and only
+    /// longword-sized index registers.
+    ///
+    pub fn decode(ext: u16) -> io::Result<Self> {
+        if ext & 0x0100 != 0 { return invalid("full extension word format"); }
+        if ext & 0x0800 == 0 { return invalid("word-sized index register"); }
+
+        let num = ((ext >> 12) & 7) as u8;
+        let reg = match ext >> 15 {
+            0 => IndexReg::Data(DataReg::with_num(num)),
+            _ => IndexReg::Addr(AddrReg::with_num(num)),
+        };
+
+        Ok(Index { reg, scale: Scale::decode(ext, 9) })
+    }
+
+    /// Encodes the `Index` as a brief extension word with the given 8-bit
+    /// displacement.
+    pub fn encode(self, disp: u8) -> u16 {
+        let reg = match self.reg {
+            IndexReg::Data(r) => (r.num() as u16) << 12,
+            IndexReg::Addr(r) => (r.num() as u16) << 12 | 0x8000,
+        };
+
+        reg | 0x0800 | self.scale.encode(9) | disp as u16
+    }
+}
+
+impl<A> ToCode<A> for IndexReg {
+    type Output = Reg<'static, A>;
+
+    /// Converts to a code-formattable value with the given annotation.
+    #[inline]
+    fn to_code(&self, ann: A) -> Self::Output {
+        match *self {
+            IndexReg::Data(r) => r.to_code(ann),
+            IndexReg::Addr(r) => r.to_code(ann),
+        }
+    }
+}
+
+impl<'a> Node for Mode<'a> {
+    /// Annotation type.
+    type Ann = ();
+
+    /// Gets the annotation for this node.
+    fn ann(&self) -> &() { &() }
+}
+
+impl<'a> Code for Mode<'a> {
+    /// Formats the value as code, using the given formatter and style.
+    fn fmt<S: Style<()> + ?Sized>
+          (&self, f: &mut Formatter, s: &S) -> fmt::Result {
+        match *self {
+            Mode::Data        (r)           => s.write_reg        (f, &r.to_code(())),
+            Mode::Addr        (r)           => s.write_reg        (f, &r.to_code(())),
+            Mode::AddrInd     (r)           => s.write_ind        (f, &r.to_code(())),
+            Mode::AddrPostInc (r)           => s.write_ind_postinc(f, &r.to_code(())),
+            Mode::AddrPreDec  (r)           => s.write_ind_predec (f, &r.to_code(())),
+            Mode::AddrDisp    (r, ref d)    => s.write_base_disp  (f, &r.to_code(()), d),
+            Mode::AddrIdxDisp (r, x, ref d) => s.write_base_disp_idx(
+                f, &r.to_code(()), d, &x.reg.to_code(()), x.scale.size()
+            ),
+            Mode::PcDisp      (ref d)       => s.write_base_disp  (f, &PC, d),
+            Mode::PcIdxDisp   (x, ref d)    => s.write_base_disp_idx(
+                f, &PC, d, &x.reg.to_code(()), x.scale.size()
+            ),
+            Mode::Abs16       (ref e)       => s.write_abs(f, e, false),
+            Mode::Abs32       (ref e)       => s.write_abs(f, e, true),
+            Mode::Imm         (ref e)       => s.write_imm(f, e),
+        }
+    }
+}
+
+/// Program counter, as used in PC-relative modes.
+const PC: Reg<'static> = Reg { name: "pc", ann: () };
+
+fn decode_index<R: DecodeRead>(r: &mut R) -> io::Result<(Index, i8)> {
+    let ext = r.read_u16()?;
+    Ok((Index::decode(ext)?, ext as u8 as i8))
+}
+
+fn decode_imm<'a, R: DecodeRead>(size: Size, r: &mut R) -> io::Result<Expr<'a>> {
+    match size {
+        Size::Byte   => {
+            let ext = r.read_u16()?;
+            if ext & 0xFF00 != 0 { return invalid("nonzero upper byte in byte immediate"); }
+            Ok(int(ext as u8 as i8))
+        },
+        Size::Word   => Ok(int(r.read_i16()?)),
+        Size::Long   => Ok(int(r.read_i32()?)),
+        Size::Single => Ok(float(r.read_f32()?)),
+        Size::Double => Ok(float(r.read_f64()?)),
+        _            => invalid("immediate size not supported"),
+    }
+}
+
+fn encode_disp16(disp: &Expr) -> Result<u16, EncodeError> {
+    let v = int_value("displacement", disp)?;
+    Ok(check_int("displacement", int_spec(16, true), v)? as u16)
+}
+
+fn encode_disp8(disp: &Expr) -> Result<u8, EncodeError> {
+    let v = int_value("displacement", disp)?;
+    Ok(check_int("displacement", int_spec(8, true), v)? as u8)
+}
+
+fn encode_imm<W: EncodeWrite>(size: Size, e: &Expr, w: &mut W) -> Result<(), EncodeError> {
+    match size {
+        Size::Byte   => w.write_u16(check_bits("immediate", 8,  e)? as u16)?,
+        Size::Word   => w.write_u16(check_bits("immediate", 16, e)? as u16)?,
+        Size::Long   => w.write_u32(check_bits("immediate", 32, e)? as u32)?,
+        Size::Single => w.write_f32(float_value("immediate", e)? as f32)?,
+        Size::Double => w.write_f64(float_value("immediate", e)?)?,
+        _            => return Err(EncodeError::Unsupported("immediate size")),
+    }
+    Ok(())
+}
+
+#[inline]
+fn float<'a, V: Into<f64>>(v: V) -> Expr<'a> {
+    Expr::Float(Float::new(v))
+}
+
+#[inline]
+fn int<'a, V: Into<BigInt>>(v: V) -> Expr<'a> {
+    Expr::Int(Int::new(v))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::io::ErrorKind::*;
+    use aex::fmt::{AttStyle, MitStyle, MotorolaStyle, ToStyled};
+    use aex::io::{DecodeReader, EncodeWriter, RewindReader};
+    use aex::util::BE;
+    use super::*;
+    use super::super::*;
+
+    const ALL: Modes = 0x0FFF;
+
+    #[test]
+    fn decode_data() {
+        let mut r = reader(&[]);
+        let m = Mode::decode(0b000_011_000_000, 6, ALL, Size::Long, &mut r).unwrap();
+        assert_eq!(m, Mode::Data(D3));
+    }
+
+    #[test]
+    fn decode_addr() {
+        let mut r = reader(&[]);
+        let m = Mode::decode(0b001_110_000_000, 6, ALL, Size::Long, &mut r).unwrap();
+        assert_eq!(m, Mode::Addr(FP));
+    }
+
+    #[test]
+    fn decode_addr_ind() {
+        let mut r = reader(&[]);
+        let m = Mode::decode(0b010_110_000_000, 6, ALL, Size::Long, &mut r).unwrap();
+        assert_eq!(m, Mode::AddrInd(FP));
+    }
+
+    #[test]
+    fn decode_addr_post_inc() {
+        let mut r = reader(&[]);
+        let m = Mode::decode(0b011_110_000_000, 6, ALL, Size::Long, &mut r).unwrap();
+        assert_eq!(m, Mode::AddrPostInc(FP));
+    }
+
+    #[test]
+    fn decode_addr_pre_dec() {
+        let mut r = reader(&[]);
+        let m = Mode::decode(0b100_110_000_000, 6, ALL, Size::Long, &mut r).unwrap();
+        assert_eq!(m, Mode::AddrPreDec(FP));
+    }
+
+    #[test]
+    fn decode_addr_disp() {
+        let mut r = reader(&[0xFF, 0xFE]);
+        let m = Mode::decode(0b101_110_000_000, 6, ALL, Size::Long, &mut r).unwrap();
+        assert_eq!(m, Mode::AddrDisp(FP, int(-2)));
+        assert_eq!(r.pending_len(), 2);
+    }
+
+    #[test]
+    fn decode_addr_idx_disp() {
+        let mut r = reader(&[0b0011_1100, 0x12]);
+        let m = Mode::decode(0b110_110_000_000, 6, ALL, Size::Long, &mut r).unwrap();
+        assert_eq!(m, Mode::AddrIdxDisp(
+            FP,
+            Index { reg: IndexReg::Data(D3), scale: Scale::Long },
+            int(0x12)
+        ));
+    }
+
+    #[test]
+    fn decode_abs16() {
+        let mut r = reader(&[0x80, 0x00]);
+        let m = Mode::decode(0b111_000, 0, ALL, Size::Long, &mut r).unwrap();
+        assert_eq!(m, Mode::Abs16(int(-0x8000)));
+    }
+
+    #[test]
+    fn decode_abs32() {
+        let mut r = reader(&[0x80, 0x00, 0x12, 0x34]);
+        let m = Mode::decode(0b111_001, 0, ALL, Size::Long, &mut r).unwrap();
+        assert_eq!(m, Mode::Abs32(int(0x80001234u32)));
+    }
+
+    #[test]
+    fn decode_pc_disp() {
+        let mut r = reader(&[0x01, 0x00]);
+        let m = Mode::decode(0b111_010, 0, ALL, Size::Long, &mut r).unwrap();
+        assert_eq!(m, Mode::PcDisp(int(0x100)));
+    }
+
+    #[test]
+    fn decode_pc_idx_disp() {
+        let mut r = reader(&[0b1101_1010, 0xFC]);
+        let m = Mode::decode(0b111_011, 0, ALL, Size::Long, &mut r).unwrap();
+        assert_eq!(m, Mode::PcIdxDisp(
+            Index { reg: IndexReg::Addr(A5), scale: Scale::Word },
+            int(-4)
+        ));
+    }
+
+    #[test]
+    fn decode_imm_byte() {
+        let mut r = reader(&[0x00, 0xFF]);
+        let m = Mode::decode(0b111_100, 0, ALL, Size::Byte, &mut r).unwrap();
+        assert_eq!(m, Mode::Imm(int(-1)));
+    }
+
+    #[test]
+    fn decode_imm_word() {
+        let mut r = reader(&[0x12, 0x34]);
+        let m = Mode::decode(0b111_100, 0, ALL, Size::Word, &mut r).unwrap();
+        assert_eq!(m, Mode::Imm(int(0x1234)));
+    }
+
+    #[test]
+    fn decode_imm_long() {
+        let mut r = reader(&[0x12, 0x34, 0x56, 0x78]);
+        let m = Mode::decode(0b111_100, 0, ALL, Size::Long, &mut r).unwrap();
+        assert_eq!(m, Mode::Imm(int(0x12345678)));
+        assert_eq!(r.pending_len(), 4);
+    }
+
+    #[test]
+    fn decode_imm_single() {
+        let mut r = reader(&[0x3F, 0xC0, 0x00, 0x00]);
+        let m = Mode::decode(0b111_100, 0, ALL, Size::Single, &mut r).unwrap();
+        assert_eq!(m, Mode::Imm(float(1.5)));
+        assert_eq!(r.pending_len(), 4);
+    }
+
+    #[test]
+    fn decode_imm_double() {
+        let mut r = reader(&[0xC0, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        let m = Mode::decode(0b111_100, 0, ALL, Size::Double, &mut r).unwrap();
+        assert_eq!(m, Mode::Imm(float(-2.5)));
+        assert_eq!(r.pending_len(), 8);
+    }
+
+    #[test]
+    fn decode_reserved() {
+        let mut r = reader(&[0x00, 0x00]);
+        let e = Mode::decode(0b111_101, 0, ALL, Size::Long, &mut r).unwrap_err();
+        assert_eq!(e.kind(), InvalidData);
+    }
+
+    #[test]
+    fn decode_not_allowed() {
+        let mut r = reader(&[0x00, 0x00]);
+        let e = Mode::decode(0b101_000, 0, DR | AR, Size::Long, &mut r).unwrap_err();
+        assert_eq!(e.kind(), InvalidData);
+        assert_eq!(r.pending_len(), 0);
+    }
+
+    #[test]
+    fn decode_full_ext_word() {
+        let mut r = reader(&[0x09, 0x00]);
+        let e = Mode::decode(0b110_000, 0, ALL, Size::Long, &mut r).unwrap_err();
+        assert_eq!(e.kind(), InvalidData);
+    }
+
+    #[test]
+    fn decode_word_index() {
+        let mut r = reader(&[0x00, 0x00]);
+        let e = Mode::decode(0b110_000, 0, ALL, Size::Long, &mut r).unwrap_err();
+        assert_eq!(e.kind(), InvalidData);
+    }
+
+    #[test]
+    fn decode_eof() {
+        let mut r = reader(&[0x00]);
+        let e = Mode::decode(0b101_000, 0, ALL, Size::Long, &mut r).unwrap_err();
+        assert_eq!(e.kind(), UnexpectedEof);
+    }
+
+    #[test]
+    fn encode_fields() {
+        let x = Index { reg: IndexReg::Data(D3), scale: Scale::Long };
+        assert_eq!(encode(Mode::Data(D3),                       Size::Long), ((0, 3), vec![]));
+        assert_eq!(encode(Mode::AddrPreDec(SP),                 Size::Long), ((4, 7), vec![]));
+        assert_eq!(encode(Mode::AddrDisp(FP, int(-2)),          Size::Long), ((5, 6), vec![0xFF, 0xFE]));
+        assert_eq!(encode(Mode::AddrIdxDisp(FP, x, int(0x12)),  Size::Long), ((6, 6), vec![0x3C, 0x12]));
+        assert_eq!(encode(Mode::Abs16(int(-0x8000)),            Size::Long), ((7, 0), vec![0x80, 0x00]));
+        assert_eq!(encode(Mode::Abs32(int(0x80001234u32)),      Size::Long), ((7, 1), vec![0x80, 0x00, 0x12, 0x34]));
+        assert_eq!(encode(Mode::PcDisp(int(0x100)),             Size::Long), ((7, 2), vec![0x01, 0x00]));
+        assert_eq!(encode(Mode::Imm(int(-1)),                   Size::Byte), ((7, 4), vec![0x00, 0xFF]));
+        assert_eq!(encode(Mode::Imm(float(-2.5)),               Size::Double),
+                   ((7, 4), vec![0xC0, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]));
+    }
+
+    #[test]
+    fn encode_index() {
+        let x = Index { reg: IndexReg::Addr(A5), scale: Scale::Word };
+        assert_eq!(x.encode(0xFC), 0b1101_1010_1111_1100);
+        assert_eq!(Index::decode(x.encode(0)).unwrap(), x);
+    }
+
+    #[test]
+    fn encode_out_of_range() {
+        let mut w = EncodeWriter::new(vec![], BE);
+        let e = Mode::Abs16(int(0x8000)).encode_fields(Size::Long, &mut w).unwrap_err();
+        assert_eq!(e.to_string(), "absolute short address 32768 out of range (-32768 to 32767)");
+    }
+
+    #[test]
+    fn kind() {
+        assert_eq!(Mode::Data(D0).kind(),       DR);
+        assert_eq!(Mode::Imm(int(0)).kind(),    IM);
+        assert_eq!(Mode::PcDisp(int(0)).kind(), PD);
+    }
+
+    #[test]
+    fn fmt_att() {
+        let s = AttStyle::default();
+        let x = Index { reg: IndexReg::Addr(A5), scale: Scale::Long };
+        assert_eq!(Mode::Data(D3)                          .styled(&s).to_string(), "%d3");
+        assert_eq!(Mode::AddrInd(FP)                       .styled(&s).to_string(), "(%fp)");
+        assert_eq!(Mode::AddrPostInc(A0)                   .styled(&s).to_string(), "(%a0)+");
+        assert_eq!(Mode::AddrPreDec(SP)                    .styled(&s).to_string(), "-(%sp)");
+        assert_eq!(Mode::AddrDisp(A1, int(-2))             .styled(&s).to_string(), "-2(%a1)");
+        assert_eq!(Mode::AddrIdxDisp(A1, x, int(8))        .styled(&s).to_string(), "8(%a1,%a5*4)");
+        assert_eq!(Mode::PcDisp(int(16))                   .styled(&s).to_string(), "16(%pc)");
+        assert_eq!(Mode::Abs16(int(0x100))                 .styled(&s).to_string(), "256.w");
+        assert_eq!(Mode::Imm(int(42))                      .styled(&s).to_string(), "#42");
+    }
+
+    #[test]
+    fn fmt_mit() {
+        let s = MitStyle::default();
+        let x = Index { reg: IndexReg::Data(D2), scale: Scale::Word };
+        assert_eq!(Mode::Addr(A2)                          .styled(&s).to_string(), "%a2");
+        assert_eq!(Mode::AddrInd(FP)                       .styled(&s).to_string(), "%fp@");
+        assert_eq!(Mode::AddrPostInc(A0)                   .styled(&s).to_string(), "%a0@+");
+        assert_eq!(Mode::AddrPreDec(SP)                    .styled(&s).to_string(), "%sp@-");
+        assert_eq!(Mode::AddrDisp(A1, int(-2))             .styled(&s).to_string(), "%a1@(-2)");
+        assert_eq!(Mode::PcIdxDisp(x, int(8))              .styled(&s).to_string(), "%pc@(8,%d2:2)");
+        assert_eq!(Mode::Abs32(int(0x100))                 .styled(&s).to_string(), "256:l");
+        assert_eq!(Mode::Imm(int(42))                      .styled(&s).to_string(), "#42");
+    }
+
+    #[test]
+    fn fmt_motorola() {
+        let s = MotorolaStyle::default();
+        let x = Index { reg: IndexReg::Data(D2), scale: Scale::Word };
+        assert_eq!(Mode::Addr(A2)                          .styled(&s).to_string(), "a2");
+        assert_eq!(Mode::AddrInd(FP)                       .styled(&s).to_string(), "(fp)");
+        assert_eq!(Mode::AddrPostInc(A0)                   .styled(&s).to_string(), "(a0)+");
+        assert_eq!(Mode::AddrPreDec(SP)                    .styled(&s).to_string(), "-(sp)");
+        assert_eq!(Mode::AddrDisp(A1, int(-2))             .styled(&s).to_string(), "(-2,a1)");
+        assert_eq!(Mode::PcIdxDisp(x, int(8))              .styled(&s).to_string(), "(8,pc,d2.l*2)");
+        assert_eq!(Mode::Abs16(int(0x100))                 .styled(&s).to_string(), "(256).w");
+        assert_eq!(Mode::Imm(int(42))                      .styled(&s).to_string(), "#42");
+    }
+
+    fn encode(mode: Mode, size: Size) -> ((u8, u8), Vec<u8>) {
+        let mut w = EncodeWriter::new(vec![], BE);
+        let f = mode.encode_fields(size, &mut w).unwrap();
+        (f, w.into_inner())
+    }
+
+    fn reader(bytes: &[u8]) -> DecodeReader<RewindReader<Cursor<Vec<u8>>>> {
+        let src = Cursor::new(bytes.to_vec());
+        let src = RewindReader::new(src);
+        DecodeReader::new(src, BE, 0)
+    }
+}
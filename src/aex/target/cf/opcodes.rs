@@ -0,0 +1,943 @@
+// ColdFire Opcodes
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{Mnemonic, OperandForms, Size};
+use super::{Acc0, Macsr, Mask, AccExt01, AccExt23};
+use super::{DR, AR, AI, AP, AM, AD, AX, MS, ML, PD, PX, IM};
+
+use super::Mnemonic::*;
+use super::OperandForms::*;
+use super::OperandForm::*;
+use super::Size::*;
+
+/// An entry in the opcodes table.
+///
+/// Describes how to assemble or disassemble an instruction, along with the
+/// supported argument types and architectures.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Opcode {
+    /// Instruction name.
+    pub name: Mnemonic,                     // 1 byte
+
+    /// Operation size.
+    pub size: Size,                         // 1 byte
+
+    /// Opcode bits.
+    pub bits: (u16, u16),                   // 4 bytes
+
+    /// Mask of significant opcode bits.
+    pub mask: (u16, u16),                   // 4 bytes
+
+    /// Operand form combination.
+    pub args: OperandForms,                 // 14 bytes
+
+    /// Flags: architectures, etc.
+    pub flags: Flags,                       // 2 bytes
+}
+
+impl Opcode {
+    /// Returns whether the opcode exists in any of the given ISA revisions
+    /// and extensions.
+    #[inline]
+    pub fn is_supported(&self, isa: Flags) -> bool {
+        self.flags & isa & !EXT_WORD != 0
+    }
+}
+
+/// Opcode flags.
+pub type Flags = u16;
+
+pub const EXT_WORD:  Flags = 1 << 0; // Has extension word
+pub const CF_A:      Flags = 1 << 1; // Appears in ColdFire ISA_A
+pub const CF_A2:     Flags = 1 << 2; // Appears in ColdFire ISA_A+
+pub const CF_B:      Flags = 1 << 3; // Appears in ColdFire ISA_B
+pub const CF_C:      Flags = 1 << 4; // Appears in ColdFire ISA_C
+pub const CF_FPU:    Flags = 1 << 5; // Appears in ColdFire FPU
+pub const CF_MAC:    Flags = 1 << 6; // Appears in ColdFire MAC
+pub const CF_EMAC:   Flags = 1 << 7; // Appears in ColdFire EMAC
+pub const CF_EMAC_B: Flags = 1 << 8; // Appears in ColdFire EMAC_B
+
+pub const CF_A_UP:   Flags = CF_A | CF_A2 | CF_B | CF_C;
+pub const CF_A2_UP:  Flags =        CF_A2 | CF_B | CF_C;
+pub const CF_B_UP:   Flags =                CF_B | CF_C;
+pub const CF_ALL:    Flags = CF_A_UP | CF_FPU | CF_MAC | CF_EMAC | CF_EMAC_B;
+
+macro_rules! opcodes {
+    {
+        $(
+            $name:ident $size:tt ( $($bits:expr),+ ) ( $($mask:expr),+ )
+                [ $( $($arg:tt):+ ),* ] $flags:expr ;
+        )*
+    } =>
+    {
+        pub static OPCODES: &'static [Opcode] = &[
+            $(
+                Opcode {
+                    name:  $name,
+                    size:  size!($size),
+                    bits:  words!($($bits),+),
+                    mask:  words!($($mask),+),
+                    args:  args!($( $($arg):+ ),*),
+                    flags: $flags | ext!($($bits),+),
+                },
+            )*
+        ];
+    };
+}
+
+macro_rules! size {
+    { - } => { Zero };
+    { S } => { Byte };
+    { B } => { Byte };
+    { W } => { Word };
+    { L } => { Long };
+    { F } => { Single };
+    { D } => { Double };
+}
+
+macro_rules! words {
+    { $a:expr          } => { ($a,  0) };
+    { $a:expr, $b:expr } => { ($a, $b) };
+}
+
+macro_rules! ext {
+    { $a:expr          } => { 0        };
+    { $a:expr, $b:expr } => { EXT_WORD };
+}
+
+macro_rules! args {
+    { } => { Nullary };
+
+    { ($form:ident) }
+        => { $form };
+
+    { $($a:tt):+ }
+        => { Unary([ arg!($($a):+) ]) };
+
+    { $($a:tt):+, $($b:tt):+ }
+        => { Binary([ arg!($($a):+), arg!($($b):+) ]) };
+
+    { $($a:tt):+, $($b:tt):+, $($c:tt):+ }
+        => { Ternary([ arg!($($a):+), arg!($($b):+), arg!($($c):+) ]) };
+
+    // ...
+}
+
+macro_rules! arg {
+    // Addressing mode combinations
+    { daipmdxnfDXI : $pos:expr } => { Mode(DR|AR|AI|AP|AM|AD|AX|MS|ML|PD|PX|IM, $pos) };
+    { d_ipmdxnfDXI : $pos:expr } => { Mode(DR|   AI|AP|AM|AD|AX|MS|ML|PD|PX|IM, $pos) };
+    { daipmdxnf___ : $pos:expr } => { Mode(DR|AR|AI|AP|AM|AD|AX|MS|ML,          $pos) };
+    { d_ipmdxnf___ : $pos:expr } => { Mode(DR|   AI|AP|AM|AD|AX|MS|ML,          $pos) };
+    { __ipmdxnfDX_ : $pos:expr } => { Mode(      AI|AP|AM|AD|AX|MS|ML|PD|PX,    $pos) };
+    { __ipmdxnf___ : $pos:expr } => { Mode(      AI|AP|AM|AD|AX|MS|ML,          $pos) };
+    { __i__dxnfDX_ : $pos:expr } => { Mode(      AI|      AD|AX|MS|ML|PD|PX,    $pos) };
+    { d_ipmd______ : $pos:expr } => { Mode(DR|   AI|AP|AM|AD,                   $pos) };
+    { __ipmd______ : $pos:expr } => { Mode(      AI|AP|AM|AD,                   $pos) };
+    { __i__d______ : $pos:expr } => { Mode(      AI|      AD,                   $pos) };
+    { d__________I : $pos:expr } => { Mode(DR|                               IM, $pos) };
+    { da_________I : $pos:expr } => { Mode(DR|AR|                           IM, $pos) };
+    { d_ipmd___D_I : $pos:expr } => { Mode(DR|   AI|AP|AM|AD|         PD|   IM, $pos) };
+    { __ipmd___D_I : $pos:expr } => { Mode(      AI|AP|AM|AD|         PD|   IM, $pos) };
+    { dst          : $pos:expr } => { DstMode($pos) };
+
+    // Other operand kinds
+    { data  : $pos:expr      } => { DataReg($pos) };
+    { dup   : $a:tt : $b:tt  } => { DataRegDup($a, $b) };
+    { pair  : $a:tt : $b:tt  } => { DataRegPair($a, $b) };
+    { addr  : $pos:expr      } => { AddrReg($pos) };
+    { ind   : $pos:expr      } => { AddrRegInd($pos) };
+    { reg   : $pos:expr      } => { NormalReg($pos) };
+    { rc    : $pos:expr      } => { CtlReg($pos) };
+    { ccr                    } => { Ccr };
+    { sr                     } => { Sr };
+    { list                   } => { RegList };
+    { cond  : $pos:expr      } => { Cond($pos) };
+    { bcc   : $pos:expr      } => { BranchCond($pos) };
+    { cache : $pos:expr      } => { CacheSel($pos) };
+    { imm                    } => { Immediate };
+    { immw                   } => { ImmWord };
+    { q3    : $pos:expr      } => { Quick3($pos) };
+    { q3n   : $pos:expr      } => { Quick3Neg($pos) };
+    { q4    : $pos:expr      } => { Quick4($pos) };
+    { q8    : $pos:expr      } => { Quick8($pos) };
+    { br8   : $pos:expr      } => { Branch8($pos) };
+    { br16                   } => { Branch16 };
+    { br32                   } => { Branch32 };
+    { fp    : $pos:expr      } => { FpReg($pos) };
+    { fpcr                   } => { Fpcr };
+    { fpsr                   } => { Fpsr };
+    { fpiar                  } => { Fpiar };
+    { fplist                 } => { FpRegList };
+    { fpcond : $pos:expr     } => { FpCond($pos) };
+    { mac   : $a:tt : $b:tt : $c:tt } => { MacSrc($a, $b, $c) };
+    { sf    : $pos:expr      } => { MacShift($pos) };
+    { acc   : $pos:expr      } => { Acc($pos) };
+    { acc0                   } => { MacReg(Acc0) };
+    { macsr                  } => { MacReg(Macsr) };
+    { mask                   } => { MacReg(Mask) };
+    { accext01               } => { MacReg(AccExt01) };
+    { accext23               } => { MacReg(AccExt23) };
+}
+
+opcodes! {
+//  NAME     S  WORDS             MASKS             OPERANDS                          FLAGS
+//  -------  -  ----------------  ----------------  --------------------------------  -----
+    Adda     L  (0xD1C0)          (0xF1C0)          [daipmdxnfDXI:0, addr:9]          CF_A_UP;
+
+    Addi     L  (0x0680)          (0xFFF8)          [imm, data:0]                     CF_A_UP;
+
+    Addq     L  (0x5080)          (0xF1C0)          [q3:9, daipmdxnf___:0]            CF_A_UP;
+
+    Add      L  (0xD080)          (0xF1C0)          [daipmdxnfDXI:0, data:9]          CF_A_UP;
+    Add      L  (0xD180)          (0xF1C0)          [data:9, __ipmdxnf___:0]          CF_A_UP;
+
+    Addx     L  (0xD180)          (0xF1F8)          [data:0, data:9]                  CF_A_UP;
+
+    Andi     L  (0x0280)          (0xFFF8)          [imm, data:0]                     CF_A_UP;
+
+    And      L  (0xC080)          (0xF1C0)          [d_ipmdxnfDXI:0, data:9]          CF_A_UP;
+    And      L  (0xC180)          (0xF1C0)          [data:9, __ipmdxnf___:0]          CF_A_UP;
+
+    Asl      L  (0xE180)          (0xF1F8)          [q3:9, data:0]                    CF_A_UP;
+    Asl      L  (0xE1A0)          (0xF1F8)          [data:9, data:0]                  CF_A_UP;
+
+    Asr      L  (0xE080)          (0xF1F8)          [q3:9, data:0]                    CF_A_UP;
+    Asr      L  (0xE0A0)          (0xF1F8)          [data:9, data:0]                  CF_A_UP;
+
+    Bitrev   L  (0x00C0)          (0xFFF8)          [data:0]                          CF_A2 | CF_C;
+
+    Bcc      S  (0x6000)          (0xF000)          [bcc:8, br8:0]                    CF_A_UP;
+    Bcc      W  (0x6000)          (0xF0FF)          [bcc:8, br16]                     CF_A_UP;
+    Bcc      L  (0x60FF)          (0xF0FF)          [bcc:8, br32]                     CF_B_UP;
+
+    Bchg     L  (0x0140)          (0xF1F8)          [data:9, data:0]                  CF_A_UP;
+    Bchg     B  (0x0140)          (0xF1C0)          [data:9, __ipmdxnf___:0]          CF_A_UP;
+    Bchg     L  (0x0840, 0x0000)  (0xFFF8, 0xFF00)  [q8:16, data:0]                   CF_A_UP;
+    Bchg     B  (0x0840, 0x0000)  (0xFFC0, 0xFF00)  [q8:16, __ipmd______:0]           CF_A_UP;
+
+    Bclr     L  (0x0180)          (0xF1F8)          [data:9, data:0]                  CF_A_UP;
+    Bclr     B  (0x0180)          (0xF1C0)          [data:9, __ipmdxnf___:0]          CF_A_UP;
+    Bclr     L  (0x0880, 0x0000)  (0xFFF8, 0xFF00)  [q8:16, data:0]                   CF_A_UP;
+    Bclr     B  (0x0880, 0x0000)  (0xFFC0, 0xFF00)  [q8:16, __ipmd______:0]           CF_A_UP;
+
+    Bra      S  (0x6000)          (0xFF00)          [br8:0]                           CF_A_UP;
+    Bra      W  (0x6000)          (0xFFFF)          [br16]                            CF_A_UP;
+    Bra      L  (0x60FF)          (0xFFFF)          [br32]                            CF_B_UP;
+
+    Bset     L  (0x01C0)          (0xF1F8)          [data:9, data:0]                  CF_A_UP;
+    Bset     B  (0x01C0)          (0xF1C0)          [data:9, __ipmdxnf___:0]          CF_A_UP;
+    Bset     L  (0x08C0, 0x0000)  (0xFFF8, 0xFF00)  [q8:16, data:0]                   CF_A_UP;
+    Bset     B  (0x08C0, 0x0000)  (0xFFC0, 0xFF00)  [q8:16, __ipmd______:0]           CF_A_UP;
+
+    Bsr      S  (0x6100)          (0xFF00)          [br8:0]                           CF_A_UP;
+    Bsr      W  (0x6100)          (0xFFFF)          [br16]                            CF_A_UP;
+    Bsr      L  (0x61FF)          (0xFFFF)          [br32]                            CF_B_UP;
+
+    Btst     L  (0x0100)          (0xF1F8)          [data:9, data:0]                  CF_A_UP;
+    Btst     B  (0x0100)          (0xF1C0)          [data:9, __ipmdxnfDX_:0]          CF_A_UP;
+    Btst     L  (0x0800, 0x0000)  (0xFFF8, 0xFF00)  [q8:16, data:0]                   CF_A_UP;
+    Btst     B  (0x0800, 0x0000)  (0xFFC0, 0xFF00)  [q8:16, __ipmd______:0]           CF_A_UP;
+
+    Byterev  L  (0x02C0)          (0xFFF8)          [data:0]                          CF_A2 | CF_C;
+
+    Clr      B  (0x4200)          (0xFFC0)          [d_ipmdxnf___:0]                  CF_A_UP;
+    Clr      W  (0x4240)          (0xFFC0)          [d_ipmdxnf___:0]                  CF_A_UP;
+    Clr      L  (0x4280)          (0xFFC0)          [d_ipmdxnf___:0]                  CF_A_UP;
+
+    Cmpa     L  (0xB1C0)          (0xF1C0)          [daipmdxnfDXI:0, addr:9]          CF_A_UP;
+    Cmpa     W  (0xB0C0)          (0xF1C0)          [daipmdxnfDXI:0, addr:9]          CF_B_UP;
+
+    Cmpi     B  (0x0C00)          (0xFFF8)          [imm, data:0]                     CF_B_UP;
+    Cmpi     W  (0x0C40)          (0xFFF8)          [imm, data:0]                     CF_B_UP;
+    Cmpi     L  (0x0C80)          (0xFFF8)          [imm, data:0]                     CF_A_UP;
+
+    Cmp      B  (0xB000)          (0xF1C0)          [d_ipmdxnfDXI:0, data:9]          CF_B_UP;
+    Cmp      W  (0xB040)          (0xF1C0)          [daipmdxnfDXI:0, data:9]          CF_B_UP;
+    Cmp      L  (0xB080)          (0xF1C0)          [daipmdxnfDXI:0, data:9]          CF_A_UP;
+
+    Cpushl   -  (0xF428)          (0xFF38)          [cache:6, ind:0]                  CF_A_UP;
+
+    Divs     W  (0x81C0)          (0xF1C0)          [d_ipmdxnfDXI:0, data:9]          CF_A_UP;
+    Divs     L  (0x4C40, 0x0800)  (0xFFC0, 0x8FF8)  [d_ipmd______:0, dup:28:16]       CF_A_UP;
+
+    Divu     W  (0x80C0)          (0xF1C0)          [d_ipmdxnfDXI:0, data:9]          CF_A_UP;
+    Divu     L  (0x4C40, 0x0000)  (0xFFC0, 0x8FF8)  [d_ipmd______:0, dup:28:16]       CF_A_UP;
+
+    Eori     L  (0x0A80)          (0xFFF8)          [imm, data:0]                     CF_A_UP;
+
+    Eor      L  (0xB180)          (0xF1C0)          [data:9, d_ipmdxnf___:0]          CF_A_UP;
+
+    Ext      W  (0x4880)          (0xFFF8)          [data:0]                          CF_A_UP;
+    Ext      L  (0x48C0)          (0xFFF8)          [data:0]                          CF_A_UP;
+
+    Extb     L  (0x49C0)          (0xFFF8)          [data:0]                          CF_A_UP;
+
+    Ff1      L  (0x04C0)          (0xFFF8)          [data:0]                          CF_A2 | CF_C;
+
+    Halt     -  (0x4AC8)          (0xFFFF)          []                                CF_A_UP;
+
+    Illegal  -  (0x4AFC)          (0xFFFF)          []                                CF_A_UP;
+
+    Intouch  -  (0xF428)          (0xFFF8)          [ind:0]                           CF_B_UP;
+
+    Jmp      -  (0x4EC0)          (0xFFC0)          [__i__dxnfDX_:0]                  CF_A_UP;
+
+    Jsr      -  (0x4E80)          (0xFFC0)          [__i__dxnfDX_:0]                  CF_A_UP;
+
+    Lea      L  (0x41C0)          (0xF1C0)          [__i__dxnfDX_:0, addr:9]          CF_A_UP;
+
+    Link     W  (0x4E50)          (0xFFF8)          [addr:0, imm]                     CF_A_UP;
+
+    Lsl      L  (0xE188)          (0xF1F8)          [q3:9, data:0]                    CF_A_UP;
+    Lsl      L  (0xE1A8)          (0xF1F8)          [data:9, data:0]                  CF_A_UP;
+
+    Lsr      L  (0xE088)          (0xF1F8)          [q3:9, data:0]                    CF_A_UP;
+    Lsr      L  (0xE0A8)          (0xF1F8)          [data:9, data:0]                  CF_A_UP;
+
+    Mov3q    L  (0xA140)          (0xF1C0)          [q3n:9, daipmdxnf___:0]           CF_B_UP;
+
+    Movea    W  (0x3040)          (0xF1C0)          [daipmdxnfDXI:0, addr:9]          CF_A_UP;
+    Movea    L  (0x2040)          (0xF1C0)          [daipmdxnfDXI:0, addr:9]          CF_A_UP;
+
+    Movec    L  (0x4E7B, 0x0000)  (0xFFFF, 0x0000)  [reg:28, rc:16]                   CF_A_UP;
+
+    Movem    L  (0x48C0, 0x0000)  (0xFFC0, 0x0000)  [list, __i__d______:0]            CF_A_UP;
+    Movem    L  (0x4CC0, 0x0000)  (0xFFC0, 0x0000)  [__i__d______:0, list]            CF_A_UP;
+
+    Moveq    L  (0x7000)          (0xF100)          [q8:0, data:9]                    CF_A_UP;
+
+    Move     B  (0x1000)          (0xF000)          [d_ipmdxnfDXI:0, dst:6]           CF_A_UP;
+    Move     W  (0x3000)          (0xF000)          [daipmdxnfDXI:0, dst:6]           CF_A_UP;
+    Move     L  (0x2000)          (0xF000)          [daipmdxnfDXI:0, dst:6]           CF_A_UP;
+    Move     W  (0x40C0)          (0xFFF8)          [sr, data:0]                      CF_A_UP;
+    Move     W  (0x42C0)          (0xFFF8)          [ccr, data:0]                     CF_A_UP;
+    Move     B  (0x44C0)          (0xFFC0)          [d__________I:0, ccr]             CF_A_UP;
+    Move     W  (0x46C0)          (0xFFC0)          [d__________I:0, sr]              CF_A_UP;
+
+    Muls     W  (0xC1C0)          (0xF1C0)          [d_ipmdxnfDXI:0, data:9]          CF_A_UP;
+    Muls     L  (0x4C00, 0x0800)  (0xFFC0, 0x8FFF)  [d_ipmd______:0, data:28]         CF_A_UP;
+
+    Mulu     W  (0xC0C0)          (0xF1C0)          [d_ipmdxnfDXI:0, data:9]          CF_A_UP;
+    Mulu     L  (0x4C00, 0x0000)  (0xFFC0, 0x8FFF)  [d_ipmd______:0, data:28]         CF_A_UP;
+
+    Mvs      B  (0x7100)          (0xF1C0)          [d_ipmdxnfDXI:0, data:9]          CF_B_UP;
+    Mvs      W  (0x7140)          (0xF1C0)          [daipmdxnfDXI:0, data:9]          CF_B_UP;
+
+    Mvz      B  (0x7180)          (0xF1C0)          [d_ipmdxnfDXI:0, data:9]          CF_B_UP;
+    Mvz      W  (0x71C0)          (0xF1C0)          [daipmdxnfDXI:0, data:9]          CF_B_UP;
+
+    Neg      L  (0x4480)          (0xFFF8)          [data:0]                          CF_A_UP;
+
+    Negx     L  (0x4080)          (0xFFF8)          [data:0]                          CF_A_UP;
+
+    Nop      -  (0x4E71)          (0xFFFF)          []                                CF_A_UP;
+
+    Not      L  (0x4680)          (0xFFF8)          [data:0]                          CF_A_UP;
+
+    Ori      L  (0x0080)          (0xFFF8)          [imm, data:0]                     CF_A_UP;
+
+    Or       L  (0x8080)          (0xF1C0)          [d_ipmdxnfDXI:0, data:9]          CF_A_UP;
+    Or       L  (0x8180)          (0xF1C0)          [data:9, __ipmdxnf___:0]          CF_A_UP;
+
+    Pea      L  (0x4840)          (0xFFC0)          [__i__dxnfDX_:0]                  CF_A_UP;
+
+    Pulse    -  (0x4ACC)          (0xFFFF)          []                                CF_A_UP;
+
+    Rems     L  (0x4C40, 0x0800)  (0xFFC0, 0x8FF8)  [d_ipmd______:0, pair:16:28]      CF_A_UP;
+
+    Remu     L  (0x4C40, 0x0000)  (0xFFC0, 0x8FF8)  [d_ipmd______:0, pair:16:28]      CF_A_UP;
+
+    Rte      -  (0x4E73)          (0xFFFF)          []                                CF_A_UP;
+
+    Rts      -  (0x4E75)          (0xFFFF)          []                                CF_A_UP;
+
+    Sats     L  (0x4C80)          (0xFFF8)          [data:0]                          CF_B_UP;
+
+    Scc      B  (0x50C0)          (0xF0F8)          [cond:8, data:0]                  CF_A_UP;
+
+    Stop     -  (0x4E72)          (0xFFFF)          [immw]                            CF_A_UP;
+
+    Strldsr  -  (0x40E7, 0x46FC)  (0xFFFF, 0xFFFF)  [immw]                            CF_A2 | CF_C;
+
+    Suba     L  (0x91C0)          (0xF1C0)          [daipmdxnfDXI:0, addr:9]          CF_A_UP;
+
+    Subi     L  (0x0480)          (0xFFF8)          [imm, data:0]                     CF_A_UP;
+
+    Subq     L  (0x5180)          (0xF1C0)          [q3:9, daipmdxnf___:0]            CF_A_UP;
+
+    Sub      L  (0x9080)          (0xF1C0)          [daipmdxnfDXI:0, data:9]          CF_A_UP;
+    Sub      L  (0x9180)          (0xF1C0)          [data:9, __ipmdxnf___:0]          CF_A_UP;
+
+    Subx     L  (0x9180)          (0xF1F8)          [data:0, data:9]                  CF_A_UP;
+
+    Swap     W  (0x4840)          (0xFFF8)          [data:0]                          CF_A_UP;
+
+    Tas      B  (0x4AC0)          (0xFFC0)          [__ipmdxnf___:0]                  CF_B_UP;
+
+    Tpf      -  (0x51FC)          (0xFFFF)          []                                CF_A_UP;
+    Tpf      W  (0x51FA)          (0xFFFF)          [imm]                             CF_A_UP;
+    Tpf      L  (0x51FB)          (0xFFFF)          [imm]                             CF_A_UP;
+
+    Trap     -  (0x4E40)          (0xFFF0)          [q4:0]                            CF_A_UP;
+
+    Tst      B  (0x4A00)          (0xFFC0)          [d_ipmdxnfDXI:0]                  CF_A_UP;
+    Tst      W  (0x4A40)          (0xFFC0)          [daipmdxnfDXI:0]                  CF_A_UP;
+    Tst      L  (0x4A80)          (0xFFC0)          [daipmdxnfDXI:0]                  CF_A_UP;
+
+    Unlk     -  (0x4E58)          (0xFFF8)          [addr:0]                          CF_A_UP;
+
+    Wddata   B  (0xFB00)          (0xFFC0)          [__ipmdxnf___:0]                  CF_A_UP;
+    Wddata   W  (0xFB40)          (0xFFC0)          [__ipmdxnf___:0]                  CF_A_UP;
+    Wddata   L  (0xFB80)          (0xFFC0)          [__ipmdxnf___:0]                  CF_A_UP;
+
+    Wdebug   L  (0xFBC0, 0x0003)  (0xFFC0, 0xFFFF)  [__i__d______:0]                  CF_A_UP;
+
+    // Floating-point unit
+
+    Fabs     D  (0xF200, 0x0018)  (0xFFFF, 0xE07F)  [fp:26, fp:23]                    CF_FPU;
+    Fabs     B  (0xF200, 0x5818)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fabs     W  (0xF200, 0x5018)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fabs     L  (0xF200, 0x4018)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fabs     F  (0xF200, 0x4418)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fabs     D  (0xF200, 0x5418)  (0xFFC0, 0xFC7F)  [__ipmd___D_I:0, fp:23]           CF_FPU;
+
+    Fadd     D  (0xF200, 0x0022)  (0xFFFF, 0xE07F)  [fp:26, fp:23]                    CF_FPU;
+    Fadd     B  (0xF200, 0x5822)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fadd     W  (0xF200, 0x5022)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fadd     L  (0xF200, 0x4022)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fadd     F  (0xF200, 0x4422)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fadd     D  (0xF200, 0x5422)  (0xFFC0, 0xFC7F)  [__ipmd___D_I:0, fp:23]           CF_FPU;
+
+    Fbcc     W  (0xF280)          (0xFFC0)          [fpcond:0, br16]                  CF_FPU;
+    Fbcc     L  (0xF2C0)          (0xFFC0)          [fpcond:0, br32]                  CF_FPU;
+
+    Fcmp     D  (0xF200, 0x0038)  (0xFFFF, 0xE07F)  [fp:26, fp:23]                    CF_FPU;
+    Fcmp     B  (0xF200, 0x5838)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fcmp     W  (0xF200, 0x5038)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fcmp     L  (0xF200, 0x4038)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fcmp     F  (0xF200, 0x4438)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fcmp     D  (0xF200, 0x5438)  (0xFFC0, 0xFC7F)  [__ipmd___D_I:0, fp:23]           CF_FPU;
+
+    Fdiv     D  (0xF200, 0x0020)  (0xFFFF, 0xE07F)  [fp:26, fp:23]                    CF_FPU;
+    Fdiv     B  (0xF200, 0x5820)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fdiv     W  (0xF200, 0x5020)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fdiv     L  (0xF200, 0x4020)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fdiv     F  (0xF200, 0x4420)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fdiv     D  (0xF200, 0x5420)  (0xFFC0, 0xFC7F)  [__ipmd___D_I:0, fp:23]           CF_FPU;
+
+    Fint     D  (0xF200, 0x0001)  (0xFFFF, 0xE07F)  [fp:26, fp:23]                    CF_FPU;
+    Fint     B  (0xF200, 0x5801)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fint     W  (0xF200, 0x5001)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fint     L  (0xF200, 0x4001)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fint     F  (0xF200, 0x4401)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fint     D  (0xF200, 0x5401)  (0xFFC0, 0xFC7F)  [__ipmd___D_I:0, fp:23]           CF_FPU;
+
+    Fintrz   D  (0xF200, 0x0003)  (0xFFFF, 0xE07F)  [fp:26, fp:23]                    CF_FPU;
+    Fintrz   B  (0xF200, 0x5803)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fintrz   W  (0xF200, 0x5003)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fintrz   L  (0xF200, 0x4003)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fintrz   F  (0xF200, 0x4403)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fintrz   D  (0xF200, 0x5403)  (0xFFC0, 0xFC7F)  [__ipmd___D_I:0, fp:23]           CF_FPU;
+
+    Fmove    D  (0xF200, 0x0000)  (0xFFFF, 0xE07F)  [fp:26, fp:23]                    CF_FPU;
+    Fmove    B  (0xF200, 0x5800)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fmove    W  (0xF200, 0x5000)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fmove    L  (0xF200, 0x4000)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fmove    F  (0xF200, 0x4400)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fmove    D  (0xF200, 0x5400)  (0xFFC0, 0xFC7F)  [__ipmd___D_I:0, fp:23]           CF_FPU;
+    Fmove    B  (0xF200, 0x7800)  (0xFFC0, 0xFC7F)  [fp:23, d_ipmd______:0]           CF_FPU;
+    Fmove    W  (0xF200, 0x7000)  (0xFFC0, 0xFC7F)  [fp:23, d_ipmd______:0]           CF_FPU;
+    Fmove    L  (0xF200, 0x6000)  (0xFFC0, 0xFC7F)  [fp:23, d_ipmd______:0]           CF_FPU;
+    Fmove    F  (0xF200, 0x6400)  (0xFFC0, 0xFC7F)  [fp:23, d_ipmd______:0]           CF_FPU;
+    Fmove    D  (0xF200, 0x7400)  (0xFFC0, 0xFC7F)  [fp:23, __ipmd______:0]           CF_FPU;
+    Fmove    L  (0xF200, 0x9000)  (0xFFC0, 0xFFFF)  [d_ipmd___D_I:0, fpcr]            CF_FPU;
+    Fmove    L  (0xF200, 0xB000)  (0xFFC0, 0xFFFF)  [fpcr, d_ipmd______:0]            CF_FPU;
+    Fmove    L  (0xF200, 0x8800)  (0xFFC0, 0xFFFF)  [d_ipmd___D_I:0, fpsr]            CF_FPU;
+    Fmove    L  (0xF200, 0xA800)  (0xFFC0, 0xFFFF)  [fpsr, d_ipmd______:0]            CF_FPU;
+    Fmove    L  (0xF200, 0x8400)  (0xFFC0, 0xFFFF)  [d_ipmd___D_I:0, fpiar]           CF_FPU;
+    Fmove    L  (0xF200, 0xA400)  (0xFFC0, 0xFFFF)  [fpiar, d_ipmd______:0]           CF_FPU;
+
+    Fmovem   D  (0xF200, 0xD000)  (0xFFC0, 0xFF00)  [__i__d______:0, fplist]          CF_FPU;
+    Fmovem   D  (0xF200, 0xF000)  (0xFFC0, 0xFF00)  [fplist, __i__d______:0]          CF_FPU;
+
+    Fmul     D  (0xF200, 0x0023)  (0xFFFF, 0xE07F)  [fp:26, fp:23]                    CF_FPU;
+    Fmul     B  (0xF200, 0x5823)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fmul     W  (0xF200, 0x5023)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fmul     L  (0xF200, 0x4023)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fmul     F  (0xF200, 0x4423)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fmul     D  (0xF200, 0x5423)  (0xFFC0, 0xFC7F)  [__ipmd___D_I:0, fp:23]           CF_FPU;
+
+    Fneg     D  (0xF200, 0x001A)  (0xFFFF, 0xE07F)  [fp:26, fp:23]                    CF_FPU;
+    Fneg     B  (0xF200, 0x581A)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fneg     W  (0xF200, 0x501A)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fneg     L  (0xF200, 0x401A)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fneg     F  (0xF200, 0x441A)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fneg     D  (0xF200, 0x541A)  (0xFFC0, 0xFC7F)  [__ipmd___D_I:0, fp:23]           CF_FPU;
+
+    Frestore -  (0xF340)          (0xFFC0)          [__i__d______:0]                  CF_FPU;
+
+    Fsave    -  (0xF300)          (0xFFC0)          [__i__d______:0]                  CF_FPU;
+
+    Fsqrt    D  (0xF200, 0x0004)  (0xFFFF, 0xE07F)  [fp:26, fp:23]                    CF_FPU;
+    Fsqrt    B  (0xF200, 0x5804)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fsqrt    W  (0xF200, 0x5004)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fsqrt    L  (0xF200, 0x4004)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fsqrt    F  (0xF200, 0x4404)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fsqrt    D  (0xF200, 0x5404)  (0xFFC0, 0xFC7F)  [__ipmd___D_I:0, fp:23]           CF_FPU;
+
+    Fsub     D  (0xF200, 0x0028)  (0xFFFF, 0xE07F)  [fp:26, fp:23]                    CF_FPU;
+    Fsub     B  (0xF200, 0x5828)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fsub     W  (0xF200, 0x5028)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fsub     L  (0xF200, 0x4028)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fsub     F  (0xF200, 0x4428)  (0xFFC0, 0xFC7F)  [d_ipmd___D_I:0, fp:23]           CF_FPU;
+    Fsub     D  (0xF200, 0x5428)  (0xFFC0, 0xFC7F)  [__ipmd___D_I:0, fp:23]           CF_FPU;
+
+    Ftst     D  (0xF200, 0x003A)  (0xFFFF, 0xE3FF)  [fp:26]                           CF_FPU;
+    Ftst     B  (0xF200, 0x583A)  (0xFFC0, 0xFFFF)  [d_ipmd___D_I:0]                  CF_FPU;
+    Ftst     W  (0xF200, 0x503A)  (0xFFC0, 0xFFFF)  [d_ipmd___D_I:0]                  CF_FPU;
+    Ftst     L  (0xF200, 0x403A)  (0xFFC0, 0xFFFF)  [d_ipmd___D_I:0]                  CF_FPU;
+    Ftst     F  (0xF200, 0x443A)  (0xFFC0, 0xFFFF)  [d_ipmd___D_I:0]                  CF_FPU;
+    Ftst     D  (0xF200, 0x543A)  (0xFFC0, 0xFFFF)  [__ipmd___D_I:0]                  CF_FPU;
+
+    // Multiply-accumulate units
+
+    Mac      W  (0xA000, 0x0000)  (0xF1B0, 0xF93F)  [mac:0:3:22, mac:9:6:23, sf:25]   CF_MAC;
+    Mac      L  (0xA000, 0x0800)  (0xF1B0, 0xF9FF)  [mac:0:3:22, mac:9:6:23, sf:25]   CF_MAC;
+    Mac      W  (0xA080, 0x0000)  (0xF180, 0x0910)  [(MacLoad)]                       CF_MAC;
+    Mac      L  (0xA080, 0x0800)  (0xF180, 0x09D0)  [(MacLoad)]                       CF_MAC;
+    Mac      W  (0xA000, 0x0000)  (0xF130, 0xF92F)  [(EmacOp)]                        CF_EMAC | CF_EMAC_B;
+    Mac      L  (0xA000, 0x0800)  (0xF130, 0xF9EF)  [(EmacOp)]                        CF_EMAC | CF_EMAC_B;
+    Mac      W  (0xA000, 0x0000)  (0xF100, 0x0900)  [(EmacLoad)]                      CF_EMAC | CF_EMAC_B;
+    Mac      L  (0xA000, 0x0800)  (0xF100, 0x09C0)  [(EmacLoad)]                      CF_EMAC | CF_EMAC_B;
+
+    Msac     W  (0xA000, 0x0100)  (0xF1B0, 0xF93F)  [mac:0:3:22, mac:9:6:23, sf:25]   CF_MAC;
+    Msac     L  (0xA000, 0x0900)  (0xF1B0, 0xF9FF)  [mac:0:3:22, mac:9:6:23, sf:25]   CF_MAC;
+    Msac     W  (0xA080, 0x0100)  (0xF180, 0x0910)  [(MacLoad)]                       CF_MAC;
+    Msac     L  (0xA080, 0x0900)  (0xF180, 0x09D0)  [(MacLoad)]                       CF_MAC;
+    Msac     W  (0xA000, 0x0100)  (0xF130, 0xF92F)  [(EmacOp)]                        CF_EMAC | CF_EMAC_B;
+    Msac     L  (0xA000, 0x0900)  (0xF130, 0xF9EF)  [(EmacOp)]                        CF_EMAC | CF_EMAC_B;
+    Msac     W  (0xA000, 0x0100)  (0xF100, 0x0900)  [(EmacLoad)]                      CF_EMAC | CF_EMAC_B;
+    Msac     L  (0xA000, 0x0900)  (0xF100, 0x09C0)  [(EmacLoad)]                      CF_EMAC | CF_EMAC_B;
+
+    Movclr   L  (0xA1C0)          (0xF9F0)          [acc:9, reg:0]                    CF_EMAC | CF_EMAC_B;
+
+    Move     L  (0xA100)          (0xFFC0)          [da_________I:0, acc0]            CF_MAC;
+    Move     L  (0xA900)          (0xFFC0)          [da_________I:0, macsr]           CF_MAC;
+    Move     L  (0xAD00)          (0xFFC0)          [da_________I:0, mask]            CF_MAC;
+    Move     L  (0xA180)          (0xFFF0)          [acc0, reg:0]                     CF_MAC;
+    Move     L  (0xA980)          (0xFFF0)          [macsr, reg:0]                    CF_MAC;
+    Move     L  (0xAD80)          (0xFFF0)          [mask, reg:0]                     CF_MAC;
+    Move     L  (0xA9C0)          (0xFFFF)          [macsr, ccr]                      CF_MAC;
+
+    Move     L  (0xA100)          (0xF9C0)          [da_________I:0, acc:9]           CF_EMAC | CF_EMAC_B;
+    Move     L  (0xA110)          (0xF9FC)          [acc:0, acc:9]                    CF_EMAC | CF_EMAC_B;
+    Move     L  (0xA900)          (0xFFC0)          [da_________I:0, macsr]           CF_EMAC | CF_EMAC_B;
+    Move     L  (0xAD00)          (0xFFC0)          [da_________I:0, mask]            CF_EMAC | CF_EMAC_B;
+    Move     L  (0xAB00)          (0xFFC0)          [da_________I:0, accext01]        CF_EMAC | CF_EMAC_B;
+    Move     L  (0xAF00)          (0xFFC0)          [da_________I:0, accext23]        CF_EMAC | CF_EMAC_B;
+    Move     L  (0xA180)          (0xF9F0)          [acc:9, reg:0]                    CF_EMAC | CF_EMAC_B;
+    Move     L  (0xA980)          (0xFFF0)          [macsr, reg:0]                    CF_EMAC | CF_EMAC_B;
+    Move     L  (0xAD80)          (0xFFF0)          [mask, reg:0]                     CF_EMAC | CF_EMAC_B;
+    Move     L  (0xAB80)          (0xFFF0)          [accext01, reg:0]                 CF_EMAC | CF_EMAC_B;
+    Move     L  (0xAF80)          (0xFFF0)          [accext23, reg:0]                 CF_EMAC | CF_EMAC_B;
+    Move     L  (0xA9C0)          (0xFFFF)          [macsr, ccr]                      CF_EMAC | CF_EMAC_B;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use aex::ast::{Expr, Float, Int};
+    use aex::io::{DecodeReader, RewindReader};
+    use aex::util::BE;
+    use super::super::*;
+
+    #[test]
+    fn decode_isa_a() {
+        use super::super::Mnemonic::*;
+        use super::super::Size::*;
+
+        let cases: &[(&[u8], Mnemonic, Size, usize)] = &[
+            (&[0x92, 0x80],             Sub,     Long, 2), // sub.l   d0, d1
+            (&[0x93, 0x90],             Sub,     Long, 2), // sub.l   d1, (a0)
+            (&[0x91, 0xC8],             Suba,    Long, 2), // suba.l  a0, a0
+            (&[0x04, 0x81, 0, 0, 0, 1], Subi,    Long, 2), // subi.l  #1, d1
+            (&[0x53, 0x88],             Subq,    Long, 2), // subq.l  #1, a0
+            (&[0x93, 0x80],             Subx,    Long, 2), // subx.l  d0, d1
+            (&[0xC2, 0x90],             And,     Long, 2), // and.l   (a0), d1
+            (&[0x02, 0x80, 0, 0, 0, 1], Andi,    Long, 2), // andi.l  #1, d0
+            (&[0x82, 0x80],             Or,      Long, 2), // or.l    d0, d1
+            (&[0x00, 0x80, 0, 0, 0, 1], Ori,     Long, 2), // ori.l   #1, d0
+            (&[0xB1, 0x81],             Eor,     Long, 2), // eor.l   d0, d1
+            (&[0x0A, 0x80, 0, 0, 0, 1], Eori,    Long, 2), // eori.l  #1, d0
+            (&[0xB2, 0x80],             Cmp,     Long, 2), // cmp.l   d0, d1
+            (&[0xB1, 0xC8],             Cmpa,    Long, 2), // cmpa.l  a0, a0
+            (&[0x0C, 0x80, 0, 0, 0, 1], Cmpi,    Long, 2), // cmpi.l  #1, d0
+            (&[0x41, 0xE8, 0x00, 0x10], Lea,     Long, 2), // lea     16(a0), a0
+            (&[0x48, 0x50],             Pea,     Long, 1), // pea     (a0)
+            (&[0x4E, 0xD0],             Jmp,     Zero, 1), // jmp     (a0)
+            (&[0x4E, 0x90],             Jsr,     Zero, 1), // jsr     (a0)
+            (&[0x60, 0x10],             Bra,     Byte, 1), // bra.s   *+18
+            (&[0x60, 0x00, 0x01, 0x00], Bra,     Word, 1), // bra.w   *+258
+            (&[0x61, 0x10],             Bsr,     Byte, 1), // bsr.s   *+18
+            (&[0x61, 0x00, 0x01, 0x00], Bsr,     Word, 1), // bsr.w   *+258
+            (&[0x66, 0x10],             Bcc,     Byte, 2), // bne.s   *+18
+            (&[0x67, 0x00, 0x01, 0x00], Bcc,     Word, 2), // beq.w   *+258
+            (&[0x57, 0xC0],             Scc,     Byte, 2), // seq     d0
+            (&[0x4A, 0x10],             Tst,     Byte, 1), // tst.b   (a0)
+            (&[0x4A, 0x48],             Tst,     Word, 1), // tst.w   a0
+            (&[0x4A, 0x80],             Tst,     Long, 1), // tst.l   d0
+            (&[0x42, 0x00],             Clr,     Byte, 1), // clr.b   d0
+            (&[0x42, 0x50],             Clr,     Word, 1), // clr.w   (a0)
+            (&[0x42, 0x80],             Clr,     Long, 1), // clr.l   d0
+            (&[0x44, 0x80],             Neg,     Long, 1), // neg.l   d0
+            (&[0x40, 0x80],             Negx,    Long, 1), // negx.l  d0
+            (&[0x46, 0x80],             Not,     Long, 1), // not.l   d0
+            (&[0x48, 0x80],             Ext,     Word, 1), // ext.w   d0
+            (&[0x48, 0xC0],             Ext,     Long, 1), // ext.l   d0
+            (&[0x49, 0xC0],             Extb,    Long, 1), // extb.l  d0
+            (&[0x48, 0x40],             Swap,    Word, 1), // swap    d0
+            (&[0xE3, 0x88],             Lsl,     Long, 2), // lsl.l   #1, d0
+            (&[0xE2, 0xA8],             Lsr,     Long, 2), // lsr.l   d1, d0
+            (&[0xE1, 0x80],             Asl,     Long, 2), // asl.l   #8, d0
+            (&[0xE2, 0xA0],             Asr,     Long, 2), // asr.l   d1, d0
+            (&[0x4E, 0x56, 0xFF, 0xF8], Link,    Word, 2), // link    a6, #-8
+            (&[0x4E, 0x5E],             Unlk,    Zero, 1), // unlk    a6
+            (&[0x4E, 0x75],             Rts,     Zero, 0), // rts
+            (&[0x4E, 0x73],             Rte,     Zero, 0), // rte
+            (&[0x4E, 0x4F],             Trap,    Zero, 1), // trap    #15
+            (&[0x48, 0xD7, 0x00, 0xFF], Movem,   Long, 2), // movem.l d0-d7, (sp)
+            (&[0x4C, 0xD7, 0x00, 0xFF], Movem,   Long, 2), // movem.l (sp), d0-d7
+            (&[0x72, 0xFF],             Moveq,   Long, 2), // moveq   #-1, d1
+            (&[0x30, 0x49],             Movea,   Word, 2), // movea.w a1, a0
+            (&[0x20, 0x40],             Movea,   Long, 2), // movea.l d0, a0
+            (&[0x40, 0xC0],             Move,    Word, 2), // move.w  sr, d0
+            (&[0x42, 0xC0],             Move,    Word, 2), // move.w  ccr, d0
+            (&[0x44, 0xFC, 0x00, 0x1F], Move,    Byte, 2), // move.b  #0x1F, ccr
+            (&[0x46, 0xFC, 0x27, 0x00], Move,    Word, 2), // move.w  #0x2700, sr
+            (&[0x4E, 0x7B, 0x08, 0x01], Movec,   Long, 2), // movec   d0, vbr
+            (&[0x81, 0xC0],             Divs,    Word, 2), // divs.w  d0, d0
+            (&[0x80, 0xC0],             Divu,    Word, 2), // divu.w  d0, d0
+            (&[0x4C, 0x40, 0x18, 0x01], Divs,    Long, 2), // divs.l  d0, d1
+            (&[0x4C, 0x40, 0x10, 0x01], Divu,    Long, 2), // divu.l  d0, d1
+            (&[0x4C, 0x40, 0x18, 0x02], Rems,    Long, 2), // rems.l  d0, d2:d1
+            (&[0x4C, 0x40, 0x10, 0x02], Remu,    Long, 2), // remu.l  d0, d2:d1
+            (&[0x4C, 0x00, 0x18, 0x00], Muls,    Long, 2), // muls.l  d0, d1
+            (&[0xC1, 0xC0],             Muls,    Word, 2), // muls.w  d0, d0
+            (&[0xC0, 0xC0],             Mulu,    Word, 2), // mulu.w  d0, d0
+            (&[0x03, 0x00],             Btst,    Long, 2), // btst    d1, d0
+            (&[0x03, 0x10],             Btst,    Byte, 2), // btst    d1, (a0)
+            (&[0x08, 0x00, 0x00, 0x03], Btst,    Long, 2), // btst    #3, d0
+            (&[0x08, 0x50, 0x00, 0x03], Bchg,    Byte, 2), // bchg    #3, (a0)
+            (&[0x03, 0x90],             Bclr,    Byte, 2), // bclr    d1, (a0)
+            (&[0x08, 0xC0, 0x00, 0x1F], Bset,    Long, 2), // bset    #31, d0
+            (&[0x4A, 0xC8],             Halt,    Zero, 0), // halt
+            (&[0x4A, 0xCC],             Pulse,   Zero, 0), // pulse
+            (&[0x4A, 0xFC],             Illegal, Zero, 0), // illegal
+            (&[0x4E, 0x72, 0x27, 0x00], Stop,    Zero, 1), // stop    #0x2700
+            (&[0xFB, 0x50],             Wddata,  Word, 1), // wddata.w (a0)
+            (&[0xFB, 0xD0, 0x00, 0x03], Wdebug,  Long, 1), // wdebug.l (a0)
+            (&[0xF4, 0xE8],             Cpushl,  Zero, 2), // cpushl  bc, (a0)
+            (&[0x51, 0xFC],             Tpf,     Zero, 0), // tpf
+            (&[0x51, 0xFA, 0x00, 0x00], Tpf,     Word, 1), // tpf.w   #0
+        ];
+
+        for &(bytes, name, size, n) in cases {
+            let i = decode(OPCODES, CF_A, &mut reader(bytes))
+                .unwrap()
+                .unwrap_or_else(|| panic!("no match: {:02X?}", bytes));
+            assert_eq!((i.name, i.size, i.operands.len()), (name, size, n),
+                       "{:02X?}", bytes);
+            assert_eq!(i.bytes, bytes);
+        }
+    }
+
+    #[test]
+    fn decode_divide_vs_remainder() {
+        // divu.l d0, d1
+        let i = decode(OPCODES, CF_A, &mut reader(&[0x4C, 0x40, 0x10, 0x01])).unwrap().unwrap();
+        assert_eq!(i.operands[1], Operand::Mode(Mode::Data(D1)));
+
+        // remu.l d0, d2:d1
+        let i = decode(OPCODES, CF_A, &mut reader(&[0x4C, 0x40, 0x10, 0x02])).unwrap().unwrap();
+        assert_eq!(i.operands[1], Operand::DataRegPair(D2, D1));
+    }
+
+    #[test]
+    fn decode_branch() {
+        // bne.s *-2
+        let i = decode(OPCODES, CF_A, &mut reader(&[0x66, 0xFC])).unwrap().unwrap();
+        assert_eq!(i.operands, vec![
+            Operand::Cond(Condition::Ne),
+            Operand::Branch(Expr::Int(Int::new(-4))),
+        ]);
+    }
+
+    #[test]
+    fn decode_reserved() {
+        // bra with 32-bit displacement (not in ISA_A)
+        let mut r = reader(&[0x60, 0xFF, 0x00, 0x00, 0x00, 0x10]);
+        assert_eq!(decode(OPCODES, CF_A, &mut r).unwrap(), None);
+
+        // cpushl with no cache selected
+        let mut r = reader(&[0xF4, 0x28]);
+        assert_eq!(decode(OPCODES, CF_A, &mut r).unwrap(), None);
+    }
+
+    #[test]
+    fn decode_isa_profile() {
+        use super::super::Mnemonic::*;
+
+        let cases: &[(&[u8], Mnemonic, Flags)] = &[
+            (&[0xA3, 0x40],             Mov3q,   CF_B_UP),       // mov3q.l  #1, d0
+            (&[0xA1, 0x40],             Mov3q,   CF_B_UP),       // mov3q.l  #-1, d0
+            (&[0x71, 0x10],             Mvs,     CF_B_UP),       // mvs.b    (a0), d0
+            (&[0x71, 0xC1],             Mvz,     CF_B_UP),       // mvz.w    d1, d0
+            (&[0x4C, 0x80],             Sats,    CF_B_UP),       // sats.l   d0
+            (&[0x02, 0xC0],             Byterev, CF_A2 | CF_C),  // byterev.l d0
+            (&[0x00, 0xC0],             Bitrev,  CF_A2 | CF_C),  // bitrev.l d0
+            (&[0x04, 0xC0],             Ff1,     CF_A2 | CF_C),  // ff1.l    d0
+            (&[0xB0, 0x01],             Cmp,     CF_B_UP),       // cmp.b    d1, d0
+            (&[0xB0, 0x41],             Cmp,     CF_B_UP),       // cmp.w    d1, d0
+            (&[0x4A, 0xD0],             Tas,     CF_B_UP),       // tas.b    (a0)
+            (&[0x60, 0xFF, 0, 0, 1, 0], Bra,     CF_B_UP),       // bra.l    *+258
+            (&[0xF4, 0x28],             Intouch, CF_B_UP),       // intouch  (a0)
+            (&[0x40, 0xE7, 0x46, 0xFC,
+               0x27, 0x00],             Strldsr, CF_A2 | CF_C),  // strldsr  #0x2700
+        ];
+
+        for &(bytes, name, flags) in cases {
+            for &isa in &[CF_A, CF_A2, CF_B, CF_C] {
+                let i = decode(OPCODES, isa, &mut reader(bytes)).unwrap();
+                let expected = flags & isa != 0;
+                assert_eq!(i.map(|i| i.name) == Some(name), expected,
+                           "{:02X?} isa={:#X}", bytes, isa);
+            }
+        }
+    }
+
+    #[test]
+    fn decode_mov3q_imm() {
+        let i = decode(OPCODES, CF_B, &mut reader(&[0xA1, 0x40])).unwrap().unwrap();
+        assert_eq!(i.operands[0], Operand::Mode(Mode::Imm(Expr::Int(Int::new(-1)))));
+    }
+
+    #[test]
+    fn decode_fpu() {
+        use super::super::Mnemonic::*;
+        use super::super::Size::*;
+
+        let cases: &[(&[u8], Mnemonic, Size, usize)] = &[
+            (&[0xF2, 0x00, 0x00, 0xA2], Fadd,     Double, 2), // fadd.d   fp0, fp1
+            (&[0xF2, 0x10, 0x45, 0x00], Fmove,    Single, 2), // fmove.s  (a0), fp2
+            (&[0xF2, 0x11, 0x75, 0x80], Fmove,    Double, 2), // fmove.d  fp3, (a1)
+            (&[0xF2, 0x00, 0x58, 0x28], Fsub,     Byte,   2), // fsub.b   d0, fp0
+            (&[0xF2, 0x28, 0x50, 0x23,
+               0x00, 0x10],             Fmul,     Word,   2), // fmul.w   16(a0), fp0
+            (&[0xF2, 0x00, 0x40, 0x20], Fdiv,     Long,   2), // fdiv.l   d0, fp0
+            (&[0xF2, 0x00, 0x00, 0x38], Fcmp,     Double, 2), // fcmp.d   fp0, fp0
+            (&[0xF2, 0x00, 0x00, 0x04], Fsqrt,    Double, 2), // fsqrt.d  fp0, fp0
+            (&[0xF2, 0x00, 0x00, 0x18], Fabs,     Double, 2), // fabs.d   fp0, fp0
+            (&[0xF2, 0x00, 0x00, 0x1A], Fneg,     Double, 2), // fneg.d   fp0, fp0
+            (&[0xF2, 0x00, 0x00, 0x01], Fint,     Double, 2), // fint.d   fp0, fp0
+            (&[0xF2, 0x00, 0x00, 0x03], Fintrz,   Double, 2), // fintrz.d fp0, fp0
+            (&[0xF2, 0x00, 0x04, 0x3A], Ftst,     Double, 1), // ftst.d   fp1
+            (&[0xF2, 0x00, 0x40, 0x3A], Ftst,     Long,   1), // ftst.l   d0
+            (&[0xF2, 0x00, 0x90, 0x00], Fmove,    Long,   2), // fmove.l  d0, fpcr
+            (&[0xF2, 0x00, 0xA8, 0x00], Fmove,    Long,   2), // fmove.l  fpsr, d0
+            (&[0xF2, 0x10, 0x84, 0x00], Fmove,    Long,   2), // fmove.l  (a0), fpiar
+            (&[0xF2, 0x17, 0xF0, 0xFF], Fmovem,   Double, 2), // fmovem.d fp0-fp7, (sp)
+            (&[0xF2, 0x17, 0xD0, 0x80], Fmovem,   Double, 2), // fmovem.d (sp), fp0
+            (&[0xF2, 0x81, 0x00, 0x10], Fbcc,     Word,   2), // fbeq.w   *+18
+            (&[0xF2, 0xCE, 0, 0, 1, 0], Fbcc,     Long,   2), // fbne.l   *+258
+            (&[0xF3, 0x10],             Fsave,    Zero,   1), // fsave    (a0)
+            (&[0xF3, 0x50],             Frestore, Zero,   1), // frestore (a0)
+        ];
+
+        for &(bytes, name, size, n) in cases {
+            let i = decode(OPCODES, CF_FPU, &mut reader(bytes))
+                .unwrap()
+                .unwrap_or_else(|| panic!("no match: {:02X?}", bytes));
+            assert_eq!((i.name, i.size, i.operands.len()), (name, size, n),
+                       "{:02X?}", bytes);
+            assert_eq!(i.bytes, bytes);
+
+            // Not available without FPU
+            let i = decode(OPCODES, CF_A_UP, &mut reader(bytes)).unwrap();
+            assert_eq!(i, None);
+        }
+    }
+
+    #[test]
+    fn decode_fpu_operands() {
+        // fadd.d fp0, fp1
+        let i = decode(OPCODES, CF_FPU, &mut reader(&[0xF2, 0x00, 0x00, 0xA2])).unwrap().unwrap();
+        assert_eq!(i.operands, vec![Operand::FpReg(FP0), Operand::FpReg(FP1)]);
+
+        // fmove.s #1.5, fp0
+        let mut r = reader(&[0xF2, 0x3C, 0x44, 0x00, 0x3F, 0xC0, 0x00, 0x00]);
+        let i = decode(OPCODES, CF_FPU, &mut r).unwrap().unwrap();
+        assert_eq!(i.operands, vec![
+            Operand::Mode(Mode::Imm(Expr::Float(Float::new(1.5)))),
+            Operand::FpReg(FP0),
+        ]);
+
+        // fmove.d #-2.5, fp7
+        let mut r = reader(&[0xF2, 0x3C, 0x57, 0x80,
+                             0xC0, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        let i = decode(OPCODES, CF_FPU, &mut r).unwrap().unwrap();
+        assert_eq!(i.operands, vec![
+            Operand::Mode(Mode::Imm(Expr::Float(Float::new(-2.5)))),
+            Operand::FpReg(FP7),
+        ]);
+
+        // fmovem.d fp0/fp7, (sp)
+        let i = decode(OPCODES, CF_FPU, &mut reader(&[0xF2, 0x17, 0xF0, 0x81])).unwrap().unwrap();
+        assert_eq!(i.operands[0], Operand::FpRegList(FP0 | FP7));
+
+        // fbcc with reserved condition
+        let i = decode(OPCODES, CF_FPU, &mut reader(&[0xF2, 0xA0, 0x00, 0x10])).unwrap();
+        assert_eq!(i, None);
+    }
+
+    #[test]
+    fn decode_mac() {
+        // mac.w d0.u, d1.l, <<
+        let i = decode(OPCODES, CF_MAC, &mut reader(&[0xA2, 0x00, 0x02, 0x40])).unwrap().unwrap();
+        assert_eq!((i.name, i.size), (Mnemonic::Mac, Size::Word));
+        assert_eq!(i.operands, vec![
+            Operand::RegHalf(Mode::Data(D0), MacHalf::Upper),
+            Operand::RegHalf(Mode::Data(D1), MacHalf::Lower),
+            Operand::MacShift(MacShift::Left),
+        ]);
+
+        // mac.w d0.u, d1.l, <<, (a0)+&, d2
+        let i = decode(OPCODES, CF_MAC, &mut reader(&[0xA4, 0x98, 0x12, 0x60])).unwrap().unwrap();
+        assert_eq!((i.name, i.size), (Mnemonic::Mac, Size::Word));
+        assert_eq!(i.operands, vec![
+            Operand::RegHalf(Mode::Data(D0), MacHalf::Upper),
+            Operand::RegHalf(Mode::Data(D1), MacHalf::Lower),
+            Operand::MacShift(MacShift::Left),
+            Operand::MaskedMode(Mode::AddrPostInc(A0), true),
+            Operand::Mode(Mode::Data(D2)),
+        ]);
+
+        // msac.l d0, d1
+        let i = decode(OPCODES, CF_MAC, &mut reader(&[0xA2, 0x00, 0x09, 0x00])).unwrap().unwrap();
+        assert_eq!((i.name, i.size), (Mnemonic::Msac, Size::Long));
+        assert_eq!(i.operands, vec![
+            Operand::Mode(Mode::Data(D0)),
+            Operand::Mode(Mode::Data(D1)),
+            Operand::MacShift(MacShift::None),
+        ]);
+
+        // Reserved shift factor
+        let i = decode(OPCODES, CF_MAC, &mut reader(&[0xA2, 0x00, 0x04, 0x00])).unwrap();
+        assert_eq!(i, None);
+    }
+
+    #[test]
+    fn decode_emac() {
+        // mac.l a1, d2, acc3
+        let bytes = &[0xA4, 0x89, 0x08, 0x10];
+        let i = decode(OPCODES, CF_EMAC, &mut reader(bytes)).unwrap().unwrap();
+        assert_eq!((i.name, i.size), (Mnemonic::Mac, Size::Long));
+        assert_eq!(i.operands, vec![
+            Operand::Mode(Mode::Addr(A1)),
+            Operand::Mode(Mode::Data(D2)),
+            Operand::MacShift(MacShift::None),
+            Operand::MacReg(Acc3),
+        ]);
+        assert_eq!(decode(OPCODES, CF_MAC, &mut reader(bytes)).unwrap(), None);
+
+        // msac.w d1.l, d0.u, >>, -(a1), a2, acc2
+        let i = decode(OPCODES, CF_EMAC, &mut reader(&[0xA4, 0x61, 0x07, 0x91])).unwrap().unwrap();
+        assert_eq!((i.name, i.size), (Mnemonic::Msac, Size::Word));
+        assert_eq!(i.operands, vec![
+            Operand::RegHalf(Mode::Data(D1), MacHalf::Lower),
+            Operand::RegHalf(Mode::Data(D0), MacHalf::Upper),
+            Operand::MacShift(MacShift::Right),
+            Operand::MaskedMode(Mode::AddrPreDec(A1), false),
+            Operand::Mode(Mode::Addr(A2)),
+            Operand::MacReg(Acc2),
+        ]);
+    }
+
+    #[test]
+    fn decode_mac_moves() {
+        use super::super::Mnemonic::*;
+
+        let cases: &[(&[u8], Mnemonic, Flags, Operand)] = &[
+            (&[0xA1, 0x80], Move,   CF_MAC,  Operand::MacReg(Acc0)),     // move.l acc, d0
+            (&[0xA9, 0xC0], Move,   CF_MAC,  Operand::MacReg(Macsr)),    // move.l macsr, ccr
+            (&[0xA5, 0xC3], Movclr, CF_EMAC, Operand::MacReg(Acc2)),     // movclr.l acc2, d3
+            (&[0xA1, 0x11], Move,   CF_EMAC, Operand::MacReg(Acc1)),     // move.l acc1, acc0
+            (&[0xAB, 0x80], Move,   CF_EMAC, Operand::MacReg(AccExt01)), // move.l accext01, d0
+            (&[0xAF, 0x89], Move,   CF_EMAC, Operand::MacReg(AccExt23)), // move.l accext23, a1
+            (&[0xAD, 0x88], Move,   CF_EMAC, Operand::MacReg(Mask)),     // move.l mask, a0
+        ];
+
+        for &(bytes, name, isa, ref op) in cases {
+            let i = decode(OPCODES, isa, &mut reader(bytes)).unwrap().unwrap();
+            assert_eq!((i.name, i.size), (name, Size::Long), "{:02X?}", bytes);
+            assert_eq!(&i.operands[0], op, "{:02X?}", bytes);
+        }
+
+        // move.l #1, macsr
+        let i = decode(OPCODES, CF_EMAC, &mut reader(&[0xA9, 0x3C, 0, 0, 0, 1])).unwrap().unwrap();
+        assert_eq!(i.operands, vec![
+            Operand::Mode(Mode::Imm(Expr::Int(Int::new(1)))),
+            Operand::MacReg(Macsr),
+        ]);
+    }
+
+    #[test]
+    fn decode_movec() {
+        // movec d0, vbr
+        let i = decode(OPCODES, CF_A, &mut reader(&[0x4E, 0x7B, 0x08, 0x01])).unwrap().unwrap();
+        assert_eq!(i.operands, vec![
+            Operand::Mode(Mode::Data(D0)),
+            Operand::CtlReg(Vbr),
+        ]);
+
+        // movec a1, rambar1
+        let i = decode(OPCODES, CF_A, &mut reader(&[0x4E, 0x7B, 0x9C, 0x05])).unwrap().unwrap();
+        assert_eq!(i.operands, vec![
+            Operand::Mode(Mode::Addr(A1)),
+            Operand::CtlReg(Rambar1),
+        ]);
+
+        // movec d0, (unknown)
+        assert_eq!(decode(OPCODES, CF_A, &mut reader(&[0x4E, 0x7B, 0x00, 0x01])).unwrap(), None);
+    }
+
+    #[test]
+    fn is_supported() {
+        let o = OPCODES.iter().find(|o| o.name == Mnemonic::Mov3q).unwrap();
+        assert!(!o.is_supported(CF_A | CF_A2 | CF_MAC));
+        assert!( o.is_supported(CF_B | CF_MAC));
+        assert!( o.is_supported(CF_C));
+    }
+
+    fn reader(bytes: &[u8]) -> DecodeReader<RewindReader<Cursor<Vec<u8>>>> {
+        let src = Cursor::new(bytes.to_vec());
+        let src = RewindReader::new(src);
+        DecodeReader::new(src, BE, 0)
+    }
+}
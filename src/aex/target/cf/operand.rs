@@ -0,0 +1,754 @@
+// ColdFire Operand Forms
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt::{self, Formatter, Write};
+use std::io;
+use num::BigInt;
+
+use aex::ast::{Expr, Int, Node, Reg};
+use aex::fmt::{Code, Style, ToCode, ToStyled};
+use aex::io::{DecodeRead, EncodeWrite};
+use aex::util::{BitPos, invalid};
+
+use super::{EncodeError, MacHalf, MacReg, MacShift, Mode, Modes, Size};
+use super::{check_int, int_spec, int_value};
+use super::{DR, AI, AP, AM, AD, AX, MS, ML, IM};
+use super::OperandForms::*;
+use super::OperandForm::*;
+
+/// A decoded operand.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Operand<'a> {
+    /// Addressing mode: register, memory location, or immediate.
+    Mode(Mode<'a>),
+
+    /// Control register.
+    CtlReg(super::CtlReg),
+
+    /// Debug module register.
+    DbgReg(super::DbgReg),
+
+    /// Condition code register.
+    Ccr,
+
+    /// Status register.
+    Sr,
+
+    /// Data/address register list.
+    RegList(super::RegList),
+
+    /// Condition code.
+    Cond(super::Condition),
+
+    /// Cache selector (2-bit encoding).
+    CacheSel(u8),
+
+    /// Data register pair Dw:Dx.
+    DataRegPair(super::DataReg, super::DataReg),
+
+    /// Branch displacement, relative to the address of the instruction
+    /// plus 2.
+    Branch(Expr<'a>),
+
+    /// Floating-point data register.
+    FpReg(super::FpReg),
+
+    /// Floating-point control register.
+    Fpcr,
+
+    /// Floating-point status register.
+    Fpsr,
+
+    /// Floating-point instruction address register.
+    Fpiar,
+
+    /// Floating-point data register list.
+    FpRegList(super::FpRegList),
+
+    /// Floating-point condition predicate.
+    FpCond(super::FpCondition),
+
+    /// MAC or EMAC register.
+    MacReg(MacReg),
+
+    /// Upper or lower word of a data or address register.
+    RegHalf(Mode<'a>, MacHalf),
+
+    /// MAC product shift factor.
+    MacShift(MacShift),
+
+    /// Memory operand, optionally masked by the MAC MASK register (&).
+    MaskedMode(Mode<'a>, bool),
+}
+
+static CACHE_NAMES: [&str; 4] = [
+    "", "dc", "ic", "bc"
+];
+
+impl<'a> Node for Operand<'a> {
+    /// Annotation type.
+    type Ann = ();
+
+    /// Gets the annotation for this node.
+    fn ann(&self) -> &() { &() }
+}
+
+impl<'a> Code for Operand<'a> {
+    /// Formats the value as code, using the given formatter and style.
+    ///
+    /// Branch displacements are written as-is; use `Instruction` formatting
+    /// to write branch targets as addresses.
+    ///
+    fn fmt<S: Style<()> + ?Sized>
+          (&self, f: &mut Formatter, s: &S) -> fmt::Result {
+        match *self {
+            Operand::Mode        (ref m)     => m.fmt(f, s),
+            Operand::CtlReg      (r)         => s.write_reg(f, &r.to_code(())),
+            Operand::DbgReg      (r)         => s.write_reg(f, &r.to_code(())),
+            Operand::Ccr                     => s.write_reg(f, &Reg::new("ccr")),
+            Operand::Sr                      => s.write_reg(f, &Reg::new("sr")),
+            Operand::RegList     (l)         => l.fmt(f, s),
+            Operand::Cond        (c)         => f.write_str(c.name_pref(s.prefer_aliases())),
+            Operand::CacheSel    (n)         => s.write_reg(f, &Reg::new(CACHE_NAMES[n as usize & 3])),
+            Operand::DataRegPair (w, x)      => {
+                s.write_reg(f, &w.to_code(()))?;
+                f.write_char(':')?;
+                s.write_reg(f, &x.to_code(()))
+            },
+            Operand::Branch      (ref e)     => e.fmt(f, s),
+            Operand::FpReg       (r)         => s.write_reg(f, &r.to_code(())),
+            Operand::Fpcr                    => s.write_reg(f, &Reg::new("fpcr")),
+            Operand::Fpsr                    => s.write_reg(f, &Reg::new("fpsr")),
+            Operand::Fpiar                   => s.write_reg(f, &Reg::new("fpiar")),
+            Operand::FpRegList   (l)         => l.fmt(f, s),
+            Operand::FpCond      (c)         => f.write_str(c.name()),
+            Operand::MacReg      (r)         => s.write_reg(f, &r.to_code(())),
+            Operand::RegHalf     (ref m, h)  => write!(f, "{}{}", m.styled(s), h.suffix()),
+            Operand::MacShift    (x)         => f.write_str(x.as_str()),
+            Operand::MaskedMode  (ref m, k)  => {
+                m.fmt(f, s)?;
+                if k { f.write_char('&')?; }
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Operand form combinations.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OperandForms {
+    /// No operands.
+    Nullary,
+
+    /// One operand.
+    Unary([OperandForm; 1]),
+
+    /// Two operands.
+    Binary([OperandForm; 2]),
+
+    /// Three operands.
+    Ternary([OperandForm; 3]),
+
+    /// MAC multiply-accumulate with load:
+    /// Ry, Rx, shift factor, memory operand, Rw.
+    MacLoad,
+
+    /// EMAC multiply-accumulate:
+    /// Ry, Rx, shift factor, accumulator.
+    EmacOp,
+
+    /// EMAC multiply-accumulate with load:
+    /// Ry, Rx, shift factor, memory operand, Rw, accumulator.
+    EmacLoad,
+}
+
+static MAC_LOAD: [OperandForm; 5] = [
+    MacSrc(16, 19, 22), MacSrc(28, 31, 23), OperandForm::MacShift(25),
+    MacMem(0, 21), SplitReg(9, 6),
+];
+
+static EMAC_OP: [OperandForm; 4] = [
+    MacSrc(0, 3, 22), MacSrc(9, 6, 23), OperandForm::MacShift(25),
+    AccSplit(7, 20),
+];
+
+static EMAC_LOAD: [OperandForm; 6] = [
+    MacSrc(16, 19, 22), MacSrc(28, 31, 23), OperandForm::MacShift(25),
+    MacMem(0, 21), SplitReg(9, 6), AccSplit(7, 20),
+];
+
+impl OperandForms {
+    /// Returns the operand forms as a slice.
+    pub fn as_slice(&self) -> &[OperandForm] {
+        match *self {
+            Nullary         => &[],
+            Unary   (ref o) => o,
+            Binary  (ref o) => o,
+            Ternary (ref o) => o,
+            MacLoad         => &MAC_LOAD,
+            EmacOp          => &EMAC_OP,
+            EmacLoad        => &EMAC_LOAD,
+        }
+    }
+
+    /// Decodes operands in these forms from the given opcode and extension
+    /// words.  Operand extension words are read from the given reader, in
+    /// operand order, and become pending.
+    pub fn decode<'a, R: DecodeRead>(&self, words: (u16, u16), size: Size, r: &mut R)
+                                    -> io::Result<Vec<Operand<'a>>> {
+        self.as_slice()
+            .iter()
+            .map(|o| o.decode(words, size, r))
+            .collect()
+    }
+
+    /// Encodes the given operands in these forms into the given opcode and
+    /// extension words.  Operand extension words are written to the given
+    /// writer, in operand order.
+    pub fn encode<W: EncodeWrite>(&self, operands: &[Operand], size: Size,
+                                  words: &mut (u16, u16), w: &mut W)
+                                 -> Result<(), EncodeError> {
+        let forms = self.as_slice();
+        if forms.len() != operands.len() {
+            return Err(EncodeError::Mismatch);
+        }
+
+        for (f, o) in forms.iter().zip(operands) {
+            f.encode(o, size, words, w)?;
+        }
+        Ok(())
+    }
+}
+
+/// Operand forms.
+///
+/// Bit positions 0-15 refer to the opcode word; positions 16-31 refer to the
+/// opcode extension word, if any.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OperandForm {
+    /// Addressing mode, restricted to the given modes (6 bits)
+    Mode(Modes, BitPos),
+
+    /// Modes d_ipmdx__nf_ (move destination) (6 bits; mode and register
+    /// subfields swapped)
+    DstMode(BitPos),
+
+    /// Data register (3 bits)
+    DataReg(BitPos),
+
+    /// Data register, repeated in a second field (3 + 3 bits)
+    DataRegDup(BitPos, BitPos),
+
+    /// Data register pair Dw:Dx (3 + 3 bits; Dw = Dx reserved)
+    DataRegPair(BitPos, BitPos),
+
+    /// Address register (3 bits)
+    AddrReg(BitPos),
+
+    /// Address register indirect (3 bits)
+    AddrRegInd(BitPos),
+
+    /// Data or address register (4 bits)
+    NormalReg(BitPos),
+
+    /// Control register (12 bits)
+    CtlReg(BitPos),
+
+    /// Debug control register (5 bits)
+    DbgReg(BitPos),
+
+    /// Condition code register (implicit)
+    Ccr,
+
+    /// Status register (implicit)
+    Sr,
+
+    /// Data/address register list (16 bits in extension word)
+    RegList,
+
+    /// Condition code (4 bits)
+    Cond(BitPos),
+
+    /// Branch condition code (4 bits; t and f reserved for bra and bsr)
+    BranchCond(BitPos),
+
+    /// Cache selector (2 bits; 0 reserved)
+    CacheSel(BitPos),
+
+    /// Immediate (16 or 32 bits in extension words)
+    Immediate,
+
+    /// Immediate word, regardless of operation size (16 bits in extension
+    /// word)
+    ImmWord,
+
+    /// Quick immediate (3 bits; 0 => 8)
+    Quick3(BitPos),
+
+    /// Quick immediate (3 bits; 0 => -1)
+    Quick3Neg(BitPos),
+
+    /// Quick immediate (4 bits unsigned)
+    Quick4(BitPos),
+
+    /// Quick immediate (8 bits signed)
+    Quick8(BitPos),
+
+    /// Branch displacement (8 bits signed; 0x00 and 0xFF reserved)
+    Branch8(BitPos),
+
+    /// Branch displacement (16 bits in extension word)
+    Branch16,
+
+    /// Branch displacement (32 bits in extension words)
+    Branch32,
+
+    /// Floating-point data register (3 bits)
+    FpReg(BitPos),
+
+    /// Floating-point control register (implicit)
+    Fpcr,
+
+    /// Floating-point status register (implicit)
+    Fpsr,
+
+    /// Floating-point instruction address register (implicit)
+    Fpiar,
+
+    /// Floating-point data register list (8 bits in extension word)
+    FpRegList,
+
+    /// Floating-point condition code (6 bits; 0x20-0x3F reserved)
+    FpCond(BitPos),
+
+    /// Data or address register, with separate register and D/A fields
+    /// (3 + 1 bits)
+    SplitReg(BitPos, BitPos),
+
+    /// MAC source register, with separate register, D/A, and U/L fields
+    /// (3 + 1 + 1 bits; U/L used by word operations only)
+    MacSrc(BitPos, BitPos, BitPos),
+
+    /// MAC product shift factor (2 bits; 0b10 reserved)
+    MacShift(BitPos),
+
+    /// Modes __ipmd______ (MAC load) with mask flag (6 + 1 bits)
+    MacMem(BitPos, BitPos),
+
+    /// EMAC accumulator (2 bits)
+    Acc(BitPos),
+
+    /// EMAC accumulator, with separate low and high bit fields (1 + 1 bits)
+    AccSplit(BitPos, BitPos),
+
+    /// MAC or EMAC register (implicit)
+    MacReg(MacReg),
+}
+
+const DST: Modes = DR | AI | AP | AM | AD | AX | MS | ML;
+
+impl OperandForm {
+    /// Decodes an operand in this form from the given opcode and extension
+    /// words.  Operand extension words, if any, are read from the given
+    /// reader and become pending.
+    pub fn decode<'a, R: DecodeRead>(self, words: (u16, u16), size: Size, r: &mut R)
+                                    -> io::Result<Operand<'a>> {
+        match self {
+            OperandForm::Mode(modes, pos) => {
+                Mode::decode_fields(
+                    field(words, pos + 3, 3) as u8,
+                    field(words, pos,     3) as u8,
+                    modes, size, r
+                ).map(Operand::Mode)
+            },
+            DstMode(pos) => {
+                Mode::decode_fields(
+                    field(words, pos,     3) as u8,
+                    field(words, pos + 3, 3) as u8,
+                    DST, size, r
+                ).map(Operand::Mode)
+            },
+            DataReg(pos) => {
+                let reg = super::DataReg::with_num(field(words, pos, 3) as u8);
+                Ok(Operand::Mode(Mode::Data(reg)))
+            },
+            DataRegDup(pos, dup) => {
+                let num = field(words, pos, 3);
+                if field(words, dup, 3) != num {
+                    return invalid("register fields differ");
+                }
+                let reg = super::DataReg::with_num(num as u8);
+                Ok(Operand::Mode(Mode::Data(reg)))
+            },
+            DataRegPair(w, x) => {
+                let (w, x) = (field(words, w, 3), field(words, x, 3));
+                if w == x {
+                    return invalid("register fields equal");
+                }
+                let w = super::DataReg::with_num(w as u8);
+                let x = super::DataReg::with_num(x as u8);
+                Ok(Operand::DataRegPair(w, x))
+            },
+            AddrReg(pos) => {
+                let reg = super::AddrReg::with_num(field(words, pos, 3) as u8);
+                Ok(Operand::Mode(Mode::Addr(reg)))
+            },
+            AddrRegInd(pos) => {
+                let reg = super::AddrReg::with_num(field(words, pos, 3) as u8);
+                Ok(Operand::Mode(Mode::AddrInd(reg)))
+            },
+            NormalReg(pos) => {
+                Ok(Operand::Mode(reg_mode(words, pos, pos + 3)))
+            },
+            CtlReg   (pos) => {
+                match super::CtlReg::decode(field(words, pos, 12)) {
+                    Some(r) => Ok(Operand::CtlReg(r)),
+                    None    => invalid("unknown control register"),
+                }
+            },
+            DbgReg   (pos) => {
+                match super::DbgReg::decode(field(words, pos, 5) as u8) {
+                    Some(r) => Ok(Operand::DbgReg(r)),
+                    None    => invalid("unknown debug register"),
+                }
+            },
+            Ccr            => Ok(Operand::Ccr),
+            Sr             => Ok(Operand::Sr),
+            RegList        => Ok(Operand::RegList(super::RegList::decode(words.1))),
+            Cond     (pos) => Ok(Operand::Cond(super::Condition::decode(field(words, pos, 4) as u8))),
+            BranchCond(pos) => {
+                match field(words, pos, 4) as u8 {
+                    0 | 1 => invalid("reserved branch condition"),
+                    c     => Ok(Operand::Cond(super::Condition::decode(c))),
+                }
+            },
+            CacheSel (pos) => {
+                match field(words, pos, 2) as u8 {
+                    0 => invalid("reserved cache selector"),
+                    c => Ok(Operand::CacheSel(c)),
+                }
+            },
+            Immediate => Mode::decode_fields(7, 4, IM, size,       r).map(Operand::Mode),
+            ImmWord   => Mode::decode_fields(7, 4, IM, Size::Word, r).map(Operand::Mode),
+            Quick3(pos) => {
+                let n = match field(words, pos, 3) { 0 => 8, n => n };
+                Ok(imm(n))
+            },
+            Quick3Neg(pos) => {
+                let n = match field(words, pos, 3) { 0 => -1, n => n as i8 };
+                Ok(imm(n))
+            },
+            Quick4(pos) => {
+                Ok(imm(field(words, pos, 4)))
+            },
+            Quick8(pos) => {
+                let n = field(words, pos, 8) as u8 as i8;
+                Ok(imm(n))
+            },
+            Branch8(pos) => {
+                match field(words, pos, 8) as u8 {
+                    0x00 | 0xFF => invalid("reserved branch displacement"),
+                    n           => Ok(branch(n as i8)),
+                }
+            },
+            Branch16 => {
+                Ok(branch(r.read_i16()?))
+            },
+            Branch32 => {
+                Ok(branch(r.read_i32()?))
+            },
+            FpReg(pos) => {
+                let reg = super::FpReg::with_num(field(words, pos, 3) as u8);
+                Ok(Operand::FpReg(reg))
+            },
+            Fpcr      => Ok(Operand::Fpcr),
+            Fpsr      => Ok(Operand::Fpsr),
+            Fpiar     => Ok(Operand::Fpiar),
+            FpRegList => Ok(Operand::FpRegList(super::FpRegList::decode(words.1 as u8))),
+            FpCond(pos) => {
+                match field(words, pos, 6) as u8 {
+                    c if c < 0x20 => Ok(Operand::FpCond(super::FpCondition::decode(c))),
+                    _             => invalid("reserved floating-point condition"),
+                }
+            },
+            SplitReg(reg, da) => {
+                Ok(Operand::Mode(reg_mode(words, reg, da)))
+            },
+            MacSrc(reg, da, ul) => {
+                let mode = reg_mode(words, reg, da);
+                Ok(match size {
+                    Size::Word => Operand::RegHalf(mode, MacHalf::decode(field(words, ul, 1))),
+                    _          => Operand::Mode(mode),
+                })
+            },
+            OperandForm::MacShift(pos) => {
+                match MacShift::decode(field(words, pos, 2)) {
+                    Some(s) => Ok(Operand::MacShift(s)),
+                    None    => invalid("reserved shift factor"),
+                }
+            },
+            MacMem(pos, mask) => {
+                let mode = Mode::decode_fields(
+                    field(words, pos + 3, 3) as u8,
+                    field(words, pos,     3) as u8,
+                    AI | AP | AM | AD, size, r
+                )?;
+                Ok(Operand::MaskedMode(mode, field(words, mask, 1) != 0))
+            },
+            Acc(pos) => {
+                Ok(Operand::MacReg(MacReg::acc(field(words, pos, 2) as u8)))
+            },
+            AccSplit(lo, hi) => {
+                let n = field(words, hi, 1) << 1 | field(words, lo, 1);
+                Ok(Operand::MacReg(MacReg::acc(n as u8)))
+            },
+            OperandForm::MacReg(reg) => Ok(Operand::MacReg(reg)),
+        }
+    }
+
+    /// Encodes the given operand in this form into the given opcode and
+    /// extension words.  Operand extension words, if any, are written to the
+    /// given writer.  An operand of another kind yields `Mismatch`.
+    pub fn encode<W: EncodeWrite>(self, operand: &Operand, size: Size,
+                                  words: &mut (u16, u16), w: &mut W)
+                                 -> Result<(), EncodeError> {
+        match (self, operand) {
+            (OperandForm::Mode(modes, pos), Operand::Mode(m)) if m.kind() & modes != 0 => {
+                let (mode, reg) = m.encode_fields(size, w)?;
+                put(words, pos + 3, 3, mode as u16);
+                put(words, pos,     3, reg  as u16);
+            },
+            (DstMode(pos), Operand::Mode(m)) if m.kind() & DST != 0 => {
+                let (mode, reg) = m.encode_fields(size, w)?;
+                put(words, pos,     3, mode as u16);
+                put(words, pos + 3, 3, reg  as u16);
+            },
+            (DataReg(pos), &Operand::Mode(Mode::Data(r))) => {
+                put(words, pos, 3, r.num() as u16);
+            },
+            (DataRegDup(pos, dup), &Operand::Mode(Mode::Data(r))) => {
+                put(words, pos, 3, r.num() as u16);
+                put(words, dup, 3, r.num() as u16);
+            },
+            (DataRegPair(wp, xp), &Operand::DataRegPair(rw, rx)) if rw != rx => {
+                put(words, wp, 3, rw.num() as u16);
+                put(words, xp, 3, rx.num() as u16);
+            },
+            (AddrReg(pos), &Operand::Mode(Mode::Addr(r))) |
+            (AddrRegInd(pos), &Operand::Mode(Mode::AddrInd(r))) => {
+                put(words, pos, 3, r.num() as u16);
+            },
+            (NormalReg(pos), Operand::Mode(m)) => {
+                put_reg_mode(words, m, pos, pos + 3)?;
+            },
+            (CtlReg(pos), &Operand::CtlReg(r)) => {
+                put(words, pos, 12, r.encode());
+            },
+            (DbgReg(pos), &Operand::DbgReg(r)) => {
+                put(words, pos, 5, r.encode() as u16);
+            },
+            (Ccr,   &Operand::Ccr  ) |
+            (Sr,    &Operand::Sr   ) |
+            (Fpcr,  &Operand::Fpcr ) |
+            (Fpsr,  &Operand::Fpsr ) |
+            (Fpiar, &Operand::Fpiar) => {},
+            (RegList, &Operand::RegList(l)) => {
+                words.1 = l.encode();
+            },
+            (Cond(pos), &Operand::Cond(c)) => {
+                put(words, pos, 4, c.encode() as u16);
+            },
+            (BranchCond(pos), &Operand::Cond(c)) => {
+                match c.encode() {
+                    0 | 1 => return reserved("branch condition", c.encode()),
+                    n     => put(words, pos, 4, n as u16),
+                }
+            },
+            (CacheSel(pos), &Operand::CacheSel(n)) => {
+                let n = check_int("cache selector", int_spec(2, false), &BigInt::from(n))?;
+                if n == 0 { return reserved("cache selector", n); }
+                put(words, pos, 2, n as u16);
+            },
+            (Immediate, &Operand::Mode(ref m @ Mode::Imm(_))) => {
+                m.encode_fields(size, w)?;
+            },
+            (ImmWord, &Operand::Mode(ref m @ Mode::Imm(_))) => {
+                m.encode_fields(Size::Word, w)?;
+            },
+            (Quick3(pos), &Operand::Mode(Mode::Imm(ref e))) => {
+                // 1 to 8, with 8 encoded as 0
+                let v = int_value("quick immediate", e)?;
+                let n = check_int("quick immediate", int_spec(3, false), &(v - 1))
+                    .map_err(|_| out_of_range("quick immediate", v, 1, 8))?;
+                put(words, pos, 3, (n as u16 + 1) & 7);
+            },
+            (Quick3Neg(pos), &Operand::Mode(Mode::Imm(ref e))) => {
+                // -1 and 1 to 7, with -1 encoded as 0
+                let v = int_value("quick immediate", e)?;
+                let n = if *v == BigInt::from(-1) {
+                    0
+                } else {
+                    match check_int("quick immediate", int_spec(3, false), v) {
+                        Ok(0) | Err(_) => return Err(out_of_range("quick immediate", v, -1, 7)),
+                        Ok(n)          => n,
+                    }
+                };
+                put(words, pos, 3, n as u16);
+            },
+            (Quick4(pos), &Operand::Mode(Mode::Imm(ref e))) => {
+                let v = int_value("quick immediate", e)?;
+                let n = check_int("quick immediate", int_spec(4, false), v)?;
+                put(words, pos, 4, n as u16);
+            },
+            (Quick8(pos), &Operand::Mode(Mode::Imm(ref e))) => {
+                let v = int_value("quick immediate", e)?;
+                let n = check_int("quick immediate", int_spec(8, true), v)?;
+                put(words, pos, 8, n as u16);
+            },
+            // A displacement that is not constant is left zero, for a
+            // relocation to supply
+            (Branch8(_), Operand::Branch(e)) if !is_int(e) => {},
+            (Branch16,   Operand::Branch(e)) if !is_int(e) => w.write_u16(0)?,
+            (Branch32,   Operand::Branch(e)) if !is_int(e) => w.write_u32(0)?,
+            (Branch8(pos), Operand::Branch(e)) => {
+                let v = int_value("branch displacement", e)?;
+                match check_int("branch displacement", int_spec(8, true), v)? {
+                    0x00 | 0xFF => return reserved("branch displacement", v.clone()),
+                    n           => put(words, pos, 8, n as u16),
+                }
+            },
+            (Branch16, Operand::Branch(e)) => {
+                let v = int_value("branch displacement", e)?;
+                w.write_u16(check_int("branch displacement", int_spec(16, true), v)? as u16)?;
+            },
+            (Branch32, Operand::Branch(e)) => {
+                let v = int_value("branch displacement", e)?;
+                w.write_u32(check_int("branch displacement", int_spec(32, true), v)? as u32)?;
+            },
+            (FpReg(pos), &Operand::FpReg(r)) => {
+                put(words, pos, 3, r.num() as u16);
+            },
+            (FpRegList, &Operand::FpRegList(l)) => {
+                put(words, 16, 8, l.encode() as u16);
+            },
+            (FpCond(pos), &Operand::FpCond(c)) => {
+                put(words, pos, 6, c.encode() as u16);
+            },
+            (SplitReg(reg, da), Operand::Mode(m)) => {
+                put_reg_mode(words, m, reg, da)?;
+            },
+            (MacSrc(reg, da, ul), &Operand::RegHalf(ref m, h)) if size == Size::Word => {
+                put_reg_mode(words, m, reg, da)?;
+                put(words, ul, 1, h.encode());
+            },
+            (MacSrc(reg, da, _), Operand::Mode(m)) if size != Size::Word => {
+                put_reg_mode(words, m, reg, da)?;
+            },
+            (OperandForm::MacShift(pos), &Operand::MacShift(s)) => {
+                put(words, pos, 2, s.encode());
+            },
+            (MacMem(pos, mask), &Operand::MaskedMode(ref m, masked))
+                    if m.kind() & (AI | AP | AM | AD) != 0 => {
+                let (mode, reg) = m.encode_fields(size, w)?;
+                put(words, pos + 3, 3, mode as u16);
+                put(words, pos,     3, reg  as u16);
+                put(words, mask,    1, masked as u16);
+            },
+            (Acc(pos), &Operand::MacReg(r)) if r.acc_num().is_some() => {
+                put(words, pos, 2, r.acc_num().unwrap() as u16);
+            },
+            (AccSplit(lo, hi), &Operand::MacReg(r)) if r.acc_num().is_some() => {
+                let n = r.acc_num().unwrap() as u16;
+                put(words, lo, 1, n & 1);
+                put(words, hi, 1, n >> 1);
+            },
+            (OperandForm::MacReg(reg), &Operand::MacReg(r)) if r == reg => {},
+            _ => return Err(EncodeError::Mismatch),
+        }
+        Ok(())
+    }
+}
+
+/// Extracts the bit field of the given width at the given position.
+#[inline]
+fn field(words: (u16, u16), pos: BitPos, width: u8) -> u16 {
+    let bits = (words.1 as u32) << 16 | words.0 as u32;
+    ((bits >> pos) & ((1 << width) - 1)) as u16
+}
+
+/// Stores the bit field of the given width at the given position.
+#[inline]
+fn put(words: &mut (u16, u16), pos: BitPos, width: u8, value: u16) {
+    let mask = ((1u32 << width) - 1) << pos;
+    let bits = (words.1 as u32) << 16 | words.0 as u32;
+    let bits = bits & !mask | ((value as u32) << pos) & mask;
+    *words = (bits as u16, (bits >> 16) as u16);
+}
+
+/// Encodes a data or address register into the given register and D/A
+/// fields.
+fn put_reg_mode(words: &mut (u16, u16), mode: &Mode, reg: BitPos, da: BitPos)
+               -> Result<(), EncodeError> {
+    let (num, da_bit) = match *mode {
+        Mode::Data(r) => (r.num(), 0),
+        Mode::Addr(r) => (r.num(), 1),
+        _             => return Err(EncodeError::Mismatch),
+    };
+    put(words, reg, 3, num as u16);
+    put(words, da,  1, da_bit);
+    Ok(())
+}
+
+fn is_int(e: &Expr) -> bool {
+    matches!(*e, Expr::Int(_))
+}
+
+fn reserved<V: Into<BigInt>>(field: &'static str, value: V) -> Result<(), EncodeError> {
+    Err(EncodeError::Reserved { field, value: value.into() })
+}
+
+fn out_of_range(field: &'static str, value: &BigInt, min: i64, max: i64) -> EncodeError {
+    EncodeError::OutOfRange {
+        field,
+        value: value.clone(),
+        min:   BigInt::from(min),
+        max:   BigInt::from(max),
+    }
+}
+
+/// Decodes a data or address register from the given register and D/A
+/// fields.
+#[inline]
+fn reg_mode<'a>(words: (u16, u16), reg: BitPos, da: BitPos) -> Mode<'a> {
+    let num = field(words, reg, 3) as u8;
+    match field(words, da, 1) {
+        0 => Mode::Data(super::DataReg::with_num(num)),
+        _ => Mode::Addr(super::AddrReg::with_num(num)),
+    }
+}
+
+#[inline]
+fn branch<'a, V: Into<BigInt>>(v: V) -> Operand<'a> {
+    Operand::Branch(Expr::Int(Int::new(v)))
+}
+
+#[inline]
+fn imm<'a, V: Into<BigInt>>(v: V) -> Operand<'a> {
+    Operand::Mode(Mode::Imm(Expr::Int(Int::new(v))))
+}
@@ -0,0 +1,341 @@
+// ColdFire Register Lists
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt::{self, Formatter};
+use std::ops::BitOr;
+
+use aex::ast::{Expr, Int, Node, Reg};
+use aex::fmt::{Code, Style, ToCode};
+
+use super::{AddrReg, DataReg, FpReg};
+
+/// A list of data and address registers, as transferred by `movem`.
+///
+/// This is a bitmask of the numbered registers:
+///
+/// ```text
+/// bits: [15 .. 08] [07 .. 00]
+/// regs:  a7 .. a0   d7 .. d0
+/// ```
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct RegList(pub u16);
+
+impl RegList {
+    /// Returns the register list with the given 16-bit mask encoding.
+    #[inline]
+    pub fn decode(bits: u16) -> Self {
+        RegList(bits)
+    }
+
+    /// Returns the 16-bit mask encoding of the register list.
+    #[inline]
+    pub fn encode(self) -> u16 {
+        self.0
+    }
+
+    /// Returns whether the list contains no registers.
+    #[inline]
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the number of registers in the list.
+    #[inline]
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Parses a register list of the form `d0-d3/a2/a5` or `d0-d3,a2`.
+    ///
+    /// Register names may have a `%` prefix.  A range must begin and end
+    /// with registers of the same kind, in ascending order.
+    ///
+    pub fn parse(s: &str) -> Option<Self> {
+        parse_bits(s, |s| {
+            DataReg::with_name(s).map(|r| r.num()).or_else(||
+            AddrReg::with_name(s).map(|r| r.num() + 8))
+        }).map(RegList)
+    }
+}
+
+/// A list of floating-point data registers, as transferred by `fmovem`.
+///
+/// This is a bitmask of the numbered registers, in reverse order:
+///
+/// ```text
+/// bits: [07 .. 00]
+/// regs: fp0 .. fp7
+/// ```
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct FpRegList(pub u8);
+
+impl FpRegList {
+    /// Returns the register list with the given 8-bit mask encoding.
+    #[inline]
+    pub fn decode(bits: u8) -> Self {
+        FpRegList(bits)
+    }
+
+    /// Returns the 8-bit mask encoding of the register list.
+    #[inline]
+    pub fn encode(self) -> u8 {
+        self.0
+    }
+
+    /// Returns whether the list contains no registers.
+    #[inline]
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the number of registers in the list.
+    #[inline]
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Parses a register list of the form `fp0-fp3/fp5` or `fp0-fp3,fp5`.
+    ///
+    /// Register names may have a `%` prefix.  A range must be in ascending
+    /// order.
+    ///
+    pub fn parse(s: &str) -> Option<Self> {
+        parse_bits(s, |s| FpReg::with_name(s).map(|r| r.num()))
+            .map(|bits| FpRegList((bits as u8).reverse_bits()))
+    }
+}
+
+// Parses a register list into a mask with bit n set for register n, using
+// the given function to number each register.
+fn parse_bits<F>(s: &str, reg: F) -> Option<u16> where F: Fn(&str) -> Option<u8> {
+    let reg = |s: &str| {
+        let s = s.trim();
+        reg(s.strip_prefix('%').unwrap_or(s))
+    };
+
+    let mut bits = 0;
+
+    for item in s.split(&['/', ','][..]) {
+        let mut ends = item.splitn(2, '-');
+        let first    = reg(ends.next()?)?;
+        let last     = match ends.next() {
+            Some(r) => reg(r)?,
+            None    => first,
+        };
+
+        // Registers must be of the same kind (bit 3) and ascending
+        if (first ^ last) & 8 != 0 || first > last { return None; }
+
+        for n in first..last + 1 {
+            bits |= 1 << n;
+        }
+    }
+
+    Some(bits)
+}
+
+// Appends the runs of set bits in the given 8-bit mask to the given list of
+// register ranges.
+fn push_runs<R, F>(items: &mut Vec<(Reg<'static, ()>, Option<Reg<'static, ()>>)>,
+                   bits: u8, reg: F)
+where R: ToCode<(), Output=Reg<'static, ()>>, F: Fn(u8) -> R {
+    let mut n = 0;
+    while n < 8 {
+        if bits & 1 << n == 0 { n += 1; continue; }
+
+        let first = n;
+        while n < 8 && bits & 1 << n != 0 { n += 1; }
+        let last = n - 1;
+
+        items.push((
+            reg(first).to_code(()),
+            if last > first { Some(reg(last).to_code(())) } else { None }
+        ));
+    }
+}
+
+impl From<DataReg> for RegList {
+    fn from(r: DataReg) -> Self { RegList(0x0001 << r.num()) }
+}
+
+impl From<AddrReg> for RegList {
+    fn from(r: AddrReg) -> Self { RegList(0x0100 << r.num()) }
+}
+
+impl<R: Into<RegList>> BitOr<R> for DataReg {
+    type Output = RegList;
+    fn bitor(self, r: R) -> RegList { RegList::from(self) | r.into() }
+}
+
+impl<R: Into<RegList>> BitOr<R> for AddrReg {
+    type Output = RegList;
+    fn bitor(self, r: R) -> RegList { RegList::from(self) | r.into() }
+}
+
+impl<R: Into<RegList>> BitOr<R> for RegList {
+    type Output = RegList;
+    fn bitor(self, r: R) -> RegList { RegList(self.0 | r.into().0) }
+}
+
+impl From<FpReg> for FpRegList {
+    fn from(r: FpReg) -> Self { FpRegList(0x80 >> r.num()) }
+}
+
+impl<R: Into<FpRegList>> BitOr<R> for FpReg {
+    type Output = FpRegList;
+    fn bitor(self, r: R) -> FpRegList { FpRegList::from(self) | r.into() }
+}
+
+impl<R: Into<FpRegList>> BitOr<R> for FpRegList {
+    type Output = FpRegList;
+    fn bitor(self, r: R) -> FpRegList { FpRegList(self.0 | r.into().0) }
+}
+
+impl Node for RegList {
+    /// Annotation type.
+    type Ann = ();
+
+    /// Gets the annotation for this node.
+    fn ann(&self) -> &() { &() }
+}
+
+impl Code for RegList {
+    /// Formats the value as code, using the given formatter and style.
+    ///
+    /// Runs of consecutive registers are written as ranges.  An empty list
+    /// is written as an immediate zero mask.
+    ///
+    fn fmt<S: Style<()> + ?Sized>
+          (&self, f: &mut Formatter, s: &S) -> fmt::Result {
+        if self.is_empty() {
+            return s.write_imm(f, &Expr::Int(Int::new(0)));
+        }
+
+        let mut items = vec![];
+        push_runs(&mut items, self.0 as u8,        DataReg::with_num);
+        push_runs(&mut items, (self.0 >> 8) as u8, AddrReg::with_num);
+        s.write_reg_list(f, &items)
+    }
+}
+
+impl Node for FpRegList {
+    /// Annotation type.
+    type Ann = ();
+
+    /// Gets the annotation for this node.
+    fn ann(&self) -> &() { &() }
+}
+
+impl Code for FpRegList {
+    /// Formats the value as code, using the given formatter and style.
+    ///
+    /// Runs of consecutive registers are written as ranges.  An empty list
+    /// is written as an immediate zero mask.
+    ///
+    fn fmt<S: Style<()> + ?Sized>
+          (&self, f: &mut Formatter, s: &S) -> fmt::Result {
+        if self.is_empty() {
+            return s.write_imm(f, &Expr::Int(Int::new(0)));
+        }
+
+        let mut items = vec![];
+        push_runs(&mut items, self.0.reverse_bits(), FpReg::with_num);
+        s.write_reg_list(f, &items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aex::fmt::{AttStyle, IntelStyle, MitStyle, ToStyled};
+    use super::*;
+    use super::super::*;
+
+    #[test]
+    fn bit_or() {
+        assert_eq!( D0 | D3 | A1, RegList(0x0209) );
+        assert_eq!( SP | D7,      RegList(0x8080) );
+    }
+
+    #[test]
+    fn encode() {
+        assert_eq!( RegList::decode(0x1234).encode(), 0x1234 );
+        assert_eq!( RegList(0x00FF).len(), 8 );
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!( RegList::parse("d0-d3/a2/a5"),     Some(D0 | D1 | D2 | D3 | A2 | A5) );
+        assert_eq!( RegList::parse("d0-d3,a2"),        Some(D0 | D1 | D2 | D3 | A2) );
+        assert_eq!( RegList::parse("%d7 / %a0-%sp"),   Some(RegList(0xFF80)) );
+        assert_eq!( RegList::parse("a6"),              Some(RegList::from(FP)) );
+        assert_eq!( RegList::parse("d0-a3"),           None );
+        assert_eq!( RegList::parse("d3-d0"),           None );
+        assert_eq!( RegList::parse("d0/"),             None );
+        assert_eq!( RegList::parse("d0-d1-d2"),        None );
+    }
+
+    #[test]
+    fn fmt() {
+        let l = D0 | D3 | D6 | D7 | A1 | A2 | A3;
+
+        assert_eq!( l.styled(&AttStyle::default()).to_string(), "%d0/%d3/%d6-%d7/%a1-%a3" );
+        assert_eq!( l.styled(&MitStyle::default()).to_string(), "%d0/%d3/%d6-%d7/%a1-%a3" );
+        assert_eq!( l.styled(&IntelStyle       ).to_string(), "d0,d3,d6-d7,a1-a3"       );
+    }
+
+    #[test]
+    fn fmt_all() {
+        let l = RegList(0xFFFF);
+
+        assert_eq!( l.styled(&AttStyle::default()).to_string(), "%d0-%d7/%a0-%sp" );
+    }
+
+    #[test]
+    fn fmt_empty() {
+        assert_eq!( RegList(0).styled(&AttStyle::default()).to_string(), "#0" );
+    }
+
+    #[test]
+    fn parse_fmt() {
+        let l = RegList(0xA5C3);
+        let s = l.styled(&IntelStyle).to_string();
+
+        assert_eq!( RegList::parse(&s), Some(l) );
+    }
+
+    #[test]
+    fn fp_parse() {
+        assert_eq!( FpRegList::parse("fp0-fp3/fp5"),   Some(FP0 | FP1 | FP2 | FP3 | FP5) );
+        assert_eq!( FpRegList::parse("%fp7, %fp0"),    Some(FpRegList(0x81)) );
+        assert_eq!( FpRegList::parse("fp3-fp1"),       None );
+        assert_eq!( FpRegList::parse("fp0-d1"),        None );
+        assert_eq!( (FP0 | FP7).len(),                 2 );
+    }
+
+    #[test]
+    fn fp_fmt() {
+        let l = FP0 | FP1 | FP2 | FP3 | FP5;
+
+        assert_eq!( l.styled(&AttStyle::default()).to_string(), "%fp0-%fp3/%fp5" );
+        assert_eq!( l.styled(&IntelStyle       ).to_string(), "fp0-fp3,fp5"     );
+        assert_eq!( FpRegList(0).styled(&AttStyle::default()).to_string(), "#0" );
+        assert_eq!( FpRegList::parse(&l.styled(&IntelStyle).to_string()), Some(l) );
+    }
+}
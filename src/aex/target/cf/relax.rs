@@ -0,0 +1,114 @@
+// ColdFire Branch Relaxation
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use num::BigInt;
+
+use super::{EncodeError, Flags, Size, CF_B_UP};
+
+/// Branch relaxer.
+///
+/// Chooses the size of a branch from its displacement.  The assembler starts
+/// each branch with the shortest size and grows branches until every
+/// displacement fits.  Branches only grow, so relaxation always terminates,
+/// and the result depends only on the input.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Relaxer {
+    long: bool,
+}
+
+impl Relaxer {
+    /// Creates a branch relaxer for the given ISA profile.  Long branches
+    /// are used only if the profile includes ISA_B or later.
+    pub fn new(isa: Flags) -> Self {
+        Relaxer { long: isa & CF_B_UP != 0 }
+    }
+
+    /// Returns the smallest branch size that can encode the given
+    /// displacement.
+    pub fn size_for(&self, disp: i64) -> Result<Size, EncodeError> {
+        // Byte displacements 0x00 and 0xFF select the word and long forms
+        if (-0x80..=0x7F).contains(&disp) && disp != 0 && disp != -1 {
+            Ok(Size::Byte)
+        } else if (-0x8000..=0x7FFF).contains(&disp) {
+            Ok(Size::Word)
+        } else if self.long && (-0x8000_0000..=0x7FFF_FFFF).contains(&disp) {
+            Ok(Size::Long)
+        } else {
+            Err(out_of_range(disp, if self.long { Size::Long } else { Size::Word }))
+        }
+    }
+}
+
+/// Returns the length in bytes of a branch of the given size.
+#[inline]
+pub fn branch_len(size: Size) -> u64 {
+    match size {
+        Size::Byte => 2,
+        Size::Word => 4,
+        _          => 6,
+    }
+}
+
+fn out_of_range(disp: i64, size: Size) -> EncodeError {
+    let bits = match size { Size::Byte => 8, Size::Word => 16, _ => 32 };
+    EncodeError::OutOfRange {
+        field: "branch displacement",
+        value: BigInt::from(disp),
+        min:   -(BigInt::from(1) << (bits - 1)),
+        max:    (BigInt::from(1) << (bits - 1)) - 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::*;
+
+    #[test]
+    fn size_for() {
+        let r = Relaxer::new(CF_A);
+        assert_eq!(r.size_for(-4).unwrap(),      Size::Byte);
+        assert_eq!(r.size_for(0x7F).unwrap(),    Size::Byte);
+        assert_eq!(r.size_for(0x80).unwrap(),    Size::Word);
+        assert_eq!(r.size_for(-0x8000).unwrap(), Size::Word);
+    }
+
+    #[test]
+    fn size_for_reserved_disp() {
+        // Byte displacements 0 and -1 are reserved
+        let r = Relaxer::new(CF_A);
+        assert_eq!(r.size_for(0).unwrap(),  Size::Word);
+        assert_eq!(r.size_for(-1).unwrap(), Size::Word);
+    }
+
+    #[test]
+    fn size_for_long() {
+        assert_eq!(Relaxer::new(CF_B).size_for(0x10000).unwrap(), Size::Long);
+
+        let e = Relaxer::new(CF_A).size_for(0x10000).unwrap_err();
+        assert_eq!(e.to_string(), "branch displacement 65536 out of range (-32768 to 32767)");
+    }
+
+    #[test]
+    fn lengths() {
+        assert_eq!(branch_len(Size::Byte), 2);
+        assert_eq!(branch_len(Size::Word), 4);
+        assert_eq!(branch_len(Size::Long), 6);
+    }
+}
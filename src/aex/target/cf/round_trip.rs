@@ -0,0 +1,240 @@
+// ColdFire Decode/Encode Round Trips
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+
+use aex::io::{DecodeReader, SliceReader};
+use aex::util::BE;
+
+use super::{Dispatch, Flags, Instruction, Mnemonic, Opcode};
+
+/// Extension word patterns that follow each opcode word in a round trip.
+/// Together they exercise zero, negative, and positive displacements and
+/// both index register sizes.
+pub static EXT_PATTERNS: [u16; 5] = [0x0000, 0xFFFF, 0x1234, 0x0800, 0x00FE];
+
+/// Number of extension words to follow each opcode word.  The longest
+/// instruction has two words of opcode and two of each operand.
+const EXT_WORDS: usize = 5;
+
+/// Summary of a decode/encode round trip over an opcodes table.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct RoundTrip {
+    /// Number of inputs decoded to an instruction.
+    pub decoded: usize,
+
+    /// Number of inputs that did not decode to an instruction.
+    pub undecodable: usize,
+
+    /// Number of decoded instructions encoded back to their bytes.
+    pub matched: usize,
+
+    /// Number of decoded instructions the encoder could not encode, by
+    /// instruction name.
+    pub unsupported: BTreeMap<Mnemonic, usize>,
+
+    /// Decoded instructions encoded to bytes other than their own.
+    pub mismatches: Vec<Mismatch>,
+}
+
+/// A decoded instruction that did not encode back to its own bytes.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Mismatch {
+    /// Bytes from which the instruction was decoded.
+    pub input: Vec<u8>,
+
+    /// Bytes to which the instruction was encoded.
+    pub output: Vec<u8>,
+}
+
+impl RoundTrip {
+    /// Returns the number of decoded instructions the encoder could not
+    /// encode.
+    pub fn unsupported_count(&self) -> usize {
+        self.unsupported.values().sum()
+    }
+
+    /// Returns whether every instruction the encoder supports encoded back
+    /// to its own bytes.
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Decodes every opcode word, followed by each of the given extension word
+/// patterns, using the opcodes of the given table that are supported by the
+/// given ISA profile.  Then encodes each decoded instruction with the given
+/// encoder and compares the result to the decoded bytes.
+///
+/// The encoder returns an error for instructions it does not support.
+/// Those are tallied as unsupported rather than as mismatches.
+///
+pub fn round_trip<F, E>(opcodes: &'static [Opcode], isa: Flags,
+                        patterns: &[u16], mut encode: F) -> RoundTrip
+where F: FnMut(&Instruction) -> Result<Vec<u8>, E> {
+    let dispatch   = Dispatch::new(opcodes, isa);
+    let mut result = RoundTrip::default();
+    let mut bytes  = Vec::with_capacity(2 * (1 + EXT_WORDS));
+
+    for word in 0..0x10000u32 {
+        let word = word as u16;
+
+        for &p in patterns {
+            bytes.clear();
+            for &w in [word].iter().chain([p; EXT_WORDS].iter()) {
+                bytes.push((w >> 8) as u8);
+                bytes.push(w        as u8);
+            }
+
+            let mut r = DecodeReader::new(SliceReader::new(&bytes), BE, 0);
+            let i = match dispatch.decode(&mut r) {
+                Ok(Some(i)) => i,
+                _           => { result.undecodable += 1; continue; },
+            };
+
+            result.decoded += 1;
+
+            match encode(&i) {
+                Ok(ref out) if *out == i.bytes => {
+                    result.matched += 1;
+                },
+                Ok(out) => {
+                    result.mismatches.push(Mismatch { input: i.bytes.clone(), output: out });
+                },
+                Err(_) => {
+                    *result.unsupported.entry(i.name).or_insert(0) += 1;
+                },
+            }
+
+            // Without extension words, other patterns yield the same result
+            if i.bytes.len() == 2 { break; }
+        }
+    }
+
+    result
+}
+
+impl Display for RoundTrip {
+    /// Formats the summary, one count per line, followed by unsupported
+    /// counts by instruction name.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "decoded:     {}", self.decoded)?;
+        writeln!(f, "undecodable: {}", self.undecodable)?;
+        writeln!(f, "matched:     {}", self.matched)?;
+        writeln!(f, "mismatched:  {}", self.mismatches.len())?;
+        writeln!(f, "unsupported: {}", self.unsupported_count())?;
+
+        for (name, count) in &self.unsupported {
+            writeln!(f, "    {:<12} {}", name.as_str(), count)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::*;
+
+    static NOP_RTS: [Opcode; 2] = [
+        Opcode {
+            name:  Mnemonic::Nop,
+            size:  Size::Zero,
+            bits:  (0x4E71, 0),
+            mask:  (0xFFFF, 0),
+            args:  OperandForms::Nullary,
+            flags: CF_A,
+        },
+        Opcode {
+            name:  Mnemonic::Rts,
+            size:  Size::Zero,
+            bits:  (0x4E75, 0),
+            mask:  (0xFFFF, 0),
+            args:  OperandForms::Nullary,
+            flags: CF_A,
+        },
+    ];
+
+    #[test]
+    fn round_trip_counts() {
+        let r = round_trip(&NOP_RTS, CF_A, &EXT_PATTERNS, |i| match i.name {
+            Mnemonic::Nop => Ok(vec![0x4E, 0x71]),
+            _             => Err(()),
+        });
+
+        assert_eq!(r.decoded,     2);
+        assert_eq!(r.undecodable, 0xFFFE * EXT_PATTERNS.len());
+        assert_eq!(r.matched,     1);
+        assert_eq!(r.unsupported_count(), 1);
+        assert_eq!(r.unsupported.get(&Mnemonic::Rts), Some(&1));
+        assert!(r.is_ok());
+    }
+
+    #[test]
+    fn round_trip_mismatch() {
+        let r = round_trip(&NOP_RTS, CF_A, &EXT_PATTERNS, |_| {
+            Ok::<_, ()>(vec![0x4E, 0x71])
+        });
+
+        assert!(!r.is_ok());
+        assert_eq!(r.mismatches, vec![
+            Mismatch { input: vec![0x4E, 0x75], output: vec![0x4E, 0x71] },
+        ]);
+    }
+
+    #[test]
+    fn display() {
+        let r = round_trip(&NOP_RTS, CF_A, &EXT_PATTERNS, |_| Err(()));
+
+        assert_eq!(r.to_string(), "\
+decoded:     2
+undecodable: 327670
+matched:     0
+mismatched:  0
+unsupported: 2
+    nop          1
+    rts          1
+");
+    }
+
+    #[test]
+    fn round_trip_table() {
+        let profiles = [
+            CF_A,
+            CF_A2 | CF_MAC,
+            CF_B  | CF_MAC,
+            CF_C  | CF_FPU | CF_EMAC,
+            CF_ALL,
+        ];
+
+        for &isa in &profiles {
+            let enc = Encoder::new(OPCODES, isa);
+            let r   = round_trip(OPCODES, isa, &EXT_PATTERNS, |i| enc.encode_instruction(i));
+
+            let mismatches: String = r.mismatches.iter().take(50)
+                .map(|m| format!("{:02x?} => {:02x?}\n", m.input, m.output))
+                .collect();
+
+            assert!(r.decoded > 0, "ISA {:#x}:\n{}", isa, r);
+            assert_eq!(r.unsupported_count(), 0, "ISA {:#x}:\n{}", isa, r);
+            assert!(r.is_ok(), "ISA {:#x}:\n{}{}", isa, r, mismatches);
+        }
+    }
+}
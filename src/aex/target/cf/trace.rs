@@ -0,0 +1,375 @@
+// ColdFire Recursive-Traversal Disassembler
+//
+// This file is part of AEx.
+// Copyright (C) 2017 Jeffrey Sharp
+//
+// AEx is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published
+// by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// AEx is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See
+// the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with AEx.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::collections::btree_map::Values;
+use std::io::{self, Write};
+use std::ops::Range;
+use num::ToPrimitive;
+
+use aex::ast::Expr;
+use aex::fmt::Style;
+use aex::io::{DecodeRead, DecodeReader, SliceReader};
+use aex::util::ByteOrder;
+
+use super::{Dispatch, Flags, Instruction, Item, Line, Mode, Opcode, Operand};
+use super::write_lines;
+use super::Mnemonic::*;
+
+/// Classification of a byte of an image.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ByteKind {
+    /// Not reached by tracing.
+    Unknown,
+
+    /// Part of an instruction reached by tracing.
+    Code,
+
+    /// Referenced as data by a traced instruction.
+    Data,
+}
+
+/// Recursive-traversal disassembler.
+///
+/// Starting from entry points, decodes instructions by following the control
+/// flow of the code: branch, `bsr` and `jsr` targets, and fall-through to the
+/// next instruction.  A path ends at `bra`, `jmp`, `rts`, `rte`, or
+/// `illegal`, or at a word that does not decode.  Memory referenced by
+/// PC-relative operands is marked as data.  Bytes never reached remain
+/// unknown.
+///
+#[derive(Clone, Debug)]
+pub struct Tracer {
+    dispatch: Dispatch,
+}
+
+/// The result of tracing an image: a map of code and data.
+#[derive(Clone, Debug)]
+pub struct CodeMap<'a> {
+    image: &'a [u8],
+    order: ByteOrder,
+    base:  u64,
+    kinds: Vec<ByteKind>,
+    insns: BTreeMap<u64, Instruction<'static>>,
+}
+
+impl Tracer {
+    /// Creates a tracer for the opcodes of the given table that are supported
+    /// by the given ISA profile.
+    pub fn new(opcodes: &'static [Opcode], isa: Flags) -> Self {
+        Tracer { dispatch: Dispatch::new(opcodes, isa) }
+    }
+
+    /// Traces the given image, loaded at address `base`, from the given
+    /// entry point addresses.
+    pub fn trace<'a>(&self,
+                     image:   &'a [u8],
+                     order:   ByteOrder,
+                     base:    u64,
+                     entries: &[u64])
+                    -> CodeMap<'a> {
+        let mut map = CodeMap {
+            image,
+            order,
+            base,
+            kinds: vec![ByteKind::Unknown; image.len()],
+            insns: BTreeMap::new(),
+        };
+
+        let mut r    = DecodeReader::new(SliceReader::new(image), order, base);
+        let mut work = entries.iter().rev().cloned().collect::<Vec<_>>();
+
+        while let Some(mut addr) = work.pop() {
+            loop {
+                // Stop at odd addresses, outside the image, and at code
+                // already traced
+                if addr & 1 != 0 { break; }
+                let pos = match map.index(addr) { Some(p) => p, None => break };
+                if map.kinds[pos] == ByteKind::Code { break; }
+
+                r.get_mut().seek(pos);
+                let i = match self.dispatch.decode(&mut r) {
+                    Ok(Some(i)) => i,
+                    _           => break,
+                };
+
+                let len = i.bytes.len();
+                map.mark(addr, len, ByteKind::Code, true);
+
+                let (target, next) = flow(&i);
+                if let Some(t) = target {
+                    work.push(t);
+                } else if let Some(d) = i.pc_ref() {
+                    map.mark(d, i.size.bytes().max(1) as usize, ByteKind::Data, false);
+                }
+
+                map.insns.insert(addr, i);
+
+                if !next { break; }
+                addr += len as u64;
+            }
+        }
+
+        map
+    }
+}
+
+impl<'a> CodeMap<'a> {
+    /// Returns the address at which the image is loaded.
+    #[inline]
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Returns the classification of the byte at the given address.  Bytes
+    /// outside the image are unknown.
+    pub fn kind(&self, addr: u64) -> ByteKind {
+        match self.index(addr) {
+            Some(pos) => self.kinds[pos],
+            None      => ByteKind::Unknown,
+        }
+    }
+
+    /// Returns the instruction traced at the given address, if any.
+    pub fn instruction(&self, addr: u64) -> Option<&Instruction<'static>> {
+        self.insns.get(&addr)
+    }
+
+    /// Returns the traced instructions, in address order.
+    pub fn instructions<'m>(&'m self) -> Values<'m, u64, Instruction<'static>> {
+        self.insns.values()
+    }
+
+    /// Returns the address ranges of the image, each with its classification,
+    /// in address order.  Adjacent bytes of the same kind form one range.
+    pub fn regions(&self) -> Vec<(Range<u64>, ByteKind)> {
+        let mut regions: Vec<(Range<u64>, ByteKind)> = Vec::new();
+
+        for (pos, &kind) in self.kinds.iter().enumerate() {
+            let addr = self.base + pos as u64;
+            match regions.last_mut() {
+                Some(&mut (ref mut range, k)) if k == kind => range.end = addr + 1,
+                _ => regions.push((addr..addr + 1, kind)),
+            }
+        }
+
+        regions
+    }
+
+    /// Returns the lines of a listing of the image.  Traced instructions are
+    /// disassembled; other bytes are shown as data words, or as single bytes
+    /// where a word would overlap an instruction or the end of the image.
+    pub fn lines(&self) -> Vec<Line<'static>> {
+        let mut r     = DecodeReader::new(SliceReader::new(self.image), self.order, self.base);
+        let mut lines = Vec::new();
+        let mut pos   = 0;
+
+        while pos < self.image.len() {
+            let addr = self.base + pos as u64;
+
+            if let Some(i) = self.insns.get(&addr) {
+                lines.push(Line {
+                    addr,
+                    bytes: i.bytes.clone(),
+                    item:  Item::Instruction(i.clone()),
+                });
+                pos += i.bytes.len();
+                continue;
+            }
+
+            r.get_mut().seek(pos);
+
+            let word = pos + 2 <= self.image.len()
+                    && !self.insns.contains_key(&(addr + 1));
+            let item = match word {
+                true  => Item::Short(r.read_u16().unwrap()),
+                false => Item::Byte (r.read_u8 ().unwrap()),
+            };
+
+            lines.push(Line { addr, bytes: r.pending_bytes().to_vec(), item });
+            pos += r.pending_len();
+        }
+
+        lines
+    }
+
+    /// Writes a listing of the image in the given code style to the given
+    /// output.  See `write_lines`.
+    pub fn write_listing<S, W>(&self, style: &S, out: &mut W) -> io::Result<()>
+    where S: Style<()> + ?Sized, W: Write {
+        write_lines(&self.lines(), style, out)
+    }
+
+    fn index(&self, addr: u64) -> Option<usize> {
+        match addr.checked_sub(self.base) {
+            Some(pos) if pos < self.image.len() as u64 => Some(pos as usize),
+            _                                          => None,
+        }
+    }
+
+    fn mark(&mut self, addr: u64, len: usize, kind: ByteKind, force: bool) {
+        if let Some(pos) = self.index(addr) {
+            let end = (pos + len).min(self.kinds.len());
+            for k in &mut self.kinds[pos..end] {
+                if force || *k == ByteKind::Unknown { *k = kind; }
+            }
+        }
+    }
+}
+
+/// Returns the code address to which an instruction can transfer control, if
+/// known, and whether execution can continue at the next instruction.
+fn flow(i: &Instruction) -> (Option<u64>, bool) {
+    match i.name {
+        Bra                 => (i.target(),     false),
+        Bcc | Bsr | Fbcc    => (i.target(),     true ),
+        Jmp                 => (jump_target(i), false),
+        Jsr                 => (jump_target(i), true ),
+        Rts | Rte | Illegal => (None,           false),
+        _                   => (None,           true ),
+    }
+}
+
+/// Returns the target address of a `jmp` or `jsr`, if statically known.
+fn jump_target(i: &Instruction) -> Option<u64> {
+    match i.operands.first() {
+        Some(&Operand::Mode(Mode::PcDisp(_))) => i.pc_ref(),
+        Some(&Operand::Mode(Mode::Abs16(Expr::Int(ref a)))) |
+        Some(&Operand::Mode(Mode::Abs32(Expr::Int(ref a)))) => {
+            a.value.to_i64().map(|a| a as u64 & 0xFFFF_FFFF)
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aex::fmt::AttStyle;
+    use aex::util::BE;
+    use super::*;
+    use super::super::*;
+
+    static IMAGE: [u8; 26] = [
+        0x61, 0x08,                     // 1000: bsr.s    100a
+        0x41, 0xFA, 0x00, 0x0E,         // 1002: lea.l    14(%pc),%a0   ; 1012
+        0x60, 0xFE,                     // 1006: bra.s    1006
+        0xFF, 0xFF,                     // 1008: (unreachable)
+        0x4A, 0x80,                     // 100a: tst.l    %d0
+        0x67, 0x02,                     // 100c: beq.s    1010
+        0x70, 0x01,                     // 100e: moveq.l  #1,%d0
+        0x4E, 0x75,                     // 1010: rts
+        0x12, 0x34, 0x56, 0x78,         // 1012: (data)
+        0x4E, 0x71,                     // 1016: (unreachable nop)
+        0x4E, 0x71,                     // 1018: (unreachable nop)
+    ];
+
+    #[test]
+    fn trace_kinds() {
+        let map = trace();
+
+        assert_eq!(map.kind(0x1000), ByteKind::Code);
+        assert_eq!(map.kind(0x1007), ByteKind::Code);
+        assert_eq!(map.kind(0x1008), ByteKind::Unknown);
+        assert_eq!(map.kind(0x100E), ByteKind::Code);
+        assert_eq!(map.kind(0x1011), ByteKind::Code);
+        assert_eq!(map.kind(0x1012), ByteKind::Data);
+        assert_eq!(map.kind(0x1015), ByteKind::Data);
+        assert_eq!(map.kind(0x1016), ByteKind::Unknown);
+        assert_eq!(map.kind(0x0FFF), ByteKind::Unknown);
+        assert_eq!(map.kind(0x2000), ByteKind::Unknown);
+    }
+
+    #[test]
+    fn trace_instructions() {
+        let map   = trace();
+        let addrs = map.instructions().map(|i| i.addr).collect::<Vec<_>>();
+
+        assert_eq!(addrs, vec![0x1000, 0x1002, 0x1006, 0x100A, 0x100C, 0x100E, 0x1010]);
+        assert_eq!(map.instruction(0x1010).unwrap().name, Mnemonic::Rts);
+        assert_eq!(map.instruction(0x1008), None);
+    }
+
+    #[test]
+    fn trace_regions() {
+        assert_eq!(trace().regions(), vec![
+            (0x1000..0x1008, ByteKind::Code),
+            (0x1008..0x100A, ByteKind::Unknown),
+            (0x100A..0x1012, ByteKind::Code),
+            (0x1012..0x1016, ByteKind::Data),
+            (0x1016..0x101A, ByteKind::Unknown),
+        ]);
+    }
+
+    #[test]
+    fn trace_jump_absolute() {
+        static IMAGE: [u8; 10] = [
+            0x4E, 0xF9, 0x00, 0x00, 0x10, 0x08, // 1000: jmp 1008.l
+            0xFF, 0xFF,                         // 1006: (unreachable)
+            0x4E, 0x75,                         // 1008: rts
+        ];
+
+        let t   = Tracer::new(OPCODES, CF_A);
+        let map = t.trace(&IMAGE, BE, 0x1000, &[0x1000]);
+
+        assert_eq!(map.kind(0x1006), ByteKind::Unknown);
+        assert_eq!(map.kind(0x1008), ByteKind::Code);
+    }
+
+    #[test]
+    fn trace_outside_image() {
+        static IMAGE: [u8; 4] = [
+            0x60, 0x10,                         // 1000: bra.s 1012
+            0x4E, 0x75,                         // 1002: rts
+        ];
+
+        let t   = Tracer::new(OPCODES, CF_A);
+        let map = t.trace(&IMAGE, BE, 0x1000, &[0x1000, 0x0FFE, 0x1001]);
+
+        assert_eq!(map.instructions().count(), 1);
+        assert_eq!(map.kind(0x1002), ByteKind::Unknown);
+    }
+
+    #[test]
+    fn write_listing() {
+        let mut out = Vec::new();
+        trace().write_listing(&AttStyle::default(), &mut out).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "\
+00001000:  6108            bsr.s sub_0000100a
+00001002:  41fa 000e       lea.l data_00001012(%pc),%a0
+L_00001006:
+00001006:  60fe            bra.s L_00001006
+00001008:  ffff            .short 0xffff
+sub_0000100a:
+0000100a:  4a80            tst.l %d0
+0000100c:  6702            beq.s L_00001010
+0000100e:  7001            moveq.l #1,%d0
+L_00001010:
+00001010:  4e75            rts
+data_00001012:
+00001012:  1234            .short 0x1234
+00001014:  5678            .short 0x5678
+00001016:  4e71            .short 0x4e71
+00001018:  4e71            .short 0x4e71
+");
+    }
+
+    fn trace() -> CodeMap<'static> {
+        Tracer::new(OPCODES, CF_A).trace(&IMAGE, BE, 0x1000, &[0x1000])
+    }
+}
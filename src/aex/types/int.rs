@@ -77,7 +77,7 @@ impl /*Contains<BigInt> for*/ IntSpec {
 }
 
 fn bit(n: u8) -> BigInt {
-    BigInt::from(1 << (n as u64))
+    BigInt::one() << n as usize
 }
 
 #[cfg(test)]
@@ -105,11 +105,19 @@ mod tests {
         assert_eq!( I8.max_value(), BigInt::from(127) );
     }
 
+    #[test]
+    fn min_max_value_32() {
+        let s = IntSpec { store_width: 32, value_width: 32, value_scale: 0, signed: true };
+        assert_eq!( s.min_value(), BigInt::from(-0x8000_0000i64) );
+        assert_eq!( s.max_value(), BigInt::from( 0x7FFF_FFFFi64) );
+    }
+
     #[test]
     fn bit() {
         assert_eq!( super::bit(0), BigInt::from(1 << 0) );
         assert_eq!( super::bit(1), BigInt::from(1 << 1) );
         assert_eq!( super::bit(7), BigInt::from(1 << 7) );
+        assert_eq!( super::bit(32), BigInt::from(1u64 << 32) );
     }
 
     #[test]
@@ -16,9 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with AEx.  If not, see <http://www.gnu.org/licenses/>.
 
-/*
 use std::io::{self, Error, ErrorKind};
-*/
 
 mod byte_order;
 
@@ -28,10 +26,9 @@ pub use self::byte_order::*;
 /// A bit position.
 pub type BitPos = u8;
 
-/*
+/// Returns an `InvalidData` error with the given message.
 #[inline]
-pub fn invalid<T>() -> io::Result<T> {
-    Err(Error::from(ErrorKind::InvalidData))
+pub fn invalid<T>(msg: &'static str) -> io::Result<T> {
+    Err(Error::new(ErrorKind::InvalidData, msg))
 }
-*/
 